@@ -0,0 +1,106 @@
+use tokio::runtime::{Builder, Runtime};
+
+use crate::config::Config;
+use crate::deepseek::{ChatMessage, DeepSeekClient, DeepSeekError, DeepSeekResponse};
+
+/// Synchronous wrapper around [`DeepSeekClient`] for code without an async runtime, such
+/// as build scripts. Each call blocks the current thread on a runtime owned by the client.
+///
+/// Do not use it from inside an async context: blocking on, or dropping, the runtime
+/// there panics. Async code should call [`DeepSeekClient`] directly.
+pub struct BlockingClient {
+    client: DeepSeekClient,
+    runtime: Runtime,
+}
+
+impl BlockingClient {
+    /// Create a blocking client from a validated configuration
+    pub fn new(config: Config) -> Result<Self, DeepSeekError> {
+        Self::from_client(DeepSeekClient::new(config)?)
+    }
+
+    /// Wrap an existing async client, e.g. one built with a custom transport
+    pub fn from_client(client: DeepSeekClient) -> Result<Self, DeepSeekError> {
+        let runtime = Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .map_err(|e| DeepSeekError::ConfigError {
+                message: format!("Failed to start the blocking runtime: {}", e),
+            })?;
+        Ok(Self { client, runtime })
+    }
+
+    /// The wrapped async client, for settings and statistics
+    pub fn client(&self) -> &DeepSeekClient {
+        &self.client
+    }
+
+    /// Blocking [`DeepSeekClient::send_request`]
+    pub fn send_request(&self, user_input: &str) -> Result<DeepSeekResponse, DeepSeekError> {
+        self.runtime.block_on(self.client.send_request(user_input))
+    }
+
+    /// Blocking [`DeepSeekClient::send_messages_raw`]
+    pub fn send_messages_raw(&self, messages: Vec<ChatMessage>) -> Result<String, DeepSeekError> {
+        self.runtime
+            .block_on(self.client.send_messages_raw(messages))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use wiremock::matchers::{method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    /// A mock API on its own runtime, so the test thread itself stays synchronous
+    fn mock_api(content: &str) -> (Runtime, MockServer) {
+        let runtime = Runtime::new().unwrap();
+        let server = runtime.block_on(MockServer::start());
+        let body = serde_json::json!({
+            "choices": [{ "message": { "role": "assistant", "content": content } }]
+        });
+        runtime.block_on(
+            Mock::given(method("POST"))
+                .and(path("/chat/completions"))
+                .respond_with(ResponseTemplate::new(200).set_body_json(body))
+                .mount(&server),
+        );
+        (runtime, server)
+    }
+
+    fn blocking_client(base_url: &str) -> BlockingClient {
+        BlockingClient::new(Config {
+            api_key: "test_key".to_string(),
+            base_url: base_url.to_string(),
+            timeout: 2,
+            ..Config::default()
+        })
+        .unwrap()
+    }
+
+    #[test]
+    fn send_request_blocks_until_the_response_is_parsed() {
+        let (_runtime, server) =
+            mock_api(r#"{"title":"T","description":"D","content":"C","confidence":0.5}"#);
+        let client = blocking_client(&server.uri());
+
+        let response = client.send_request("hello").unwrap();
+        assert_eq!(response.title, "T");
+        assert_eq!(response.confidence, Some(0.5));
+    }
+
+    #[test]
+    fn send_messages_raw_returns_the_content() {
+        let (_runtime, server) = mock_api(r#"{"n":1}"#);
+        let client = blocking_client(&server.uri());
+
+        let reply = client
+            .send_messages_raw(vec![ChatMessage {
+                role: "user".to_string(),
+                content: "hi".to_string(),
+            }])
+            .unwrap();
+        assert_eq!(reply, r#"{"n":1}"#);
+    }
+}
@@ -0,0 +1,146 @@
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+use std::time::{Duration, SystemTime};
+
+use crate::deepseek::ChatRequest;
+
+/// Opt-in disk cache of raw replies, one file per request in a cache directory.
+/// Entries older than the TTL are ignored and overwritten on the next miss.
+#[derive(Debug, Clone)]
+pub struct ResponseCache {
+    dir: PathBuf,
+    ttl: Duration,
+}
+
+impl ResponseCache {
+    pub fn new(dir: impl Into<PathBuf>, ttl: Duration) -> Self {
+        Self {
+            dir: dir.into(),
+            ttl,
+        }
+    }
+
+    /// Cache key for a request: a SHA-256 hex digest of the whole request body, so every
+    /// setting that shapes the reply is part of it, but not the per-call prompt timestamp
+    pub fn key(request: &ChatRequest) -> String {
+        request.digest()
+    }
+
+    /// Stored reply for `key`, unless it is missing, unreadable or expired
+    pub fn get(&self, key: &str) -> Option<String> {
+        let path = self.path(key);
+        let modified = fs::metadata(&path).and_then(|m| m.modified()).ok()?;
+        let age = SystemTime::now()
+            .duration_since(modified)
+            .unwrap_or_default();
+        if age > self.ttl {
+            return None;
+        }
+        fs::read_to_string(path).ok()
+    }
+
+    /// Store `content` as the reply for `key`, creating the cache directory if needed
+    pub fn put(&self, key: &str, content: &str) -> io::Result<()> {
+        fs::create_dir_all(&self.dir)?;
+        fs::write(self.path(key), content)
+    }
+
+    fn path(&self, key: &str) -> PathBuf {
+        self.dir.join(format!("{}.json", key))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::deepseek::{ChatMessage, build_json_prompt};
+
+    fn request(content: &str) -> ChatRequest {
+        ChatRequest {
+            model: "m".to_string(),
+            messages: vec![ChatMessage {
+                role: "user".to_string(),
+                content: content.to_string(),
+            }],
+            response_format: None,
+            max_tokens: 100,
+            temperature: 0.5,
+            top_p: None,
+            frequency_penalty: None,
+            presence_penalty: None,
+            stop: None,
+            tools: None,
+            n: None,
+        }
+    }
+
+    fn temp_cache_dir(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!("deepseek_cache_{}_{}", name, std::process::id()))
+    }
+
+    #[test]
+    fn key_depends_on_every_request_part() {
+        let base = request("hi");
+        let key = ResponseCache::key(&base);
+        assert_eq!(key.len(), 64);
+        assert_eq!(key, ResponseCache::key(&request("hi")));
+
+        let variants = [
+            ChatRequest {
+                model: "other".to_string(),
+                ..base.clone()
+            },
+            request("bye"),
+            ChatRequest {
+                temperature: 0.6,
+                ..base.clone()
+            },
+            ChatRequest {
+                max_tokens: 200,
+                ..base.clone()
+            },
+            ChatRequest {
+                top_p: Some(0.9),
+                ..base.clone()
+            },
+            ChatRequest {
+                stop: Some(vec!["END".to_string()]),
+                ..base.clone()
+            },
+            ChatRequest {
+                n: Some(2),
+                ..base.clone()
+            },
+        ];
+        for variant in &variants {
+            assert_ne!(key, ResponseCache::key(variant), "{:?}", variant);
+        }
+    }
+
+    #[test]
+    fn key_ignores_the_prompt_timestamp() {
+        let first = request(&build_json_prompt("hi", &[]));
+        std::thread::sleep(Duration::from_millis(2));
+        let second = request(&build_json_prompt("hi", &[]));
+        assert_ne!(first.messages[0].content, second.messages[0].content);
+        assert_eq!(ResponseCache::key(&first), ResponseCache::key(&second));
+    }
+
+    #[test]
+    fn get_returns_stored_reply_until_it_expires() -> io::Result<()> {
+        let dir = temp_cache_dir("ttl");
+        let cache = ResponseCache::new(&dir, Duration::from_secs(60));
+        assert_eq!(cache.get("k"), None);
+
+        cache.put("k", r#"{"ok":true}"#)?;
+        assert_eq!(cache.get("k").as_deref(), Some(r#"{"ok":true}"#));
+
+        let expired = ResponseCache::new(&dir, Duration::ZERO);
+        std::thread::sleep(Duration::from_millis(10));
+        assert_eq!(expired.get("k"), None);
+
+        fs::remove_dir_all(&dir)
+    }
+}
@@ -1,8 +1,31 @@
 use anyhow::{Context, Result};
-use clap::Parser;
+use clap::{Args, Parser, Subcommand};
+use futures::stream::{self, StreamExt};
+use serde::Serialize;
 use std::env;
+use std::io::{IsTerminal, Read};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tracing_subscriber::EnvFilter;
+use tracing_subscriber::prelude::*;
 
-use crate::{App, Config, DEFAULT_MAX_QUESTIONS};
+use crate::config::Mode;
+use crate::console::{
+    DEFAULT_OVERCONFIDENT_THRESHOLD, OVERCONFIDENT_NOTE, Theme, ThemeName, pretty_print_json,
+    replay_transcript, token_budget_line, write_export,
+};
+use crate::deepseek::{ChatMessage, REQUEST_LOG_TARGET, parse_response_with_aliases};
+use crate::metrics;
+use crate::output::{format_response, format_responses};
+use crate::{
+    AnswersPayload, App, Config, Console, ConsoleOptions, DEFAULT_MAX_QUESTIONS,
+    DEFAULT_MAX_ROUNDS, DeepSeekClient, DeepSeekError, DeepSeekResponse, END_TOKEN,
+    EmptyInputBehavior, OutputFormat, artifact_json_schema,
+};
+
+/// Roles accepted in a `--messages-file`
+const MESSAGE_ROLES: &[&str] = &["system", "user", "assistant"];
 
 /// Command line interface for the application
 #[derive(Parser, Debug)]
@@ -13,115 +36,1317 @@ use crate::{App, Config, DEFAULT_MAX_QUESTIONS};
     about = "A CLI tool for interacting with DeepSeek API and getting structured JSON responses"
 )]
 pub struct Cli {
-    /// Send a single query and exit (non-interactive mode)
-    #[arg(short, long)]
-    pub query: Option<String>,
+    #[command(subcommand)]
+    pub command: Option<Command>,
+
+    /// Override the default model (DEEPSEEK_MODEL, or deepseek-chat)
+    #[arg(short, long, global = true)]
+    pub model: Option<String>,
+
+    /// Set the temperature for response generation, 0.0-2.0 (DEEPSEEK_TEMPERATURE, or 0.7)
+    #[arg(short, long, global = true)]
+    pub temperature: Option<f32>,
+
+    /// Nucleus sampling: only consider tokens within this probability mass (0.0-1.0)
+    #[arg(long, global = true)]
+    pub top_p: Option<f32>,
+
+    /// Penalize tokens by how often they already appeared (-2.0-2.0)
+    #[arg(long, global = true, allow_hyphen_values = true)]
+    pub frequency_penalty: Option<f32>,
 
-    /// Override the default model
-    #[arg(short, long, default_value = "deepseek-chat")]
-    pub model: String,
+    /// Penalize tokens that already appeared at all (-2.0-2.0)
+    #[arg(long, global = true, allow_hyphen_values = true)]
+    pub presence_penalty: Option<f32>,
 
-    /// Set the temperature for response generation (0.0-2.0)
-    #[arg(short, long, default_value_t = 0.7)]
-    pub temperature: f32,
+    /// Stop generating at this sequence (repeatable)
+    #[arg(long = "stop", global = true, value_name = "SEQ")]
+    pub stop: Vec<String>,
 
-    /// Set the maximum number of tokens in the response
-    #[arg(long, default_value_t = 4096)]
-    pub max_tokens: u32,
+    /// Set the maximum number of tokens in the response (DEEPSEEK_MAX_TOKENS, or 4096)
+    #[arg(long, global = true)]
+    pub max_tokens: Option<u32>,
 
-    /// Request timeout in seconds
-    #[arg(long, default_value_t = 180)]
-    pub timeout: u64,
+    /// Settings preset: `extraction` forces temperature 0 and adds stricter JSON
+    /// instructions to the system prompt; `creative` keeps the configured settings
+    #[arg(long, global = true, value_enum, default_value_t = Mode::Creative)]
+    pub mode: Mode,
+
+    /// Request timeout in seconds (DEEPSEEK_TIMEOUT, or 180)
+    #[arg(long, global = true)]
+    pub timeout: Option<u64>,
 
     /// DeepSeek API base URL
-    #[arg(long)]
+    #[arg(long, global = true)]
     pub base_url: Option<String>,
 
-    /// Enable TaskFinisher-JSON mode
+    /// Custom system prompt for queries (JSON format instructions are still added)
+    #[arg(
+        long,
+        global = true,
+        value_name = "TEXT",
+        conflicts_with = "system_file"
+    )]
+    pub system: Option<String>,
+
+    /// Read the system prompt for queries from a file
+    #[arg(long, global = true, value_name = "PATH")]
+    pub system_file: Option<PathBuf>,
+
+    /// Keep retrying failed requests for at most this many seconds, backoffs included,
+    /// instead of a fixed number of attempts
+    #[arg(long, global = true, value_name = "SECONDS", value_parser = clap::value_parser!(u64).range(1..))]
+    pub retry_deadline: Option<u64>,
+
+    /// Show a line on stderr whenever a failed request is retried
+    #[arg(long, global = true, default_value_t = false)]
+    pub verbose: bool,
+
+    /// Require a confidence score in every answer; ask once more, then fail if it is missing
+    #[arg(long, global = true, default_value_t = false)]
+    pub require_confidence: bool,
+
+    /// Let answers leave out or empty this normally required field (repeatable)
+    #[arg(
+        long = "optional-field",
+        global = true,
+        value_name = "FIELD",
+        value_parser = ["title", "description", "content"]
+    )]
+    pub optional_fields: Vec<String>,
+
+    /// Reject queries matching this regular expression without calling the API (repeatable;
+    /// added to DEEPSEEK_INPUT_DENYLIST)
+    #[arg(long = "deny-input", global = true, value_name = "REGEX")]
+    pub deny_input: Vec<String>,
+
+    /// Read this reply field as one of the response fields, for backends with their own
+    /// naming, e.g. `summary=description` (repeatable)
+    #[arg(long = "field-alias", global = true, value_name = "NAME=FIELD", value_parser = parse_field_alias)]
+    pub field_aliases: Vec<(String, String)>,
+
+    /// Reuse replies to identical requests cached in this directory, and cache new ones
+    #[arg(long, global = true, value_name = "PATH")]
+    pub cache_dir: Option<PathBuf>,
+
+    /// Seconds a cached reply stays valid (default 86400)
+    #[arg(long, global = true, value_name = "SECS")]
+    pub cache_ttl: Option<u64>,
+
+    /// Always call the API, ignoring --cache-dir and DEEPSEEK_CACHE_DIR
+    #[arg(
+        long,
+        global = true,
+        default_value_t = false,
+        conflicts_with = "cache_dir"
+    )]
+    pub no_cache: bool,
+
+    /// Serve Prometheus metrics at http://127.0.0.1:PORT/metrics while running
+    #[arg(long, global = true, value_name = "PORT")]
+    pub metrics_port: Option<u16>,
+
+    /// Stop sending requests once the session has used this many tokens in total
+    #[arg(long, global = true, value_name = "N")]
+    pub token_budget: Option<u64>,
+
+    /// Validate the content of answers in CATEGORY against a JSON Schema file (repeatable)
+    #[arg(long, global = true, value_name = "CATEGORY=PATH", value_parser = parse_category_schema)]
+    pub category_schema: Vec<(String, PathBuf)>,
+
+    /// Load environment variables from this file instead of `.env` in the current directory
+    #[arg(long, global = true, value_name = "PATH")]
+    pub env_file: Option<PathBuf>,
+
+    /// Append a JSON log of every API request (model, tokens, latency, errors) to this file
+    #[arg(long, global = true, value_name = "PATH")]
+    pub log_file: Option<PathBuf>,
+
+    /// Export a span per API call to this OpenTelemetry collector (OTLP over HTTP),
+    /// e.g. http://localhost:4318
+    #[cfg(feature = "otlp")]
+    #[arg(long, global = true, value_name = "URL")]
+    pub otlp_endpoint: Option<String>,
+
+    /// Disable colored output (also honored via the NO_COLOR environment variable)
+    #[arg(long, global = true, default_value_t = false)]
+    pub no_color: bool,
+
+    /// Color theme for responses, artifacts and console headers
+    #[arg(long, global = true, value_enum, default_value_t = ThemeName::Dark)]
+    pub theme: ThemeName,
+
+    /// TOML file whose [theme] section overrides colors of the --theme preset
+    #[arg(long, global = true, value_name = "PATH")]
+    pub theme_file: Option<PathBuf>,
+
+    /// Print the JSON Schema of the TaskFinisher artifact and exit
+    #[arg(long, global = true, hide = true, default_value_t = false)]
+    pub print_schema: bool,
+}
+
+/// Modes of operation; without one, the interactive console starts (or a piped stdin is
+/// answered as a single query)
+#[derive(Subcommand, Debug)]
+pub enum Command {
+    /// Ask questions interactively in a console loop
+    Chat(ChatArgs),
+    /// Send a single query and exit
+    Query(QueryArgs),
+    /// Answer each line of a file as a separate query and output a JSON array of results
+    Batch(BatchArgs),
+    /// Collect requirements through clarifying questions into a technical task (TaskFinisher-JSON)
+    Task(TaskArgs),
+    /// Verify the API key and endpoint with a one-token "ping" request
+    Check,
+    /// Check that the endpoint returns OpenAI-compatible responses
+    Probe,
+    /// List the IDs of the models the endpoint offers, for use with --model
+    Models,
+    /// Show the answers saved in a `chat --transcript` file again, without calling the API
+    Replay(ReplayArgs),
+}
+
+/// Options of the modes that end with a run report
+#[derive(Args, Debug, Default)]
+pub struct ReportArgs {
+    /// Print a run report (requests, tokens, time, errors) to stderr when done
+    #[arg(long, default_value_t = false)]
+    pub summary: bool,
+
+    /// Print the --summary report as JSON; for `task`, also print the final artifact as plain
+    /// JSON on stdout with all status messages on stderr
+    #[arg(long, default_value_t = false)]
+    pub json: bool,
+}
+
+/// Options of the modes that show structured answers: `chat` and `query`
+#[derive(Args, Debug)]
+pub struct AnswerArgs {
+    /// Ask for N alternative answers per query and show them all (not used by chat --history)
+    #[arg(
+        long,
+        value_name = "N",
+        default_value_t = 1,
+        value_parser = clap::value_parser!(u32).range(1..)
+    )]
+    pub choices: u32,
+
+    /// Show the chain-of-thought of reasoning models such as deepseek-reasoner
+    /// (on stderr for `query`)
+    #[arg(long, default_value_t = false)]
+    pub show_reasoning: bool,
+
+    /// Flag answers whose confidence exceeds this threshold (0.0-1.0) as likely overconfident;
+    /// the console flags scores above 0.99 by default
+    #[arg(long, value_name = "THRESHOLD", value_parser = parse_threshold)]
+    pub warn_overconfident: Option<f32>,
+}
+
+impl Default for AnswerArgs {
+    fn default() -> Self {
+        Self {
+            choices: 1,
+            show_reasoning: false,
+            warn_overconfident: None,
+        }
+    }
+}
+
+/// Options of the interactive `chat` mode
+#[derive(Args, Debug, Default)]
+pub struct ChatArgs {
+    /// What to do on an empty line
+    #[arg(long, value_enum, default_value_t = EmptyInputBehavior::Ignore)]
+    pub on_empty: EmptyInputBehavior,
+
+    /// Keep conversation history across turns so follow-up questions have context
     #[arg(long, default_value_t = false)]
-    pub taskfinisher: bool,
+    pub history: bool,
+
+    /// With --history, remind the model of the JSON format every N turns
+    #[arg(long, value_name = "N", requires = "history", value_parser = clap::value_parser!(u32).range(1..))]
+    pub remind_every: Option<u32>,
+
+    /// Append each question and answer as JSON lines to this file
+    #[arg(long, value_name = "PATH")]
+    pub transcript: Option<PathBuf>,
+
+    #[command(flatten)]
+    pub answers: AnswerArgs,
+
+    #[command(flatten)]
+    pub report: ReportArgs,
+}
+
+/// Options of the `replay` mode
+#[derive(Args, Debug)]
+pub struct ReplayArgs {
+    /// Transcript file written by `chat --transcript`
+    #[arg(value_name = "PATH")]
+    pub file: PathBuf,
+
+    /// Flag answers whose confidence exceeds this threshold (0.0-1.0) as likely overconfident
+    /// instead of above 0.99
+    #[arg(long, value_name = "THRESHOLD", value_parser = parse_threshold)]
+    pub warn_overconfident: Option<f32>,
+}
+
+/// Options of the single-query `query` mode
+#[derive(Args, Debug, Default)]
+pub struct QueryArgs {
+    /// The question to send; read from piped stdin when omitted
+    pub text: Option<String>,
+
+    /// JSON Schema file the answer must follow instead of the default shape
+    #[arg(long, value_name = "PATH")]
+    pub schema: Option<PathBuf>,
+
+    /// Send this JSON array of {"role", "content"} messages verbatim instead of a question;
+    /// the reply is formatted when it parses as a structured response and printed raw otherwise
+    #[arg(long, value_name = "PATH", conflicts_with_all = ["text", "schema"])]
+    pub messages_file: Option<PathBuf>,
+
+    /// Print the model's reply exactly as received, without parsing it
+    #[arg(long, default_value_t = false, conflicts_with_all = ["schema", "output"])]
+    pub raw: bool,
+
+    /// Output format
+    #[arg(long, value_enum, default_value_t = OutputFormat::Json)]
+    pub output: OutputFormat,
+
+    /// Write the response to this file instead of stdout
+    #[arg(long, value_name = "PATH")]
+    pub out: Option<PathBuf>,
+
+    /// Print how long the request took (to stderr)
+    #[arg(long, default_value_t = false)]
+    pub timing: bool,
+
+    /// Print the request that would be sent (API key redacted) and exit without calling the API
+    #[arg(long, default_value_t = false)]
+    pub dry_run: bool,
+
+    /// Open the --out file with the system default application
+    #[arg(long, default_value_t = false)]
+    pub open: bool,
+
+    #[command(flatten)]
+    pub answers: AnswerArgs,
+
+    #[command(flatten)]
+    pub report: ReportArgs,
+}
+
+/// Options of the `batch` mode
+#[derive(Args, Debug)]
+pub struct BatchArgs {
+    /// File with one query per line
+    #[arg(value_name = "PATH")]
+    pub file: PathBuf,
+
+    /// Number of queries sent at the same time
+    #[arg(long, value_name = "N", default_value_t = 1, value_parser = clap::value_parser!(u32).range(1..))]
+    pub concurrency: u32,
+
+    /// Write the results to this file instead of stdout
+    #[arg(long, value_name = "PATH")]
+    pub out: Option<PathBuf>,
+
+    /// Open the --out file with the system default application
+    #[arg(long, default_value_t = false)]
+    pub open: bool,
+
+    #[command(flatten)]
+    pub report: ReportArgs,
+}
+
+/// Options of the TaskFinisher-JSON `task` mode
+#[derive(Args, Debug)]
+pub struct TaskArgs {
+    /// The task request; asked for interactively when omitted
+    pub prompt: Option<String>,
+
+    /// Model for TaskFinisher requests (defaults to --model)
+    #[arg(long, value_name = "MODEL")]
+    pub taskfinisher_model: Option<String>,
 
-    /// Maximum clarifying questions for TaskFinisher-JSON mode
+    /// Maximum clarifying questions
     #[arg(long, default_value_t = DEFAULT_MAX_QUESTIONS)]
     pub max_questions: u32,
+
+    /// Maximum rounds of clarifying questions before giving up on the artifact
+    #[arg(
+        long,
+        value_name = "N",
+        default_value_t = DEFAULT_MAX_ROUNDS,
+        value_parser = clap::value_parser!(u32).range(1..)
+    )]
+    pub max_rounds: u32,
+
+    /// Write the final artifact as Markdown to this file
+    #[arg(long, value_name = "PATH")]
+    pub export_md: Option<PathBuf>,
+
+    /// Write every clarifying question and answer of the session to this JSON file
+    #[arg(long, value_name = "PATH")]
+    pub question_log: Option<PathBuf>,
+
+    /// Write each round's checklist progress as a JSON line to this file (`-` for stdout)
+    #[arg(long, value_name = "PATH")]
+    pub checklist_log: Option<PathBuf>,
+
+    /// Sentinel the final artifact must carry in `end_token`, e.g. an ASCII `<<END>>`
+    #[arg(long, value_name = "TOKEN", default_value = END_TOKEN)]
+    pub end_token: String,
+
+    /// JSON file of clarifying answers (`{"answers":[{"id":..,"answer":..}]}`) to submit
+    /// without prompting; questions it does not cover are still asked
+    #[arg(long, value_name = "PATH")]
+    pub answers: Option<PathBuf>,
+
+    /// Also list checklist fields that are already complete
+    #[arg(long, default_value_t = false)]
+    pub show_complete: bool,
+
+    /// Save the conversation to this file after each round and resume from it
+    #[arg(long, value_name = "PATH")]
+    pub session_file: Option<PathBuf>,
+
+    /// Print the first request that would be sent (API key redacted) and exit without
+    /// calling the API
+    #[arg(long, default_value_t = false)]
+    pub dry_run: bool,
+
+    /// Open exported files with the system default application
+    #[arg(long, default_value_t = false)]
+    pub open: bool,
+
+    #[command(flatten)]
+    pub report: ReportArgs,
+}
+
+/// Whether to emit ANSI colors: off when requested via flag or a non-empty `NO_COLOR`,
+/// or when stdout is not a terminal
+fn color_enabled(no_color_flag: bool, no_color_env: Option<&str>, stdout_is_tty: bool) -> bool {
+    let no_color_env = no_color_env.is_some_and(|value| !value.is_empty());
+    !no_color_flag && !no_color_env && stdout_is_tty
 }
 
 /// Entry point for running the application via CLI
 pub async fn run_cli() -> Result<()> {
-    // Initialize logging
-    unsafe {
-        if env::var("RUST_LOG").is_err() {
-            env::set_var("RUST_LOG", "info");
-        }
+    if let Err(error) = run().await {
+        eprintln!("Error: {:#}", error);
+        std::process::exit(error_exit_code(&error));
     }
-    tracing_subscriber::fmt()
-        .with_env_filter(tracing_subscriber::EnvFilter::from_default_env())
-        .init();
-
-    // Load environment variables once at startup
-    dotenv::dotenv().ok();
+    Ok(())
+}
 
+/// Parse the command line and run the selected mode
+async fn run() -> Result<()> {
     // Parse command line arguments
     let cli = Cli::parse();
 
-    // Handle single query mode / taskfinisher mode / interactive
-    if cli.taskfinisher {
-        return handle_taskfinisher_mode(&cli).await;
+    if cli.print_schema {
+        let schema = serde_json::to_string_pretty(&artifact_json_schema())?;
+        println!("{}", schema);
+        return Ok(());
+    }
+
+    // Initialize logging; the guard flushes exported spans when `run` returns
+    let _logging = init_logging(&cli)?;
+
+    // Load environment variables once at startup
+    match &cli.env_file {
+        Some(path) => {
+            if let Err(e) = dotenv::from_path(path) {
+                tracing::warn!("Could not load env file {}: {}", path.display(), e);
+            }
+        }
+        None => {
+            dotenv::dotenv().ok();
+        }
     }
-    if let Some(query) = &cli.query {
-        return handle_single_query(query, &cli).await;
+
+    let no_color_env = env::var("NO_COLOR").ok();
+    if !color_enabled(
+        cli.no_color,
+        no_color_env.as_deref(),
+        std::io::stdout().is_terminal(),
+    ) {
+        colored::control::set_override(false);
     }
 
-    // Run in interactive mode
-    crate::run().await.context("Failed to run application")
+    if let Some(port) = cli.metrics_port {
+        metrics::spawn_server(port).await?;
+    }
+
+    match &cli.command {
+        Some(Command::Chat(args)) => handle_interactive_mode(&cli, args).await,
+        Some(Command::Query(args)) => handle_query_command(&cli, args).await,
+        Some(Command::Batch(args)) => handle_batch(&cli, args).await,
+        Some(Command::Task(args)) => handle_taskfinisher_mode(&cli, args).await,
+        Some(Command::Check) => handle_check(&cli).await,
+        Some(Command::Probe) => handle_probe(&cli).await,
+        Some(Command::Models) => handle_models(&cli).await,
+        Some(Command::Replay(args)) => handle_replay(&cli, args),
+        // Without a subcommand, answer a piped stdin or start the console
+        None if !std::io::stdin().is_terminal() => {
+            handle_query_command(&cli, &QueryArgs::default()).await
+        }
+        None => handle_interactive_mode(&cli, &ChatArgs::default()).await,
+    }
 }
 
-/// Handle a single query in non-interactive mode
-async fn handle_single_query(query: &str, cli: &Cli) -> Result<()> {
-    // Create configuration with CLI overrides
-    let mut config = Config::load().context("Failed to load configuration")?;
+/// Run the `query` subcommand with its text, or with piped stdin when the text is omitted
+async fn handle_query_command(cli: &Cli, args: &QueryArgs) -> Result<()> {
+    if let Some(path) = &args.messages_file {
+        return handle_messages_query(path, cli, args).await;
+    }
+    match &args.text {
+        Some(query) => handle_single_query(query, cli, args).await,
+        None => handle_single_query(&read_piped_query()?, cli, args).await,
+    }
+}
+
+/// Read the whole of a piped stdin as the query, e.g. `echo "summarize this" | deepseek_json`
+fn read_piped_query() -> Result<String> {
+    let mut input = String::new();
+    std::io::stdin()
+        .read_to_string(&mut input)
+        .context("Failed to read query from stdin")?;
+    let query = input.trim();
+    if query.is_empty() {
+        anyhow::bail!("No query given: stdin is empty and no query text was passed");
+    }
+    Ok(query.to_string())
+}
+
+/// Log to the terminal as configured by `RUST_LOG` (default `info`), and additionally write
+/// the per-request audit log as JSON lines to `log_file` when given. With the `otlp` feature,
+/// API call spans are also exported to `otlp_endpoint` when given.
+fn init_logging(cli: &Cli) -> Result<LoggingGuard> {
+    // The request log is for the file only unless RUST_LOG asks for it explicitly
+    let terminal_filter = EnvFilter::try_from_default_env()
+        .unwrap_or_else(|_| EnvFilter::new(format!("info,{}=off", REQUEST_LOG_TARGET)));
 
-    // Apply CLI overrides
-    config.model = cli.model.clone();
-    config.temperature = cli.temperature;
-    config.max_tokens = cli.max_tokens;
-    config.timeout = cli.timeout;
+    let file_layer = match cli.log_file.as_deref() {
+        Some(path) => {
+            let file = std::fs::OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(path)
+                .with_context(|| format!("Failed to open log file {}", path.display()))?;
+            let layer = tracing_subscriber::fmt::layer()
+                .json()
+                .with_writer(Mutex::new(file))
+                .with_filter(EnvFilter::new("deepseek_json=info"));
+            Some(layer)
+        }
+        None => None,
+    };
+
+    #[cfg(feature = "otlp")]
+    let (otlp_layer, guard) = match &cli.otlp_endpoint {
+        Some(endpoint) => {
+            let (layer, otlp) = crate::telemetry::otlp_layer(endpoint)?;
+            (Some(layer), LoggingGuard { _otlp: Some(otlp) })
+        }
+        None => (None, LoggingGuard::default()),
+    };
+    #[cfg(not(feature = "otlp"))]
+    let (otlp_layer, guard) = (
+        None::<tracing_subscriber::layer::Identity>,
+        LoggingGuard::default(),
+    );
+
+    tracing_subscriber::registry()
+        .with(tracing_subscriber::fmt::layer().with_filter(terminal_filter))
+        .with(file_layer)
+        .with(otlp_layer)
+        .init();
+    Ok(guard)
+}
+
+/// Keeps logging exporters alive for the rest of the run
+#[derive(Default)]
+struct LoggingGuard {
+    #[cfg(feature = "otlp")]
+    _otlp: Option<crate::telemetry::OtlpGuard>,
+}
+
+/// Process exit code for a failed run: the mapped code of the first [`DeepSeekError`]
+/// in the error chain, or 1 for any other failure
+fn error_exit_code(error: &anyhow::Error) -> i32 {
+    error
+        .chain()
+        .find_map(|cause| cause.downcast_ref::<DeepSeekError>())
+        .map_or(1, exit_code)
+}
+
+/// Process exit code for a [`DeepSeekError`], so scripts can branch on the failure type
+pub fn exit_code(err: &DeepSeekError) -> i32 {
+    match err {
+        DeepSeekError::ConfigError { .. } => 2,
+        DeepSeekError::NetworkError { .. } => 3,
+        DeepSeekError::Timeout { .. } => 4,
+        DeepSeekError::ApiError { .. } | DeepSeekError::ServerBusy { .. } => 5,
+        DeepSeekError::ParseError { .. }
+        | DeepSeekError::EmptyResponse
+        | DeepSeekError::Truncated { .. } => 6,
+        DeepSeekError::InputRejected { .. } => 7,
+        DeepSeekError::BudgetExceeded { .. } => 8,
+    }
+}
+
+/// Load configuration from the environment, reporting failures as config errors
+fn load_env_config() -> Result<Config> {
+    Config::load()
+        .map_err(|e| DeepSeekError::ConfigError {
+            message: format!("{:#}", e),
+        })
+        .context("Failed to load configuration")
+}
+
+/// Handle the interactive console loop
+async fn handle_interactive_mode(cli: &Cli, args: &ChatArgs) -> Result<()> {
+    let config = load_config(cli)?;
+    let options = ConsoleOptions {
+        empty_input: args.on_empty,
+        chat: args.history,
+        remind_every: args.remind_every,
+        transcript: args.transcript.clone(),
+        show_reasoning: args.answers.show_reasoning,
+        choices: Some(args.answers.choices),
+        theme: load_theme(cli)?,
+        warn_overconfident: args.answers.warn_overconfident,
+        ..ConsoleOptions::default()
+    };
+
+    let started = Instant::now();
+    let app = App::with_options(config, options)?;
+    let result = app.run().await.context("Failed to run application");
+    report_summary(&app, &args.report, started, result)
+}
+
+/// Print the `--summary` run report and, with `--token-budget`, the tokens spent to stderr,
+/// then pass the mode's result through
+fn report_summary(
+    app: &App,
+    report: &ReportArgs,
+    started: Instant,
+    result: Result<()>,
+) -> Result<()> {
+    if let Some(budget) = app.client().token_budget() {
+        eprintln!("{}", token_budget_line(app.client().tokens_used(), budget));
+    }
+    if report.summary {
+        let summary = app.session_summary(started.elapsed());
+        if report.json {
+            eprintln!("{}", serde_json::to_string_pretty(&summary)?);
+        } else {
+            eprint!("{}", summary.to_text());
+        }
+    }
+    result
+}
+
+/// Load configuration from the environment and apply CLI overrides
+fn load_config(cli: &Cli) -> Result<Config> {
+    let mut config = load_env_config()?;
+    apply_cli_overrides(&mut config, cli)?;
+    Ok(config)
+}
+
+/// Apply the global CLI flags on top of `config`
+fn apply_cli_overrides(config: &mut Config, cli: &Cli) -> Result<()> {
+    // Flags left unset keep the values from the environment
+    if let Some(model) = &cli.model {
+        config.model = model.clone();
+    }
+    if let Some(temperature) = cli.temperature {
+        config.temperature = temperature;
+    }
+    config.mode = cli.mode;
+    if let Some(max_tokens) = cli.max_tokens {
+        config.max_tokens = max_tokens;
+    }
+    if let Some(timeout) = cli.timeout {
+        config.timeout = timeout;
+    }
+    config.top_p = cli.top_p;
+    config.frequency_penalty = cli.frequency_penalty;
+    config.presence_penalty = cli.presence_penalty;
+    config.stop_sequences = cli.stop.clone();
+    config.require_confidence = cli.require_confidence;
+    config.verbose = cli.verbose;
+    if let Some(deadline) = cli.retry_deadline {
+        config.retry_deadline = Some(deadline);
+    }
+    config.optional_fields = cli.optional_fields.clone();
+    config.input_denylist.extend(cli.deny_input.iter().cloned());
+    config
+        .field_aliases
+        .extend(cli.field_aliases.iter().cloned());
+    config.token_budget = cli.token_budget;
 
     if let Some(base_url) = &cli.base_url {
         config.base_url = base_url.clone();
     }
 
+    if cli.no_cache {
+        config.cache_dir = None;
+    } else if let Some(dir) = &cli.cache_dir {
+        config.cache_dir = Some(dir.clone());
+    }
+    if let Some(ttl) = cli.cache_ttl {
+        config.cache_ttl = ttl;
+    }
+
+    if let Some(system) = &cli.system {
+        config.system_prompt = Some(system.clone());
+    } else if let Some(path) = &cli.system_file {
+        let system = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read system prompt from {}", path.display()))?;
+        config.system_prompt = Some(system.trim().to_string());
+    }
+
+    for (category, path) in &cli.category_schema {
+        config
+            .category_schemas
+            .insert(category.clone(), read_schema(path)?);
+    }
+
+    Ok(())
+}
+
+/// The `--theme` preset, with the colors from `--theme-file` applied on top
+fn load_theme(cli: &Cli) -> Result<Theme> {
+    let theme = Theme::named(cli.theme);
+    match &cli.theme_file {
+        Some(path) => Theme::load(path, theme),
+        None => Ok(theme),
+    }
+}
+
+/// Parse a `--warn-overconfident` threshold between 0.0 and 1.0
+fn parse_threshold(value: &str) -> Result<f32, String> {
+    match value.parse::<f32>() {
+        Ok(threshold) if (0.0..=1.0).contains(&threshold) => Ok(threshold),
+        _ => Err(format!(
+            "expected a number between 0.0 and 1.0, got '{}'",
+            value
+        )),
+    }
+}
+
+/// Parse a `NAME=FIELD` pair for `--field-alias`
+fn parse_field_alias(value: &str) -> Result<(String, String), String> {
+    match value.split_once('=') {
+        Some((name, field)) if !name.trim().is_empty() && !field.trim().is_empty() => {
+            Ok((name.trim().to_string(), field.trim().to_string()))
+        }
+        _ => Err(format!("expected NAME=FIELD, got '{}'", value)),
+    }
+}
+
+/// Parse a `CATEGORY=PATH` pair for `--category-schema`
+fn parse_category_schema(value: &str) -> Result<(String, PathBuf), String> {
+    match value.split_once('=') {
+        Some((category, path)) if !category.trim().is_empty() && !path.is_empty() => {
+            Ok((category.trim().to_string(), PathBuf::from(path)))
+        }
+        _ => Err(format!("expected CATEGORY=PATH, got '{}'", value)),
+    }
+}
+
+/// Read and parse a JSON Schema file
+fn read_schema(path: &Path) -> Result<serde_json::Value> {
+    let schema_text = std::fs::read_to_string(path)
+        .with_context(|| format!("Failed to read schema from {}", path.display()))?;
+    serde_json::from_str(&schema_text)
+        .with_context(|| format!("Schema file {} is not valid JSON", path.display()))
+}
+
+/// Read the `--answers` file of pre-supplied TaskFinisher answers
+fn read_answers(path: &Path) -> Result<AnswersPayload> {
+    let text = std::fs::read_to_string(path)
+        .with_context(|| format!("Failed to read answers from {}", path.display()))?;
+    serde_json::from_str(&text).with_context(|| {
+        format!(
+            "Answers file {} is not a valid answers payload",
+            path.display()
+        )
+    })
+}
+
+/// Send a minimal request to verify the API key and endpoint before a big run
+async fn handle_check(cli: &Cli) -> Result<()> {
+    let app = App::with_config(load_config(cli)?)?;
+    let client = app.client();
+
+    let result = client.check().await;
+    Console::display_check_result(
+        &client.model(),
+        client.base_url(),
+        &result,
+        &load_theme(cli)?,
+    );
+    result.map(|_| ()).context("API check failed")
+}
+
+/// Probe the configured endpoint for OpenAI-compatible responses
+async fn handle_probe(cli: &Cli) -> Result<()> {
+    let app = App::with_config(load_config(cli)?)?;
+
+    let report = app
+        .client()
+        .probe_compatibility()
+        .await
+        .context("Compatibility probe failed")?;
+    Console::display_compatibility_report(&report, &load_theme(cli)?);
+
+    if !report.is_compatible() {
+        anyhow::bail!("Gateway at {} is not OpenAI-compatible", report.endpoint);
+    }
+    Ok(())
+}
+
+/// Print the model IDs the endpoint offers, one per line
+async fn handle_models(cli: &Cli) -> Result<()> {
+    let app = App::with_config(load_config(cli)?)?;
+
+    let models = app
+        .client()
+        .list_models()
+        .await
+        .context("Failed to list models")?;
+    for model in models {
+        println!("{}", model);
+    }
+    Ok(())
+}
+
+/// Re-render a saved transcript; needs no API key
+fn handle_replay(cli: &Cli, args: &ReplayArgs) -> Result<()> {
+    let threshold = args
+        .warn_overconfident
+        .unwrap_or(DEFAULT_OVERCONFIDENT_THRESHOLD);
+    let shown = replay_transcript(&args.file, &load_theme(cli)?, threshold)?;
+    if shown == 0 {
+        anyhow::bail!("Transcript {} is empty", args.file.display());
+    }
+    Ok(())
+}
+
+/// Handle a single query in non-interactive mode
+async fn handle_single_query(query: &str, cli: &Cli, args: &QueryArgs) -> Result<()> {
+    let started = Instant::now();
+    let config = load_config(cli)?;
     let app = App::with_config(config)?;
 
+    if args.dry_run {
+        let client = app.client();
+        let messages = match &args.schema {
+            Some(path) => client.schema_messages(query, &read_schema(path)?),
+            None => client.structured_messages(query),
+        };
+        return print_request_preview(app.client(), &messages, &app.client().model());
+    }
+
+    let result = match &args.schema {
+        Some(path) => handle_schema_query(&app, query, path, args).await,
+        None => send_single_query(&app, query, args).await,
+    };
+    report_summary(&app, &args.report, started, result)
+}
+
+/// Send the conversation in a messages file as is, bypassing the structured prompt
+async fn handle_messages_query(path: &Path, cli: &Cli, args: &QueryArgs) -> Result<()> {
+    let started = Instant::now();
+    let messages = read_messages(path)?;
+    let app = App::with_config(load_config(cli)?)?;
+
+    if args.dry_run {
+        return print_request_preview(app.client(), &messages, &app.client().model());
+    }
+
+    let result = send_messages_query(&app, messages, args).await;
+    report_summary(&app, &args.report, started, result)
+}
+
+async fn send_messages_query(
+    app: &App,
+    messages: Vec<ChatMessage>,
+    args: &QueryArgs,
+) -> Result<()> {
+    let client = app.client();
+    let (raw, meta) = client
+        .send_messages_raw_with_meta(messages)
+        .await
+        .context("Failed to process messages")?;
+
+    let text =
+        match parse_response_with_aliases(&raw, client.optional_fields(), client.field_aliases()) {
+            Ok(response) if !args.raw => format_response(&response, args.output)?,
+            _ => raw,
+        };
+    emit_output(&text, args.out.as_deref(), args.open)?;
+    report_timing(args, meta.latency);
+    Ok(())
+}
+
+/// Read a JSON array of chat messages, each with a `system`, `user` or `assistant` role
+fn read_messages(path: &Path) -> Result<Vec<ChatMessage>> {
+    let text = std::fs::read_to_string(path)
+        .with_context(|| format!("Failed to read messages from {}", path.display()))?;
+    let messages: Vec<ChatMessage> = serde_json::from_str(&text).with_context(|| {
+        format!(
+            "Messages file {} is not a JSON array of {{\"role\", \"content\"}} objects",
+            path.display()
+        )
+    })?;
+    validate_messages(&messages)
+        .with_context(|| format!("Invalid messages file {}", path.display()))?;
+    Ok(messages)
+}
+
+fn validate_messages(messages: &[ChatMessage]) -> Result<()> {
+    if messages.is_empty() {
+        anyhow::bail!("expected at least one message");
+    }
+    for (index, message) in messages.iter().enumerate() {
+        if !MESSAGE_ROLES.contains(&message.role.as_str()) {
+            anyhow::bail!(
+                "message {} has role '{}' (expected one of: {})",
+                index + 1,
+                message.role,
+                MESSAGE_ROLES.join(", ")
+            );
+        }
+    }
+    Ok(())
+}
+
+/// Print the request `--dry-run` would have sent, as pretty JSON with the API key redacted
+fn print_request_preview(
+    client: &DeepSeekClient,
+    messages: &[ChatMessage],
+    model: &str,
+) -> Result<()> {
+    let preview = client.preview_request(messages, model);
+    println!("{}", serde_json::to_string_pretty(&preview)?);
+    Ok(())
+}
+
+/// Send a single query and print the response in the requested format
+async fn send_single_query(app: &App, query: &str, args: &QueryArgs) -> Result<()> {
+    if args.raw {
+        let started = Instant::now();
+        let raw = app
+            .send_request_raw(query)
+            .await
+            .context("Failed to process query")?;
+        // Piped or saved output stays exactly as received
+        let raw = if args.out.is_none() && std::io::stdout().is_terminal() {
+            pretty_print_json(&raw)
+        } else {
+            raw
+        };
+        emit_output(&raw, args.out.as_deref(), args.open)?;
+        report_timing(args, started.elapsed());
+        return Ok(());
+    }
+
+    if args.answers.choices > 1 {
+        let (responses, meta) = app
+            .client()
+            .send_request_multi(query, args.answers.choices)
+            .await
+            .context("Failed to process query")?;
+        emit_output(
+            &format_responses(&responses, args.output)?,
+            args.out.as_deref(),
+            args.open,
+        )?;
+        report_timing(args, meta.latency);
+        return Ok(());
+    }
+
     // Send the request
-    let response = app
-        .send_request(query)
+    let (response, meta) = app
+        .client()
+        .send_request_with_meta(query)
         .await
-        .map_err(|e| anyhow::anyhow!("Failed to process query: {}", e))?;
+        .context("Failed to process query")?;
 
-    // Display the response in a clean format
-    println!(
-        "{}",
-        serde_json::to_string_pretty(&response).context("Failed to serialize response")?
-    );
+    if args.answers.show_reasoning
+        && let Some(reasoning) = &meta.reasoning
+    {
+        eprintln!("🧠 Reasoning:\n{}\n", reasoning.trim());
+    }
 
+    // Only on request here, so that scripted output on stderr stays unchanged by default
+    if let (Some(threshold), Some(confidence)) =
+        (args.answers.warn_overconfident, response.confidence)
+        && confidence > threshold
+    {
+        eprintln!("Confidence {:.2}: {}", confidence, OVERCONFIDENT_NOTE);
+    }
+
+    // Display or save the response in the requested format
+    emit_output(
+        &format_response(&response, args.output)?,
+        args.out.as_deref(),
+        args.open,
+    )?;
+    report_timing(args, meta.latency);
     Ok(())
 }
 
+/// Print the request latency to stderr when `--timing` is set, keeping stdout parseable
+fn report_timing(args: &QueryArgs, latency: Duration) {
+    if args.timing {
+        eprintln!("⏱ {:.1}s", latency.as_secs_f64());
+    }
+}
+
+/// Print single-query or batch output, or write it to the `--out` file when given
+fn emit_output(text: &str, out: Option<&Path>, open_after: bool) -> Result<()> {
+    match out {
+        Some(path) => write_export(path, text, "response", open_after),
+        None => {
+            println!("{}", text);
+            Ok(())
+        }
+    }
+}
+
+/// Handle a single query whose answer must follow a user-supplied JSON Schema
+async fn handle_schema_query(
+    app: &App,
+    query: &str,
+    schema_path: &Path,
+    args: &QueryArgs,
+) -> Result<()> {
+    let schema = read_schema(schema_path)?;
+
+    let value = app
+        .client()
+        .send_request_schema(query, &schema)
+        .await
+        .context("Failed to process query")?;
+
+    emit_output(
+        &serde_json::to_string_pretty(&value)?,
+        args.out.as_deref(),
+        args.open,
+    )
+}
+
+/// Result for one line of a batch file: the answer, or the error for that input
+#[derive(Debug, Serialize)]
+#[serde(untagged)]
+enum BatchEntry {
+    Answer(DeepSeekResponse),
+    Failure { input: String, error: String },
+}
+
+/// Queries in a batch file: one per non-blank line
+fn batch_queries(text: &str) -> Vec<&str> {
+    text.lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+        .collect()
+}
+
+/// Answer every query of a batch file, up to `--concurrency` at a time, and output the
+/// results as a JSON array in input order. Failed queries do not abort the batch.
+async fn handle_batch(cli: &Cli, args: &BatchArgs) -> Result<()> {
+    let started = Instant::now();
+    let text = std::fs::read_to_string(&args.file)
+        .with_context(|| format!("Failed to read batch file {}", args.file.display()))?;
+    let app = App::with_config(load_config(cli)?)?;
+    let client = app.client();
+
+    let entries: Vec<BatchEntry> = stream::iter(batch_queries(&text))
+        .map(|query| async move {
+            match client.send_request(query).await {
+                Ok(response) => BatchEntry::Answer(response),
+                Err(e) => BatchEntry::Failure {
+                    input: query.to_string(),
+                    error: e.to_string(),
+                },
+            }
+        })
+        .buffered(args.concurrency as usize)
+        .collect()
+        .await;
+
+    let result = serde_json::to_string_pretty(&entries)
+        .context("Failed to serialize batch results")
+        .and_then(|json| emit_output(&json, args.out.as_deref(), args.open));
+    report_summary(&app, &args.report, started, result)
+}
+
 /// Handle TaskFinisher-JSON mode
-async fn handle_taskfinisher_mode(cli: &Cli) -> Result<()> {
-    let mut config = Config::load().context("Failed to load configuration")?;
-    config.model = cli.model.clone();
-    config.temperature = cli.temperature;
-    config.max_tokens = cli.max_tokens;
-    config.timeout = cli.timeout;
-    if let Some(base_url) = &cli.base_url {
-        config.base_url = base_url.clone();
+async fn handle_taskfinisher_mode(cli: &Cli, args: &TaskArgs) -> Result<()> {
+    let mut config = load_config(cli)?;
+    if let Some(model) = &args.taskfinisher_model {
+        config.taskfinisher_model = Some(model.clone());
     }
+    let options = ConsoleOptions {
+        export_md: args.export_md.clone(),
+        session_file: args.session_file.clone(),
+        question_log: args.question_log.clone(),
+        checklist_log: args.checklist_log.clone(),
+        show_complete: args.show_complete,
+        end_token: Some(args.end_token.clone()),
+        json: args.report.json,
+        answers: args.answers.as_deref().map(read_answers).transpose()?,
+        open_exports: args.open,
+        theme: load_theme(cli)?,
+        ..ConsoleOptions::default()
+    };
+    let started = Instant::now();
+    let app = App::with_options(config, options)?;
 
-    let app = App::with_config(config)?;
+    if args.dry_run {
+        let user_prompt = match &args.prompt {
+            Some(prompt) => prompt.clone(),
+            None => Console::prompt_user("💬 Enter your technical task request: ").await?,
+        };
+        let max_questions = match args.max_questions {
+            0 => DEFAULT_MAX_QUESTIONS,
+            n => n,
+        };
+        let messages = Console::initial_history(max_questions, &args.end_token, &user_prompt);
+        let client = app.client();
+        return print_request_preview(client, &messages, &client.taskfinisher_model());
+    }
 
-    let initial_prompt = cli.query.as_deref();
-    app.run_taskfinisher(initial_prompt, cli.max_questions)
+    let initial_prompt = args.prompt.as_deref();
+    let result = app
+        .run_taskfinisher(initial_prompt, args.max_questions, args.max_rounds)
         .await
+        .map(|_| ());
+    report_summary(&app, &args.report, started, result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn subcommands_take_their_own_flags_and_globals_anywhere() {
+        use clap::CommandFactory;
+        Cli::command().debug_assert();
+
+        let cli = Cli::try_parse_from([
+            "deepseek-json",
+            "task",
+            "Build a price cache",
+            "--max-questions",
+            "2",
+            "--model",
+            "deepseek-reasoner",
+        ])
+        .unwrap();
+        assert_eq!(cli.model.as_deref(), Some("deepseek-reasoner"));
+        match cli.command {
+            Some(Command::Task(args)) => {
+                assert_eq!(args.prompt.as_deref(), Some("Build a price cache"));
+                assert_eq!(args.max_questions, 2);
+            }
+            other => panic!("expected task, got {:?}", other),
+        }
+
+        let cli =
+            Cli::try_parse_from(["deepseek-json", "-t", "0.2", "query", "What is Rust?"]).unwrap();
+        assert_eq!(cli.temperature, Some(0.2));
+        assert!(matches!(
+            cli.command,
+            Some(Command::Query(QueryArgs { text: Some(_), .. }))
+        ));
+
+        // TaskFinisher options are meaningless outside `task` and are rejected there
+        assert!(
+            Cli::try_parse_from(["deepseek-json", "query", "x", "--max-questions", "2"]).is_err()
+        );
+        assert!(Cli::try_parse_from(["deepseek-json", "--max-questions", "2"]).is_err());
+    }
+
+    #[test]
+    fn chat_applies_global_flags_to_its_config() {
+        let cli = Cli::try_parse_from([
+            "deepseek-json",
+            "chat",
+            "--verbose",
+            "--retry-deadline",
+            "30",
+            "--mode",
+            "extraction",
+            "--model",
+            "deepseek-reasoner",
+            "--temperature",
+            "0.2",
+            "--max-tokens",
+            "512",
+            "--base-url",
+            "http://localhost:8080",
+            "--cache-dir",
+            "cache",
+            "--optional-field",
+            "title",
+            "--field-alias",
+            "summary=description",
+            "--deny-input",
+            "secret",
+        ])
+        .unwrap();
+        assert!(matches!(cli.command, Some(Command::Chat(_))));
+
+        let mut config = Config::default();
+        apply_cli_overrides(&mut config, &cli).unwrap();
+        assert!(config.verbose);
+        assert_eq!(config.retry_deadline, Some(30));
+        assert_eq!(config.mode, Mode::Extraction);
+        assert_eq!(config.model, "deepseek-reasoner");
+        assert_eq!(config.temperature, 0.2);
+        assert_eq!(config.max_tokens, 512);
+        assert_eq!(config.base_url, "http://localhost:8080");
+        assert_eq!(config.cache_dir, Some(PathBuf::from("cache")));
+        assert_eq!(config.optional_fields, ["title"]);
+        assert_eq!(
+            config.field_aliases.get("summary").map(String::as_str),
+            Some("description")
+        );
+        assert_eq!(config.input_denylist, ["secret"]);
+    }
+
+    #[test]
+    fn environment_settings_survive_when_flags_are_omitted() {
+        let _guard = crate::config::lock_env();
+        unsafe {
+            std::env::set_var("DEEPSEEK_API_KEY", "test_key");
+            std::env::set_var("DEEPSEEK_MODEL", "deepseek-reasoner");
+            std::env::set_var("DEEPSEEK_TEMPERATURE", "0.3");
+        }
+
+        let cli = Cli::try_parse_from(["deepseek-json", "chat"]).unwrap();
+        let config = load_config(&cli);
+        let cli = Cli::try_parse_from(["deepseek-json", "chat", "-m", "flag-model"]).unwrap();
+        let overridden = load_config(&cli);
+
+        unsafe {
+            std::env::remove_var("DEEPSEEK_API_KEY");
+            std::env::remove_var("DEEPSEEK_MODEL");
+            std::env::remove_var("DEEPSEEK_TEMPERATURE");
+        }
+        let config = config.unwrap();
+        assert_eq!(config.model, "deepseek-reasoner");
+        assert_eq!(config.temperature, 0.3);
+        assert_eq!(overridden.unwrap().model, "flag-model");
+    }
+
+    #[test]
+    fn max_rounds_defaults_to_five_and_must_be_positive() {
+        let cli = Cli::try_parse_from(["deepseek-json", "task", "x"]).unwrap();
+        match cli.command {
+            Some(Command::Task(args)) => assert_eq!(args.max_rounds, DEFAULT_MAX_ROUNDS),
+            other => panic!("expected task, got {:?}", other),
+        }
+        assert!(Cli::try_parse_from(["deepseek-json", "task", "x", "--max-rounds", "0"]).is_err());
+    }
+
+    #[test]
+    fn warn_overconfident_takes_a_threshold_between_zero_and_one() {
+        let cli =
+            Cli::try_parse_from(["deepseek-json", "query", "q", "--warn-overconfident", "0.9"])
+                .unwrap();
+        match cli.command {
+            Some(Command::Query(args)) => assert_eq!(args.answers.warn_overconfident, Some(0.9)),
+            other => panic!("expected query, got {:?}", other),
+        }
+
+        for bad in ["1.5", "-0.1", "high"] {
+            assert!(
+                Cli::try_parse_from(["deepseek-json", "query", "q", "--warn-overconfident", bad])
+                    .is_err(),
+                "{} should be rejected",
+                bad
+            );
+        }
+    }
+
+    #[test]
+    fn mode_specific_flags_are_rejected_by_other_subcommands() {
+        for accepted in [
+            &["query", "q", "--dry-run"][..],
+            &["task", "t", "--dry-run"],
+            &["chat", "--choices", "2", "--show-reasoning", "--summary"],
+            &["batch", "queries.txt", "--open", "--summary", "--json"],
+        ] {
+            let argv = std::iter::once("deepseek-json").chain(accepted.iter().copied());
+            assert!(Cli::try_parse_from(argv).is_ok(), "{:?}", accepted);
+        }
+
+        // --dry-run never reaches a mode that would call the API anyway
+        for rejected in [
+            &["batch", "queries.txt", "--dry-run"][..],
+            &["chat", "--dry-run"],
+            &["check", "--dry-run"],
+            &["probe", "--dry-run"],
+            &["models", "--dry-run"],
+            &["--dry-run", "query", "q"],
+            &["batch", "queries.txt", "--choices", "2"],
+            &["batch", "queries.txt", "--show-reasoning"],
+            &["batch", "queries.txt", "--warn-overconfident", "0.9"],
+            &["check", "--summary"],
+        ] {
+            let argv = std::iter::once("deepseek-json").chain(rejected.iter().copied());
+            assert!(Cli::try_parse_from(argv).is_err(), "{:?}", rejected);
+        }
+    }
+    #[test]
+    fn validate_messages_accepts_only_known_roles() {
+        let message = |role: &str| ChatMessage {
+            role: role.to_string(),
+            content: "hi".to_string(),
+        };
+        assert!(
+            validate_messages(&[message("system"), message("user"), message("assistant")]).is_ok()
+        );
+        assert!(validate_messages(&[]).is_err());
+
+        let err = validate_messages(&[message("user"), message("tool")]).unwrap_err();
+        assert!(
+            err.to_string().contains("message 2 has role 'tool'"),
+            "unexpected error: {}",
+            err
+        );
+    }
+
+    #[test]
+    fn batch_queries_skip_blank_lines() {
+        let text = "What is Rust?\n\n   \n  Who wrote it?  \r\n";
+        assert_eq!(batch_queries(text), vec!["What is Rust?", "Who wrote it?"]);
+    }
+
+    #[test]
+    fn exit_code_is_found_through_context() {
+        let error = anyhow::Error::new(DeepSeekError::Timeout { seconds: 5 })
+            .context("Failed to process query");
+        assert_eq!(error_exit_code(&error), 4);
+        assert_eq!(error_exit_code(&anyhow::anyhow!("other")), 1);
+    }
+
+    #[test]
+    fn color_enabled_respects_flag_env_and_tty() {
+        assert!(color_enabled(false, None, true));
+        assert!(!color_enabled(true, None, true));
+        assert!(!color_enabled(false, Some("1"), true));
+        assert!(color_enabled(false, Some(""), true));
+        assert!(!color_enabled(false, None, false));
+    }
 }
@@ -1,34 +1,227 @@
 use anyhow::{Context, Result};
+use std::collections::HashMap;
 use std::env;
+use std::fmt;
+use std::path::PathBuf;
+use std::str::FromStr;
+
+use crate::deepseek::{REQUIRED_RESPONSE_FIELDS, RESPONSE_FIELDS};
 
 const DEFAULT_BASE_URL: &str = "https://api.deepseek.com";
 const DEFAULT_MODEL: &str = "deepseek-chat";
 const DEFAULT_MAX_TOKENS: u32 = 4096;
 const DEFAULT_TEMPERATURE: f32 = 0.7;
 const DEFAULT_TIMEOUT: u64 = 180;
+const DEFAULT_RETRY_MAX_ATTEMPTS: u32 = 3;
+const DEFAULT_RETRY_BASE_BACKOFF_MS: u64 = 500;
+const DEFAULT_RETRY_ON_500: bool = true;
+const DEFAULT_CACHE_TTL: u64 = 24 * 60 * 60;
+/// `User-Agent` sent when `DEEPSEEK_USER_AGENT` is not set
+pub const DEFAULT_USER_AGENT: &str = concat!("deepseek_json/", env!("CARGO_PKG_VERSION"));
+
+/// Path of the chat completions endpoint when `DEEPSEEK_CHAT_PATH` is not set
+pub const DEFAULT_CHAT_PATH: &str = "/chat/completions";
+
+/// Header carrying the API key for [`AuthStyle::ApiKeyHeader`]
+pub const API_KEY_HEADER: &str = "api-key";
+
+/// Query parameter carrying the API key for [`AuthStyle::QueryParam`]
+pub const API_KEY_QUERY_PARAM: &str = "api-key";
+
+/// Placeholder shown instead of the API key in debug output
+const REDACTED: &str = "***";
+
+/// Environment variables with this prefix become extra HTTP headers
+const HEADER_ENV_PREFIX: &str = "DEEPSEEK_HEADER_";
+
+/// How the API key is attached to requests
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum AuthStyle {
+    /// `Authorization: Bearer <key>`, as DeepSeek and OpenAI expect
+    #[default]
+    Bearer,
+    /// An `api-key: <key>` header, as Azure OpenAI expects
+    ApiKeyHeader,
+    /// An `api-key=<key>` query parameter, for gateways that read the key from the URL
+    QueryParam,
+}
+
+impl FromStr for AuthStyle {
+    type Err = anyhow::Error;
+
+    /// Parse `bearer`, `api-key-header` or `query-param`; case and `_`/`-` are ignored
+    fn from_str(value: &str) -> Result<Self> {
+        match value.trim().to_ascii_lowercase().replace('_', "-").as_str() {
+            "bearer" => Ok(Self::Bearer),
+            "api-key-header" => Ok(Self::ApiKeyHeader),
+            "query-param" => Ok(Self::QueryParam),
+            _ => anyhow::bail!(
+                "Unknown auth style '{}' (expected bearer, api-key-header or query-param)",
+                value
+            ),
+        }
+    }
+}
+
+/// Preset of request settings for a kind of use
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum Mode {
+    /// The configured temperature and system prompt
+    #[default]
+    Creative,
+    /// Temperature 0 and stricter JSON instructions, for reliable data extraction
+    Extraction,
+}
+
+impl Mode {
+    /// Sampling temperature of requests in this mode, given the configured one
+    pub fn temperature(self, configured: f32) -> f32 {
+        match self {
+            Mode::Creative => configured,
+            Mode::Extraction => 0.0,
+        }
+    }
+}
 
 /// Configuration structure for the DeepSeek client
-#[derive(Debug, Clone)]
+#[derive(Clone)]
 pub struct Config {
     pub api_key: String,
+    /// How `api_key` is sent; gateways such as Azure OpenAI do not accept a bearer token
+    pub auth_style: AuthStyle,
     pub base_url: String,
+    /// Path appended to `base_url` for chat completions, e.g. `/v1/chat/completions`;
+    /// `None` uses [`DEFAULT_CHAT_PATH`]
+    pub chat_path: Option<String>,
     pub model: String,
+    /// Model for TaskFinisher requests; falls back to `model` when unset
+    pub taskfinisher_model: Option<String>,
     pub max_tokens: u32,
     pub temperature: f32,
+    /// Settings preset; `Extraction` overrides `temperature` and tightens the system prompt
+    pub mode: Mode,
     pub timeout: u64,
+    /// Seconds allowed for establishing the connection, separate from the total `timeout`;
+    /// `None` leaves it to the total timeout
+    pub connect_timeout: Option<u64>,
+    /// Nucleus sampling cutoff (0.0-1.0); omitted from requests when unset
+    pub top_p: Option<f32>,
+    /// Penalty for frequently repeated tokens (-2.0-2.0); omitted from requests when unset
+    pub frequency_penalty: Option<f32>,
+    /// Penalty for tokens already present in the text (-2.0-2.0); omitted from requests when unset
+    pub presence_penalty: Option<f32>,
+    /// Total attempts per request, including the first one (1 disables retrying)
+    pub retry_max_attempts: u32,
+    /// Seconds a request may spend retrying, backoffs included; when set, it replaces
+    /// `retry_max_attempts` as the limit
+    pub retry_deadline: Option<u64>,
+    /// Delay before the first retry; doubled after each failed attempt
+    pub retry_base_backoff_ms: u64,
+    /// Retry HTTP 500 responses like other transient server errors
+    pub retry_on_500: bool,
+    /// Print a notice to stderr before each retry, independent of the tracing log level
+    pub verbose: bool,
+    /// Send identical concurrent requests only once, sharing the response between callers
+    pub dedup_requests: bool,
+    /// Maximum HTTP requests in flight at once across the client and its clones;
+    /// `None` means unlimited
+    pub max_concurrent_requests: Option<usize>,
+    /// Directory for caching raw replies to identical requests; `None` disables the cache
+    pub cache_dir: Option<PathBuf>,
+    /// Seconds a cached reply stays valid
+    pub cache_ttl: u64,
+    /// `User-Agent` header for all API requests; `None` uses [`DEFAULT_USER_AGENT`]
+    pub user_agent: Option<String>,
+    /// HTTP(S) or SOCKS5 proxy for all API requests, e.g. `http://proxy:8080`
+    pub proxy_url: Option<String>,
+    /// Extra HTTP headers sent with every request, e.g. tenant routing headers for a gateway
+    pub extra_headers: HashMap<String, String>,
+    /// Sequences at which the model stops generating; omitted from requests when empty
+    pub stop_sequences: Vec<String>,
+    /// Regex patterns; user input matching any of them is rejected without calling the API
+    pub input_denylist: Vec<String>,
+    /// System prompt for structured queries; the JSON format instructions are still appended
+    /// to the user message. `None` uses the built-in prompt.
+    pub system_prompt: Option<String>,
+    /// Treat a response without a confidence score as an error, after one corrective retry
+    pub require_confidence: bool,
+    /// Normally required `DeepSeekResponse` fields (`title`, `description`, `content`) the
+    /// model may leave out or empty
+    pub optional_fields: Vec<String>,
+    /// Reply field names read as `DeepSeekResponse` fields, alias to field (e.g. `summary`
+    /// to `description`), for backends with their own naming
+    pub field_aliases: HashMap<String, String>,
+    /// Maximum total tokens a session may consume; further requests are refused once reached
+    pub token_budget: Option<u64>,
+    /// JSON Schemas for the `content` field, keyed by category (matched case-insensitively).
+    /// A mismatch triggers one corrective retry; categories without a schema are not checked.
+    pub category_schemas: HashMap<String, serde_json::Value>,
+}
+
+// Written by hand so the API key never ends up in logs or error output
+impl fmt::Debug for Config {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Config")
+            .field("api_key", &REDACTED)
+            .field("auth_style", &self.auth_style)
+            .field("base_url", &self.base_url)
+            .field("chat_path", &self.chat_path)
+            .field("model", &self.model)
+            .field("taskfinisher_model", &self.taskfinisher_model)
+            .field("max_tokens", &self.max_tokens)
+            .field("temperature", &self.temperature)
+            .field("mode", &self.mode)
+            .field("timeout", &self.timeout)
+            .field("connect_timeout", &self.connect_timeout)
+            .field("top_p", &self.top_p)
+            .field("frequency_penalty", &self.frequency_penalty)
+            .field("presence_penalty", &self.presence_penalty)
+            .field("retry_max_attempts", &self.retry_max_attempts)
+            .field("retry_deadline", &self.retry_deadline)
+            .field("retry_base_backoff_ms", &self.retry_base_backoff_ms)
+            .field("retry_on_500", &self.retry_on_500)
+            .field("verbose", &self.verbose)
+            .field("dedup_requests", &self.dedup_requests)
+            .field("max_concurrent_requests", &self.max_concurrent_requests)
+            .field("cache_dir", &self.cache_dir)
+            .field("cache_ttl", &self.cache_ttl)
+            .field("user_agent", &self.user_agent)
+            .field("proxy_url", &self.proxy_url)
+            .field("extra_headers", &self.extra_headers)
+            .field("stop_sequences", &self.stop_sequences)
+            .field("input_denylist", &self.input_denylist)
+            .field("system_prompt", &self.system_prompt)
+            .field("require_confidence", &self.require_confidence)
+            .field("optional_fields", &self.optional_fields)
+            .field("field_aliases", &self.field_aliases)
+            .field("token_budget", &self.token_budget)
+            .field("category_schemas", &self.category_schemas)
+            .finish()
+    }
 }
 
 impl Config {
     /// Load configuration from environment variables
     pub fn load() -> Result<Self> {
-        let api_key = env::var("DEEPSEEK_API_KEY")
-            .context("DEEPSEEK_API_KEY environment variable not set")?;
+        let api_key = load_api_key()?;
+
+        let auth_style = env::var("DEEPSEEK_AUTH_STYLE")
+            .ok()
+            .filter(|style| !style.trim().is_empty())
+            .map(|style| style.parse::<AuthStyle>())
+            .transpose()
+            .context("DEEPSEEK_AUTH_STYLE is invalid")?
+            .unwrap_or_default();
 
         let base_url =
             env::var("DEEPSEEK_BASE_URL").unwrap_or_else(|_| DEFAULT_BASE_URL.to_string());
 
         let model = env::var("DEEPSEEK_MODEL").unwrap_or_else(|_| DEFAULT_MODEL.to_string());
 
+        let taskfinisher_model = env::var("DEEPSEEK_TASKFINISHER_MODEL")
+            .ok()
+            .filter(|m| !m.trim().is_empty());
+
         let max_tokens = env::var("DEEPSEEK_MAX_TOKENS")
             .unwrap_or_else(|_| DEFAULT_MAX_TOKENS.to_string())
             .parse::<u32>()
@@ -44,13 +237,112 @@ impl Config {
             .parse::<u64>()
             .context("DEEPSEEK_TIMEOUT must be a valid number")?;
 
+        let connect_timeout = env::var("DEEPSEEK_CONNECT_TIMEOUT")
+            .ok()
+            .map(|value| value.parse::<u64>())
+            .transpose()
+            .context("DEEPSEEK_CONNECT_TIMEOUT must be a valid number")?;
+
+        let retry_max_attempts = env::var("DEEPSEEK_RETRY_ATTEMPTS")
+            .unwrap_or_else(|_| DEFAULT_RETRY_MAX_ATTEMPTS.to_string())
+            .parse::<u32>()
+            .context("DEEPSEEK_RETRY_ATTEMPTS must be a valid number")?;
+
+        let retry_deadline = env::var("DEEPSEEK_RETRY_DEADLINE")
+            .ok()
+            .map(|value| value.parse::<u64>())
+            .transpose()
+            .context("DEEPSEEK_RETRY_DEADLINE must be a valid number")?;
+
+        let retry_base_backoff_ms = env::var("DEEPSEEK_RETRY_BACKOFF_MS")
+            .unwrap_or_else(|_| DEFAULT_RETRY_BASE_BACKOFF_MS.to_string())
+            .parse::<u64>()
+            .context("DEEPSEEK_RETRY_BACKOFF_MS must be a valid number")?;
+
+        let retry_on_500 = env::var("DEEPSEEK_RETRY_ON_500")
+            .unwrap_or_else(|_| DEFAULT_RETRY_ON_500.to_string())
+            .parse::<bool>()
+            .context("DEEPSEEK_RETRY_ON_500 must be true or false")?;
+
+        let dedup_requests = env::var("DEEPSEEK_DEDUP_REQUESTS")
+            .unwrap_or_else(|_| "false".to_string())
+            .parse::<bool>()
+            .context("DEEPSEEK_DEDUP_REQUESTS must be true or false")?;
+
+        let max_concurrent_requests = env::var("DEEPSEEK_MAX_CONCURRENT_REQUESTS")
+            .ok()
+            .map(|value| value.parse::<usize>())
+            .transpose()
+            .context("DEEPSEEK_MAX_CONCURRENT_REQUESTS must be a valid number")?;
+
+        let extra_headers = headers_from_env(env::vars());
+
+        let cache_dir = env::var("DEEPSEEK_CACHE_DIR")
+            .ok()
+            .filter(|dir| !dir.trim().is_empty())
+            .map(PathBuf::from);
+
+        let cache_ttl = env::var("DEEPSEEK_CACHE_TTL")
+            .unwrap_or_else(|_| DEFAULT_CACHE_TTL.to_string())
+            .parse::<u64>()
+            .context("DEEPSEEK_CACHE_TTL must be a valid number")?;
+
+        let user_agent = env::var("DEEPSEEK_USER_AGENT").ok();
+
+        let chat_path = env::var("DEEPSEEK_CHAT_PATH")
+            .ok()
+            .filter(|path| !path.trim().is_empty());
+
+        let proxy_url = env::var("DEEPSEEK_PROXY")
+            .ok()
+            .filter(|url| !url.trim().is_empty());
+
+        let input_denylist = env::var("DEEPSEEK_INPUT_DENYLIST")
+            .map(|patterns| {
+                patterns
+                    .split(',')
+                    .map(str::trim)
+                    .filter(|pattern| !pattern.is_empty())
+                    .map(String::from)
+                    .collect()
+            })
+            .unwrap_or_default();
+
         Ok(Self {
             api_key,
+            auth_style,
             base_url,
+            chat_path,
             model,
+            taskfinisher_model,
             max_tokens,
             temperature,
+            mode: Mode::default(),
             timeout,
+            connect_timeout,
+            top_p: None,
+            frequency_penalty: None,
+            presence_penalty: None,
+            retry_max_attempts,
+            retry_deadline,
+            retry_base_backoff_ms,
+            retry_on_500,
+            verbose: false,
+            dedup_requests,
+            max_concurrent_requests,
+            cache_dir,
+            cache_ttl,
+            user_agent,
+            proxy_url,
+            extra_headers,
+            stop_sequences: Vec::new(),
+            input_denylist,
+            system_prompt: None,
+            require_confidence: false,
+            optional_fields: Vec::new(),
+            field_aliases: HashMap::new(),
+            token_budget: None,
+            category_schemas: HashMap::new(),
         })
     }
 
@@ -60,18 +352,99 @@ impl Config {
             anyhow::bail!("API key cannot be empty");
         }
 
+        validate_base_url(&self.base_url)?;
+
+        if let Some(chat_path) = &self.chat_path
+            && !chat_path.starts_with('/')
+        {
+            anyhow::bail!("Chat path '{}' must start with '/'", chat_path);
+        }
+
         if self.temperature < 0.0 || self.temperature > 2.0 {
             anyhow::bail!("Temperature must be between 0.0 and 2.0");
         }
 
+        if let Some(top_p) = self.top_p
+            && !(0.0..=1.0).contains(&top_p)
+        {
+            anyhow::bail!("Top-p must be between 0.0 and 1.0");
+        }
+
+        for (name, penalty) in [
+            ("Frequency penalty", self.frequency_penalty),
+            ("Presence penalty", self.presence_penalty),
+        ] {
+            if let Some(penalty) = penalty
+                && !(-2.0..=2.0).contains(&penalty)
+            {
+                anyhow::bail!("{} must be between -2.0 and 2.0", name);
+            }
+        }
+
         if self.max_tokens == 0 {
             anyhow::bail!("Max tokens must be greater than 0");
         }
 
+        if self.max_concurrent_requests == Some(0) {
+            anyhow::bail!("Max concurrent requests must be greater than 0");
+        }
+
         if self.timeout == 0 {
             anyhow::bail!("Timeout must be greater than 0");
         }
 
+        if let Some(user_agent) = &self.user_agent
+            && (user_agent.trim().is_empty()
+                || reqwest::header::HeaderValue::from_str(user_agent).is_err())
+        {
+            anyhow::bail!("User agent must be a non-empty, valid HTTP header value");
+        }
+
+        if self.cache_ttl == 0 {
+            anyhow::bail!("Cache TTL must be greater than 0");
+        }
+
+        if self.connect_timeout == Some(0) {
+            anyhow::bail!("Connect timeout must be greater than 0");
+        }
+
+        if self.retry_max_attempts == 0 {
+            anyhow::bail!("Retry attempts must be at least 1");
+        }
+
+        if self.retry_deadline == Some(0) {
+            anyhow::bail!("Retry deadline must be greater than 0");
+        }
+
+        if let Some(field) = self
+            .optional_fields
+            .iter()
+            .find(|field| !REQUIRED_RESPONSE_FIELDS.contains(&field.as_str()))
+        {
+            anyhow::bail!(
+                "Unknown optional field '{}' (expected one of: {})",
+                field,
+                REQUIRED_RESPONSE_FIELDS.join(", ")
+            );
+        }
+
+        for (alias, field) in &self.field_aliases {
+            if !RESPONSE_FIELDS.contains(&field.as_str()) {
+                anyhow::bail!(
+                    "Field alias '{}' maps to unknown field '{}' (expected one of: {})",
+                    alias,
+                    field,
+                    RESPONSE_FIELDS.join(", ")
+                );
+            }
+            if alias.trim().is_empty() || RESPONSE_FIELDS.contains(&alias.as_str()) {
+                anyhow::bail!(
+                    "Field alias '{}' must be a name other than the response fields",
+                    alias
+                );
+            }
+        }
+
         Ok(())
     }
 }
@@ -80,38 +453,145 @@ impl Default for Config {
     fn default() -> Self {
         Self {
             api_key: String::new(),
+            auth_style: AuthStyle::default(),
             base_url: DEFAULT_BASE_URL.to_string(),
+            chat_path: None,
             model: DEFAULT_MODEL.to_string(),
+            taskfinisher_model: None,
             max_tokens: DEFAULT_MAX_TOKENS,
             temperature: DEFAULT_TEMPERATURE,
+            mode: Mode::default(),
             timeout: DEFAULT_TIMEOUT,
+            connect_timeout: None,
+            top_p: None,
+            frequency_penalty: None,
+            presence_penalty: None,
+            retry_max_attempts: DEFAULT_RETRY_MAX_ATTEMPTS,
+            retry_deadline: None,
+            retry_base_backoff_ms: DEFAULT_RETRY_BASE_BACKOFF_MS,
+            retry_on_500: DEFAULT_RETRY_ON_500,
+            verbose: false,
+            dedup_requests: false,
+            max_concurrent_requests: None,
+            cache_dir: None,
+            cache_ttl: DEFAULT_CACHE_TTL,
+            user_agent: None,
+            proxy_url: None,
+            extra_headers: HashMap::new(),
+            stop_sequences: Vec::new(),
+            input_denylist: Vec::new(),
+            system_prompt: None,
+            require_confidence: false,
+            optional_fields: Vec::new(),
+            field_aliases: HashMap::new(),
+            token_budget: None,
+            category_schemas: HashMap::new(),
         }
     }
 }
 
+/// Read the API key from `DEEPSEEK_API_KEY`, or else from the file named by
+/// `DEEPSEEK_API_KEY_FILE` (as used by Docker secrets and systemd credentials)
+fn load_api_key() -> Result<String> {
+    // A blank variable, e.g. `DEEPSEEK_API_KEY=` left in a .env file, counts as unset
+    if let Ok(api_key) = env::var("DEEPSEEK_API_KEY")
+        && !api_key.trim().is_empty()
+    {
+        return Ok(api_key);
+    }
+
+    let Ok(path) = env::var("DEEPSEEK_API_KEY_FILE") else {
+        anyhow::bail!(
+            "DEEPSEEK_API_KEY environment variable not set (set it, or DEEPSEEK_API_KEY_FILE to a file containing the key)"
+        );
+    };
+    let api_key = std::fs::read_to_string(&path).with_context(|| {
+        format!(
+            "Failed to read API key from DEEPSEEK_API_KEY_FILE ({})",
+            path
+        )
+    })?;
+    let api_key = api_key.trim();
+    if api_key.is_empty() {
+        anyhow::bail!("API key file {} (DEEPSEEK_API_KEY_FILE) is empty", path);
+    }
+    Ok(api_key.to_string())
+}
+
+/// Check that `base_url` is an absolute `http`/`https` URL without the chat completions
+/// path, which the client appends itself
+fn validate_base_url(base_url: &str) -> Result<()> {
+    let url = url::Url::parse(base_url)
+        .with_context(|| format!("Base URL '{}' is not a valid URL", base_url))?;
+    if !matches!(url.scheme(), "http" | "https") {
+        anyhow::bail!(
+            "Base URL '{}' must use http or https, not '{}'",
+            base_url,
+            url.scheme()
+        );
+    }
+    if url
+        .path()
+        .trim_end_matches('/')
+        .ends_with("/chat/completions")
+    {
+        anyhow::bail!(
+            "Base URL '{}' must not include /chat/completions; it is appended to every request",
+            base_url
+        );
+    }
+    Ok(())
+}
+
+/// Collect `DEEPSEEK_HEADER_<NAME>=value` variables as headers; underscores in the name
+/// become dashes, so `DEEPSEEK_HEADER_X_ORG_ID` maps to `x-org-id`
+fn headers_from_env(vars: impl Iterator<Item = (String, String)>) -> HashMap<String, String> {
+    vars.filter_map(|(key, value)| {
+        let name = key.strip_prefix(HEADER_ENV_PREFIX)?;
+        (!name.is_empty()).then(|| (name.replace('_', "-").to_ascii_lowercase(), value))
+    })
+    .collect()
+}
+
+/// Serialize tests that mutate process env vars, across modules
+#[cfg(test)]
+pub(crate) fn lock_env() -> std::sync::MutexGuard<'static, ()> {
+    static ENV_LOCK: std::sync::OnceLock<std::sync::Mutex<()>> = std::sync::OnceLock::new();
+    ENV_LOCK
+        .get_or_init(|| std::sync::Mutex::new(()))
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use std::env;
-    use std::sync::{Mutex, OnceLock};
-
-    // Serialize tests that mutate process env vars
-    static ENV_LOCK: OnceLock<Mutex<()>> = OnceLock::new();
-
-    fn lock_env() -> std::sync::MutexGuard<'static, ()> {
-        ENV_LOCK
-            .get_or_init(|| Mutex::new(()))
-            .lock()
-            .expect("env mutex poisoned")
-    }
 
     const ENV_KEYS: &[&str] = &[
         "DEEPSEEK_API_KEY",
+        "DEEPSEEK_API_KEY_FILE",
         "DEEPSEEK_BASE_URL",
         "DEEPSEEK_MODEL",
+        "DEEPSEEK_TASKFINISHER_MODEL",
         "DEEPSEEK_MAX_TOKENS",
         "DEEPSEEK_TEMPERATURE",
         "DEEPSEEK_TIMEOUT",
+        "DEEPSEEK_RETRY_ATTEMPTS",
+        "DEEPSEEK_RETRY_BACKOFF_MS",
+        "DEEPSEEK_RETRY_ON_500",
+        "DEEPSEEK_RETRY_DEADLINE",
+        "DEEPSEEK_MAX_CONCURRENT_REQUESTS",
+        "DEEPSEEK_DEDUP_REQUESTS",
+        "DEEPSEEK_CONNECT_TIMEOUT",
+        "DEEPSEEK_USER_AGENT",
+        "DEEPSEEK_CHAT_PATH",
+        "DEEPSEEK_AUTH_STYLE",
+        "DEEPSEEK_CACHE_DIR",
+        "DEEPSEEK_CACHE_TTL",
+        "DEEPSEEK_PROXY",
+        "DEEPSEEK_INPUT_DENYLIST",
+        "DEEPSEEK_HEADER_X_ORG_ID",
     ];
 
     fn clear_env() {
@@ -122,6 +602,57 @@ mod tests {
         }
     }
 
+    #[test]
+    fn debug_output_redacts_api_key() {
+        let config = Config {
+            api_key: "sk-very-secret".to_string(),
+            ..Config::default()
+        };
+        let debug = format!("{:?}", config);
+        assert!(!debug.contains("sk-very-secret"));
+        assert!(debug.contains(r#"api_key: "***""#));
+        assert!(debug.contains("base_url"));
+    }
+
+    #[test]
+    fn load_api_key_from_file() -> Result<()> {
+        let _guard = lock_env();
+        clear_env();
+        let path = env::temp_dir().join(format!("deepseek_key_{}", std::process::id()));
+        std::fs::write(&path, "  file_key\n")?;
+        unsafe {
+            env::set_var("DEEPSEEK_API_KEY_FILE", &path);
+        }
+        assert_eq!(Config::load()?.api_key, "file_key");
+
+        // The direct variable wins over the file
+        unsafe {
+            env::set_var("DEEPSEEK_API_KEY", "env_key");
+        }
+        assert_eq!(Config::load()?.api_key, "env_key");
+
+        // A blank direct variable falls back to the file
+        unsafe {
+            env::set_var("DEEPSEEK_API_KEY", "  ");
+        }
+        assert_eq!(Config::load()?.api_key, "file_key");
+
+        unsafe {
+            env::remove_var("DEEPSEEK_API_KEY");
+        }
+        std::fs::write(&path, "\n")?;
+        let err = Config::load().unwrap_err();
+        assert!(
+            err.to_string().contains("is empty"),
+            "unexpected error: {err}"
+        );
+
+        std::fs::remove_file(&path)?;
+        let err = Config::load().unwrap_err();
+        assert!(err.to_string().contains("DEEPSEEK_API_KEY_FILE"));
+        Ok(())
+    }
+
     #[test]
     fn load_missing_api_key_errors() {
         let _guard = lock_env();
@@ -151,6 +682,9 @@ mod tests {
         assert_eq!(config.max_tokens, DEFAULT_MAX_TOKENS);
         assert!((config.temperature - DEFAULT_TEMPERATURE).abs() < f32::EPSILON);
         assert_eq!(config.timeout, DEFAULT_TIMEOUT);
+        assert_eq!(config.retry_max_attempts, DEFAULT_RETRY_MAX_ATTEMPTS);
+        assert_eq!(config.retry_base_backoff_ms, DEFAULT_RETRY_BASE_BACKOFF_MS);
+        assert!(config.retry_on_500);
 
         // Also ensure validate passes on defaults
         config.validate()?;
@@ -165,18 +699,35 @@ mod tests {
             env::set_var("DEEPSEEK_API_KEY", "k");
             env::set_var("DEEPSEEK_BASE_URL", "https://example.com");
             env::set_var("DEEPSEEK_MODEL", "custom-model");
+            env::set_var("DEEPSEEK_TASKFINISHER_MODEL", "artifact-model");
             env::set_var("DEEPSEEK_MAX_TOKENS", "1234");
             env::set_var("DEEPSEEK_TEMPERATURE", "1.25");
             env::set_var("DEEPSEEK_TIMEOUT", "33");
+            env::set_var("DEEPSEEK_RETRY_ATTEMPTS", "5");
+            env::set_var("DEEPSEEK_RETRY_BACKOFF_MS", "250");
+            env::set_var("DEEPSEEK_RETRY_ON_500", "false");
+            env::set_var("DEEPSEEK_MAX_CONCURRENT_REQUESTS", "4");
+            env::set_var("DEEPSEEK_PROXY", "socks5://127.0.0.1:1080");
+            env::set_var("DEEPSEEK_HEADER_X_ORG_ID", "abc");
         }
 
         let config = Config::load()?;
         assert_eq!(config.api_key, "k");
         assert_eq!(config.base_url, "https://example.com");
         assert_eq!(config.model, "custom-model");
+        assert_eq!(config.taskfinisher_model.as_deref(), Some("artifact-model"));
         assert_eq!(config.max_tokens, 1234);
         assert!((config.temperature - 1.25).abs() < f32::EPSILON);
         assert_eq!(config.timeout, 33);
+        assert_eq!(config.retry_max_attempts, 5);
+        assert_eq!(config.retry_base_backoff_ms, 250);
+        assert!(!config.retry_on_500);
+        assert_eq!(config.max_concurrent_requests, Some(4));
+        assert_eq!(config.proxy_url.as_deref(), Some("socks5://127.0.0.1:1080"));
+        assert_eq!(
+            config.extra_headers.get("x-org-id").map(String::as_str),
+            Some("abc")
+        );
         Ok(())
     }
 
@@ -234,6 +785,147 @@ mod tests {
         );
     }
 
+    #[test]
+    fn load_user_agent_from_env() -> Result<()> {
+        let _guard = lock_env();
+        clear_env();
+        unsafe {
+            env::set_var("DEEPSEEK_API_KEY", "k");
+            env::set_var("DEEPSEEK_USER_AGENT", "acme-gateway/2.0");
+        }
+        let mut config = Config::load()?;
+        assert_eq!(config.user_agent.as_deref(), Some("acme-gateway/2.0"));
+        config.validate()?;
+
+        config.user_agent = Some("  ".to_string());
+        assert!(config.validate().is_err());
+        config.user_agent = Some("bad\nagent".to_string());
+        assert!(config.validate().is_err());
+        clear_env();
+        Ok(())
+    }
+
+    #[test]
+    fn load_chat_path_from_env() -> Result<()> {
+        let _guard = lock_env();
+        clear_env();
+        unsafe {
+            env::set_var("DEEPSEEK_API_KEY", "k");
+        }
+        assert_eq!(Config::load()?.chat_path, None);
+
+        unsafe {
+            env::set_var("DEEPSEEK_CHAT_PATH", "/v1/chat/completions");
+        }
+        let mut config = Config::load()?;
+        assert_eq!(config.chat_path.as_deref(), Some("/v1/chat/completions"));
+        config.validate()?;
+
+        config.chat_path = Some("v1/chat/completions".to_string());
+        let err = config.validate().unwrap_err();
+        assert!(
+            err.to_string().contains("must start with '/'"),
+            "unexpected error: {}",
+            err
+        );
+        clear_env();
+        Ok(())
+    }
+
+    #[test]
+    fn load_auth_style_from_env() -> Result<()> {
+        let _guard = lock_env();
+        clear_env();
+        unsafe {
+            env::set_var("DEEPSEEK_API_KEY", "k");
+        }
+        assert_eq!(Config::load()?.auth_style, AuthStyle::Bearer);
+
+        unsafe {
+            env::set_var("DEEPSEEK_AUTH_STYLE", "API_KEY_HEADER");
+        }
+        assert_eq!(Config::load()?.auth_style, AuthStyle::ApiKeyHeader);
+
+        unsafe {
+            env::set_var("DEEPSEEK_AUTH_STYLE", "query-param");
+        }
+        assert_eq!(Config::load()?.auth_style, AuthStyle::QueryParam);
+
+        unsafe {
+            env::set_var("DEEPSEEK_AUTH_STYLE", "basic");
+        }
+        assert!(Config::load().is_err());
+        clear_env();
+        Ok(())
+    }
+
+    #[test]
+    fn load_connect_timeout_from_env() -> Result<()> {
+        let _guard = lock_env();
+        clear_env();
+        unsafe {
+            env::set_var("DEEPSEEK_API_KEY", "k");
+        }
+        assert_eq!(Config::load()?.connect_timeout, None);
+
+        unsafe {
+            env::set_var("DEEPSEEK_CONNECT_TIMEOUT", "5");
+        }
+        let mut config = Config::load()?;
+        assert_eq!(config.connect_timeout, Some(5));
+        config.validate()?;
+
+        config.connect_timeout = Some(0);
+        assert!(config.validate().is_err());
+        clear_env();
+        Ok(())
+    }
+
+    #[test]
+    fn load_cache_settings_from_env() -> Result<()> {
+        let _guard = lock_env();
+        clear_env();
+        unsafe {
+            env::set_var("DEEPSEEK_API_KEY", "k");
+        }
+        let config = Config::load()?;
+        assert_eq!(config.cache_dir, None);
+        assert_eq!(config.cache_ttl, DEFAULT_CACHE_TTL);
+
+        unsafe {
+            env::set_var("DEEPSEEK_CACHE_DIR", "/tmp/deepseek-cache");
+            env::set_var("DEEPSEEK_CACHE_TTL", "60");
+        }
+        let mut config = Config::load()?;
+        assert_eq!(config.cache_dir, Some(PathBuf::from("/tmp/deepseek-cache")));
+        assert_eq!(config.cache_ttl, 60);
+
+        config.cache_ttl = 0;
+        assert!(config.validate().is_err());
+        clear_env();
+        Ok(())
+    }
+
+    #[test]
+    fn load_input_denylist_from_env() -> Result<()> {
+        let _guard = lock_env();
+        clear_env();
+        unsafe {
+            env::set_var("DEEPSEEK_API_KEY", "k");
+        }
+        assert!(Config::load()?.input_denylist.is_empty());
+
+        unsafe {
+            env::set_var("DEEPSEEK_INPUT_DENYLIST", r"(?i)\bpassword\b, secret ,");
+        }
+        assert_eq!(
+            Config::load()?.input_denylist,
+            [r"(?i)\bpassword\b", "secret"]
+        );
+        clear_env();
+        Ok(())
+    }
+
     #[test]
     fn validate_rejects_empty_api_key() {
         let config = Config {
@@ -243,6 +935,7 @@ mod tests {
             max_tokens: DEFAULT_MAX_TOKENS,
             temperature: DEFAULT_TEMPERATURE,
             timeout: DEFAULT_TIMEOUT,
+            ..Config::default()
         };
         let err = config.validate().unwrap_err();
         assert!(
@@ -261,6 +954,7 @@ mod tests {
             max_tokens: DEFAULT_MAX_TOKENS,
             temperature: -0.1,
             timeout: DEFAULT_TIMEOUT,
+            ..Config::default()
         };
         let err = config.validate().unwrap_err();
         assert!(
@@ -289,6 +983,7 @@ mod tests {
             max_tokens: 0,
             temperature: DEFAULT_TEMPERATURE,
             timeout: DEFAULT_TIMEOUT,
+            ..Config::default()
         };
         let err = config.validate().unwrap_err();
         assert!(
@@ -306,5 +1001,143 @@ mod tests {
             "unexpected error: {}",
             err
         );
+
+        config.timeout = 1;
+        config.retry_max_attempts = 0;
+        let err = config.validate().unwrap_err();
+        assert!(
+            err.to_string()
+                .contains("Retry attempts must be at least 1"),
+            "unexpected error: {}",
+            err
+        );
+    }
+
+    #[test]
+    fn validate_accepts_http_and_https_base_urls() {
+        for base_url in [
+            "https://api.deepseek.com",
+            "https://api.deepseek.com/v1",
+            "http://127.0.0.1:8080",
+        ] {
+            let config = Config {
+                api_key: "k".to_string(),
+                base_url: base_url.to_string(),
+                ..Config::default()
+            };
+            assert!(config.validate().is_ok(), "{} should be valid", base_url);
+        }
+    }
+
+    #[test]
+    fn validate_rejects_malformed_base_urls() {
+        for (base_url, expected) in [
+            ("htps://api.deepseek.com", "must use http or https"),
+            ("api.deepseek.com", "is not a valid URL"),
+            ("https://", "is not a valid URL"),
+            (
+                "https://api.deepseek.com/chat/completions",
+                "must not include /chat/completions",
+            ),
+        ] {
+            let config = Config {
+                api_key: "k".to_string(),
+                base_url: base_url.to_string(),
+                ..Config::default()
+            };
+            let err = config.validate().unwrap_err();
+            assert!(
+                err.to_string().contains(expected),
+                "unexpected error for {}: {}",
+                base_url,
+                err
+            );
+        }
+    }
+
+    #[test]
+    fn validate_checks_field_aliases() {
+        let mut config = Config {
+            api_key: "k".to_string(),
+            ..Config::default()
+        };
+        config
+            .field_aliases
+            .insert("summary".to_string(), "description".to_string());
+        assert!(config.validate().is_ok());
+
+        config
+            .field_aliases
+            .insert("body".to_string(), "text".to_string());
+        let err = config.validate().unwrap_err();
+        assert!(
+            err.to_string().contains("unknown field 'text'"),
+            "unexpected error: {}",
+            err
+        );
+
+        config.field_aliases.clear();
+        config
+            .field_aliases
+            .insert("title".to_string(), "content".to_string());
+        assert!(config.validate().is_err());
+    }
+
+    #[test]
+    fn validate_rejects_unknown_optional_fields() {
+        let mut config = Config {
+            api_key: "k".to_string(),
+            optional_fields: vec!["title".to_string(), "category".to_string()],
+            ..Config::default()
+        };
+        let err = config.validate().unwrap_err();
+        assert!(
+            err.to_string()
+                .contains("Unknown optional field 'category'"),
+            "unexpected error: {}",
+            err
+        );
+
+        config.optional_fields.pop();
+        assert!(config.validate().is_ok());
+    }
+
+    #[test]
+    fn validate_rejects_sampling_controls_out_of_range() {
+        let mut config = Config {
+            api_key: "k".to_string(),
+            top_p: Some(1.5),
+            ..Config::default()
+        };
+        let err = config.validate().unwrap_err();
+        assert!(
+            err.to_string()
+                .contains("Top-p must be between 0.0 and 1.0"),
+            "unexpected error: {}",
+            err
+        );
+
+        config.top_p = Some(1.0);
+        config.frequency_penalty = Some(-2.5);
+        let err = config.validate().unwrap_err();
+        assert!(
+            err.to_string()
+                .contains("Frequency penalty must be between -2.0 and 2.0"),
+            "unexpected error: {}",
+            err
+        );
+
+        config.frequency_penalty = Some(2.0);
+        config.presence_penalty = Some(2.01);
+        let err = config.validate().unwrap_err();
+        assert!(
+            err.to_string()
+                .contains("Presence penalty must be between -2.0 and 2.0"),
+            "unexpected error: {}",
+            err
+        );
+
+        config.presence_penalty = Some(-2.0);
+        assert!(config.validate().is_ok());
     }
 }
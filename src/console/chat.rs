@@ -0,0 +1,137 @@
+use colored::*;
+
+use crate::deepseek::{ChatMessage, DeepSeekError, build_json_prompt, parse_response_with_aliases};
+
+use super::Console;
+
+/// System message periodically re-sent in long chats to keep replies in the JSON format
+const FORMAT_REMINDER: &str = "Reminder: reply with a single JSON object with the fields \
+title, description, content, category, timestamp and confidence. No text outside the JSON.";
+
+impl Console {
+    /// Opening history for a multi-turn chat session
+    pub(super) fn initial_chat_history(&self) -> Vec<ChatMessage> {
+        vec![ChatMessage {
+            role: "system".to_string(),
+            content: self.client.system_prompt(),
+        }]
+    }
+
+    /// Send one chat turn with the accumulated history, display the reply and return it.
+    /// The user message and the assistant reply are kept in `history` only on success.
+    pub(super) async fn send_chat_turn(
+        &self,
+        input: &str,
+        history: &mut Vec<ChatMessage>,
+    ) -> Result<String, DeepSeekError> {
+        history.push(ChatMessage {
+            role: "user".to_string(),
+            content: build_json_prompt(input, self.client.optional_fields()),
+        });
+
+        let messages = with_format_reminder(history, self.options.remind_every);
+        let (raw, meta) = match super::render::with_loading_spinner(
+            self.client.send_messages_raw_with_meta(messages),
+        )
+        .await
+        {
+            Ok(reply) => reply,
+            Err(e) => {
+                history.pop();
+                return Err(e);
+            }
+        };
+
+        self.display_reasoning(&meta);
+        match parse_response_with_aliases(
+            &raw,
+            self.client.optional_fields(),
+            self.client.field_aliases(),
+        ) {
+            Ok(response) => self.display_response(&response),
+            Err(_) => super::render::display_raw_reply(&raw, &self.options.theme),
+        }
+        super::render::display_timing(&meta, &self.options.theme);
+        history.push(ChatMessage {
+            role: "assistant".to_string(),
+            content: raw.clone(),
+        });
+
+        Ok(raw)
+    }
+}
+
+/// Copy of `history` for sending, with a format reminder placed before the latest user
+/// message on every `every`-th turn. The reminder is never stored in the history itself.
+fn with_format_reminder(history: &[ChatMessage], every: Option<u32>) -> Vec<ChatMessage> {
+    let mut messages = history.to_vec();
+    let Some(every) = every.filter(|n| *n > 0) else {
+        return messages;
+    };
+
+    let turn = history.iter().filter(|m| m.role == "user").count() as u32;
+    if turn > 0 && turn.is_multiple_of(every) {
+        messages.insert(
+            messages.len() - 1,
+            ChatMessage {
+                role: "system".to_string(),
+                content: FORMAT_REMINDER.to_string(),
+            },
+        );
+    }
+    messages
+}
+
+/// Print a short note after the history has been cleared
+pub(super) fn display_reset() {
+    println!("{}", "🧹 Conversation history cleared.".bright_green());
+}
+
+/// Tell the user `/reset` has nothing to clear outside chat mode
+pub(super) fn display_reset_unavailable() {
+    println!(
+        "{}",
+        "ℹ️ Each question is independent; run `chat --history` to keep conversation history."
+            .blue()
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn history_with_turns(turns: usize) -> Vec<ChatMessage> {
+        let mut history = vec![ChatMessage {
+            role: "system".to_string(),
+            content: "sys".to_string(),
+        }];
+        for i in 0..turns {
+            if i > 0 {
+                history.push(ChatMessage {
+                    role: "assistant".to_string(),
+                    content: "{}".to_string(),
+                });
+            }
+            history.push(ChatMessage {
+                role: "user".to_string(),
+                content: format!("q{}", i + 1),
+            });
+        }
+        history
+    }
+
+    #[test]
+    fn reminder_is_inserted_before_every_nth_user_turn() {
+        let history = history_with_turns(3);
+        let messages = with_format_reminder(&history, Some(3));
+        assert_eq!(messages.len(), history.len() + 1);
+        assert_eq!(messages[messages.len() - 2].content, FORMAT_REMINDER);
+        assert_eq!(messages.last().unwrap().content, "q3");
+
+        assert_eq!(
+            with_format_reminder(&history_with_turns(2), Some(3)).len(),
+            4
+        );
+        assert_eq!(with_format_reminder(&history, None).len(), history.len());
+    }
+}
@@ -0,0 +1,81 @@
+/// A slash command typed at the interactive prompt
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ConsoleCommand {
+    /// `/quit` or `/exit`: end the session
+    Quit,
+    /// `/help` or `/commands`: list the available commands
+    Help,
+    /// `/why`: explain the last error
+    Why,
+    /// `/reset`: clear the chat history
+    Reset,
+    /// `/model [name]`: switch or show the model
+    Model(String),
+    /// `/temp [value]`: change or show the temperature
+    Temp(String),
+}
+
+/// Usage and effect of every command, as listed by `/help`
+pub const COMMANDS: &[(&str, &str)] = &[
+    ("/help, /commands", "Show this list of commands"),
+    ("/why", "Explain the last error and how to fix it"),
+    (
+        "/reset",
+        "Clear the conversation history (with chat --history)",
+    ),
+    (
+        "/model [name]",
+        "Switch the model for the next requests, or show it",
+    ),
+    (
+        "/temp [value]",
+        "Set the temperature (0.0-2.0) for the next requests, or show it",
+    ),
+    ("/quit, /exit", "End the session (Ctrl+C works too)"),
+];
+
+impl ConsoleCommand {
+    /// Recognize a command, case-insensitively; `None` for questions and unknown commands
+    pub fn parse(input: &str) -> Option<Self> {
+        let input = input.trim();
+        let (name, argument) = input.split_once(char::is_whitespace).unwrap_or((input, ""));
+        let argument = argument.trim();
+
+        // Only /model and /temp take an argument; "/quit now" is an ordinary question
+        match name.to_ascii_lowercase().as_str() {
+            "/model" => Some(Self::Model(argument.to_string())),
+            "/temp" => Some(Self::Temp(argument.to_string())),
+            _ if !argument.is_empty() => None,
+            "/quit" | "/exit" => Some(Self::Quit),
+            "/help" | "/commands" => Some(Self::Help),
+            "/why" => Some(Self::Why),
+            "/reset" => Some(Self::Reset),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_commands_and_arguments() {
+        assert_eq!(ConsoleCommand::parse("/EXIT"), Some(ConsoleCommand::Quit));
+        assert_eq!(
+            ConsoleCommand::parse("/commands"),
+            Some(ConsoleCommand::Help)
+        );
+        assert_eq!(
+            ConsoleCommand::parse("/model  deepseek-reasoner "),
+            Some(ConsoleCommand::Model("deepseek-reasoner".to_string()))
+        );
+        assert_eq!(
+            ConsoleCommand::parse("/temp"),
+            Some(ConsoleCommand::Temp(String::new()))
+        );
+        assert_eq!(ConsoleCommand::parse("/why not"), None);
+        assert_eq!(ConsoleCommand::parse("/unknown"), None);
+        assert_eq!(ConsoleCommand::parse("What is /help?"), None);
+    }
+}
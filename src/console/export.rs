@@ -0,0 +1,60 @@
+use anyhow::{Context, Result};
+use colored::*;
+use std::path::Path;
+
+/// Write an exported document, creating missing parent directories, print a
+/// confirmation to stderr, and optionally open it
+pub fn write_export(path: &Path, contents: &str, label: &str, open_after: bool) -> Result<()> {
+    if let Some(parent) = path.parent()
+        && !parent.as_os_str().is_empty()
+    {
+        std::fs::create_dir_all(parent)
+            .with_context(|| format!("Failed to create directory {}", parent.display()))?;
+    }
+    std::fs::write(path, contents)
+        .with_context(|| format!("Failed to write {} export to {}", label, path.display()))?;
+    eprintln!(
+        "{} {}",
+        format!("📝 Exported {} to", label).green(),
+        path.display().to_string().bright_white()
+    );
+    if open_after {
+        open_export(path);
+    }
+    Ok(())
+}
+
+/// Open an exported file with the system default application. When no desktop
+/// session is available, the path is printed instead.
+pub fn open_export(path: &Path) {
+    if is_headless() || open::that_detached(path).is_err() {
+        eprintln!(
+            "{} {}",
+            "📂 Could not launch a viewer. Open it at:".blue(),
+            path.display()
+        );
+    }
+}
+
+/// Linux without an X11/Wayland display has nothing to open files with
+fn is_headless() -> bool {
+    cfg!(target_os = "linux")
+        && std::env::var_os("DISPLAY").is_none()
+        && std::env::var_os("WAYLAND_DISPLAY").is_none()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn write_export_creates_parent_directories() {
+        let dir = std::env::temp_dir().join(format!("deepseek_json_export_{}", std::process::id()));
+        let path = dir.join("nested/out.json");
+
+        write_export(&path, "{}", "response", false).unwrap();
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "{}");
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
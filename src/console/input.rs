@@ -3,6 +3,8 @@ use colored::*;
 use std::io::{self, Write};
 use tokio::io::{AsyncBufReadExt, BufReader};
 
+use super::command::ConsoleCommand;
+
 /// Get user input from the console (async version)
 pub async fn get_user_input() -> Result<String> {
     print!("{}", "💬 Enter your question: ".bright_cyan().bold());
@@ -22,7 +24,18 @@ pub async fn get_user_input() -> Result<String> {
 pub async fn prompt_user(prompt_text: &str) -> Result<String> {
     print!("{}", prompt_text.bright_cyan().bold());
     io::stdout().flush().unwrap();
+    read_line().await
+}
 
+/// Like [`prompt_user`], but writes the prompt to stderr so stdout stays machine-readable
+pub async fn prompt_user_on_stderr(prompt_text: &str) -> Result<String> {
+    eprint!("{}", prompt_text.bright_cyan().bold());
+    io::stderr().flush().unwrap();
+    read_line().await
+}
+
+/// Read one line from stdin (trimmed)
+async fn read_line() -> Result<String> {
     let mut reader = BufReader::new(tokio::io::stdin());
     let mut input = String::new();
     reader
@@ -33,7 +46,24 @@ pub async fn prompt_user(prompt_text: &str) -> Result<String> {
     Ok(input.trim().to_string())
 }
 
+/// Check if the input asks for details about the last error
+pub fn is_why_command(input: &str) -> bool {
+    ConsoleCommand::parse(input) == Some(ConsoleCommand::Why)
+}
+
+/// Check if the input asks to clear the chat history
+pub fn is_reset_command(input: &str) -> bool {
+    ConsoleCommand::parse(input) == Some(ConsoleCommand::Reset)
+}
+
+/// Argument of a `/command <argument>` input (trimmed, possibly empty), or `None` when the
+/// input is a different command or a question
+pub fn command_argument<'a>(input: &'a str, command: &str) -> Option<&'a str> {
+    let (name, argument) = input.split_once(char::is_whitespace).unwrap_or((input, ""));
+    name.eq_ignore_ascii_case(command).then(|| argument.trim())
+}
+
 /// Check if the input is a quit command
 pub fn is_quit_command(input: &str) -> bool {
-    input.eq_ignore_ascii_case("/quit") || input.eq_ignore_ascii_case("/exit")
+    ConsoleCommand::parse(input) == Some(ConsoleCommand::Quit)
 }
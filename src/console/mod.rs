@@ -1,27 +1,116 @@
 use anyhow::{Error, Result};
+use colored::*;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use std::time::Duration;
 use tokio::select;
 
-use crate::deepseek::{DeepSeekClient, DeepSeekError, DeepSeekResponse};
-use crate::taskfinisher::TechnicalTaskArtifact;
+use crate::deepseek::{
+    ChatMessage, CompatibilityReport, DeepSeekClient, DeepSeekError, DeepSeekResponse, ResponseMeta,
+};
+use crate::summary::TaskFinisherSummary;
+use crate::taskfinisher::{AnswersPayload, TechnicalTaskArtifact};
 
+mod chat;
+mod command;
+mod export;
 mod input;
 mod render;
+mod settings;
 mod taskfinisher;
+mod theme;
+mod transcript;
+
+/// Number of consecutive empty lines that ends the session in `Quit` mode
+const EMPTY_LINES_TO_QUIT: u32 = 3;
+
+/// What the interactive loop does when the user submits an empty line
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum EmptyInputBehavior {
+    /// Silently wait for the next line
+    #[default]
+    Ignore,
+    /// Print a short usage hint
+    Hint,
+    /// Quit after three empty lines in a row
+    Quit,
+}
+
+/// Options controlling the interactive console behavior
+#[derive(Debug, Clone, Default)]
+pub struct ConsoleOptions {
+    pub empty_input: EmptyInputBehavior,
+    /// Write the final TaskFinisher artifact as Markdown to this path
+    pub export_md: Option<PathBuf>,
+    /// Write all clarifying questions and answers of the session as JSON to this path
+    pub question_log: Option<PathBuf>,
+    /// Append each round's checklist progress as a JSON line to this path (`-` for stdout)
+    pub checklist_log: Option<PathBuf>,
+    /// Print the final TaskFinisher artifact as plain JSON on stdout, and everything else
+    /// (status messages, prompts) on stderr
+    pub json: bool,
+    /// Token the final TaskFinisher artifact must carry in `end_token` (default `【END】`)
+    pub end_token: Option<String>,
+    /// Answers to submit without prompting when a clarifying question with the same id comes up
+    pub answers: Option<AnswersPayload>,
+    /// List checklist fields that are already complete instead of hiding them
+    pub show_complete: bool,
+    /// Open exported files with the system default application
+    pub open_exports: bool,
+    /// Save the TaskFinisher conversation here after each round and resume from it on startup
+    pub session_file: Option<PathBuf>,
+    /// Keep conversation history across turns so follow-up questions have context
+    pub chat: bool,
+    /// In chat mode, re-send a short JSON format reminder every N user turns
+    pub remind_every: Option<u32>,
+    /// Append each question and answer as JSON lines to this file
+    pub transcript: Option<PathBuf>,
+    /// Show the chain-of-thought of reasoning models before each answer
+    pub show_reasoning: bool,
+    /// Colors for rendered responses and artifacts
+    pub theme: Theme,
+    /// Ask for this many alternative answers per query and show them numbered (not used in
+    /// chat mode); `None` or 1 asks for one
+    pub choices: Option<u32>,
+    /// Flag answers whose confidence exceeds this; `None` uses
+    /// [`DEFAULT_OVERCONFIDENT_THRESHOLD`]
+    pub warn_overconfident: Option<f32>,
+}
 
 /// Console interface for the DeepSeek application
 pub struct Console {
     client: DeepSeekClient,
+    options: ConsoleOptions,
+    /// Outcome of the last TaskFinisher run, for the session summary
+    taskfinisher_outcome: Mutex<Option<TaskFinisherSummary>>,
 }
 
 impl Console {
     /// Create a new console interface with the provided DeepSeek client
     pub fn new(client: DeepSeekClient) -> Self {
-        Self { client }
+        Self::with_options(client, ConsoleOptions::default())
     }
 
-    /// Display a welcome banner
-    pub fn display_welcome() {
-        render::display_welcome();
+    /// Create a new console interface with custom options
+    pub fn with_options(client: DeepSeekClient, options: ConsoleOptions) -> Self {
+        Self {
+            client,
+            options,
+            taskfinisher_outcome: Mutex::new(None),
+        }
+    }
+
+    /// Outcome of the last completed TaskFinisher run, if any
+    pub fn taskfinisher_outcome(&self) -> Option<TaskFinisherSummary> {
+        self.taskfinisher_outcome
+            .lock()
+            .ok()
+            .and_then(|outcome| *outcome)
+    }
+
+    /// Display a welcome banner in the configured theme
+    pub fn display_welcome(&self) {
+        render::display_welcome(&self.options.theme);
     }
 
     /// Get user input from the console (async)
@@ -39,19 +128,25 @@ impl Console {
         input::is_quit_command(input_text)
     }
 
-    /// Display a loading message
-    pub fn display_loading() {
-        render::display_loading();
+    /// Start the loading spinner; it is cleared when the returned guard is dropped
+    pub fn display_loading() -> render::LoadingSpinner {
+        render::display_loading()
     }
 
-    /// Display the structured response from DeepSeek
-    pub fn display_response(response: &DeepSeekResponse) {
-        render::display_response(response);
+    /// Display the structured response from DeepSeek in the configured theme
+    pub fn display_response(&self, response: &DeepSeekResponse) {
+        render::display_response(
+            response,
+            &self.options.theme,
+            self.options
+                .warn_overconfident
+                .unwrap_or(DEFAULT_OVERCONFIDENT_THRESHOLD),
+        );
     }
 
     /// Display a TaskFinisher Technical Task artifact with colored sections
-    pub fn display_taskfinisher_artifact(artifact: &TechnicalTaskArtifact) {
-        render::display_taskfinisher_artifact(artifact);
+    pub fn display_taskfinisher_artifact(&self, artifact: &TechnicalTaskArtifact) {
+        render::display_taskfinisher_artifact(artifact, &self.options.theme);
     }
 
     /// Display an error message with context-aware messaging
@@ -64,20 +159,51 @@ impl Console {
         render::display_deepseek_error(error);
     }
 
-    /// Display a goodbye message
-    pub fn display_goodbye() {
-        render::display_goodbye();
+    /// Display the result of an API key / endpoint health check in `theme`
+    pub fn display_check_result(
+        model: &str,
+        base_url: &str,
+        result: &Result<Duration, DeepSeekError>,
+        theme: &Theme,
+    ) {
+        render::display_check_result(model, base_url, result, theme);
+    }
+
+    /// Display the result of a gateway compatibility probe in `theme`
+    pub fn display_compatibility_report(report: &CompatibilityReport, theme: &Theme) {
+        render::display_compatibility_report(report, theme);
+    }
+
+    /// Display a goodbye message in the configured theme
+    pub fn display_goodbye(&self) {
+        render::display_goodbye(&self.options.theme);
     }
 
     /// Run the main console loop (interactive mode)
     pub async fn run(&self) -> Result<()> {
-        Self::display_welcome();
+        self.display_welcome();
+        let mut empty_streak = 0u32;
+        let mut last_error: Option<DeepSeekError> = None;
+        let mut history = self.initial_chat_history();
+        let mut transcript = self
+            .options
+            .transcript
+            .as_deref()
+            .map(transcript::open_transcript)
+            .transpose()?;
+        if self.options.chat {
+            println!(
+                "{}",
+                "💬 Chat mode: follow-up questions keep context. Type '/reset' to start over.\n"
+                    .blue()
+            );
+        }
 
         loop {
             select! {
                 // Handle Ctrl+C gracefully
                 _ = tokio::signal::ctrl_c() => {
-                    Self::display_goodbye();
+                    self.display_goodbye();
                     break;
                 }
                 // Handle user input
@@ -91,28 +217,70 @@ impl Console {
                     };
 
                     if input.is_empty() {
+                        empty_streak += 1;
+                        match self.options.empty_input {
+                            EmptyInputBehavior::Ignore => {}
+                            EmptyInputBehavior::Hint => {
+                                println!("{}", "(type a question or /quit)".truecolor(150, 150, 150));
+                            }
+                            EmptyInputBehavior::Quit if empty_streak >= EMPTY_LINES_TO_QUIT => {
+                                self.display_goodbye();
+                                break;
+                            }
+                            EmptyInputBehavior::Quit => {}
+                        }
                         continue;
                     }
+                    empty_streak = 0;
 
-                    if Self::is_quit_command(&input) {
-                        Self::display_goodbye();
-                        break;
+                    if let Some(command) = ConsoleCommand::parse(&input) {
+                        match command {
+                            ConsoleCommand::Quit => {
+                                self.display_goodbye();
+                                break;
+                            }
+                            ConsoleCommand::Help => render::display_help(&self.options.theme),
+                            ConsoleCommand::Why => {
+                                render::display_error_explanation(last_error.as_ref(), &self.options.theme)
+                            }
+                            ConsoleCommand::Model(name) => self.change_model(&name),
+                            ConsoleCommand::Temp(value) => self.change_temperature(&value),
+                            ConsoleCommand::Reset if self.options.chat => {
+                                history = self.initial_chat_history();
+                                chat::display_reset();
+                            }
+                            ConsoleCommand::Reset => chat::display_reset_unavailable(),
+                        }
+                        continue;
                     }
 
-                    Self::display_loading();
-
-                    // Allow request to be cancelled by Ctrl+C
+                    // Allow request to be cancelled by Ctrl+C; the spinner inside `send_turn`
+                    // is cleared when its future is dropped
                     select! {
                         _ = tokio::signal::ctrl_c() => {
                             println!("\n⚠️ Request cancelled by user");
-                            Self::display_goodbye();
+                            self.display_goodbye();
                             break;
                         }
-                        result = self.client.send_request(&input) => {
+                        result = self.send_turn(&input, &mut history) => {
                             match result {
-                                Ok(response) => Self::display_response(&response),
-                                Err(e) => Self::display_deepseek_error(&e),
+                                Ok(answer) => {
+                                    if let Some(file) = transcript.as_mut()
+                                        && let Err(e) = transcript::record_turn(file, &input, &answer)
+                                    {
+                                        println!("{} {:#}", "⚠️ Could not update transcript:".bright_yellow(), e);
+                                    }
+                                }
+                                Err(e) => {
+                                    Self::display_deepseek_error(&e);
+                                    if e.is_budget_exceeded() {
+                                        self.display_goodbye();
+                                        break;
+                                    }
+                                    last_error = Some(e);
+                                }
                             }
+                            self.display_token_budget();
                         }
                     }
                 }
@@ -121,11 +289,76 @@ impl Console {
 
         Ok(())
     }
+
+    /// Show tokens used against the session budget, when one is configured
+    fn display_token_budget(&self) {
+        if let Some(budget) = self.client.token_budget() {
+            let used = self.client.tokens_used();
+            if self.options.json {
+                eprintln!("{}", render::token_budget_line(used, budget));
+            } else {
+                render::display_token_budget(used, budget);
+            }
+        }
+    }
+
+    /// Show the model's chain-of-thought when `show_reasoning` is set and it sent one
+    fn display_reasoning(&self, meta: &ResponseMeta) {
+        if self.options.show_reasoning
+            && let Some(reasoning) = &meta.reasoning
+        {
+            render::display_reasoning(reasoning, &self.options.theme);
+        }
+    }
+
+    /// Answer one interactive input, either as a standalone query or as a chat turn.
+    /// Returns the answer as JSON text, for the transcript.
+    async fn send_turn(
+        &self,
+        input: &str,
+        history: &mut Vec<ChatMessage>,
+    ) -> Result<String, DeepSeekError> {
+        if self.options.chat {
+            return self.send_chat_turn(input, history).await;
+        }
+        if let Some(n) = self.options.choices.filter(|n| *n > 1) {
+            let (responses, meta) =
+                render::with_loading_spinner(self.client.send_request_multi(input, n)).await?;
+            self.display_reasoning(&meta);
+            for (number, response) in (1..).zip(&responses) {
+                render::display_choice_header(number, responses.len(), &self.options.theme);
+                self.display_response(response);
+            }
+            render::display_timing(&meta, &self.options.theme);
+            return serde_json::to_string(&responses).map_err(|e| DeepSeekError::ParseError {
+                message: format!("Failed to serialize responses: {}", e),
+            });
+        }
+        let (response, meta) =
+            render::with_loading_spinner(self.client.send_request_with_meta(input)).await?;
+        self.display_reasoning(&meta);
+        self.display_response(&response);
+        render::display_timing(&meta, &self.options.theme);
+        serde_json::to_string(&response).map_err(|e| DeepSeekError::ParseError {
+            message: format!("Failed to serialize response: {}", e),
+        })
+    }
 }
 
 // Re-export utilities for optional external use
-pub use input::{get_user_input, is_quit_command, prompt_user};
+pub use command::{COMMANDS, ConsoleCommand};
+pub use export::{open_export, write_export};
+pub use input::{
+    command_argument, get_user_input, is_quit_command, is_reset_command, is_why_command,
+    prompt_user,
+};
 pub use render::{
-    display_deepseek_error, display_error, display_goodbye, display_loading, display_response,
-    display_taskfinisher_artifact, display_welcome,
+    DEFAULT_OVERCONFIDENT_THRESHOLD, LoadingSpinner, OVERCONFIDENT_NOTE, display_check_result,
+    display_choice_header, display_compatibility_report, display_deepseek_error, display_error,
+    display_error_explanation, display_goodbye, display_help, display_loading, display_raw_reply,
+    display_reasoning, display_response, display_taskfinisher_artifact, display_timing,
+    display_token_budget, display_welcome, pretty_print_json, token_budget_line,
+    with_loading_spinner,
 };
+pub use theme::{Theme, ThemeName};
+pub use transcript::{TranscriptRecord, read_transcript, replay_transcript};
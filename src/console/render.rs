@@ -1,297 +1,448 @@
 use anyhow::Error;
 use colored::*;
+use indicatif::{ProgressBar, ProgressStyle};
+use std::fmt::Display;
+use std::time::Duration;
+use terminal_size::{Width, terminal_size};
 
-use crate::deepseek::{DeepSeekError, DeepSeekResponse};
+use super::command::COMMANDS;
+use super::theme::Theme;
+use crate::deepseek::{
+    CompatibilityReport, DeepSeekError, DeepSeekResponse, ResponseMeta, api_error_details,
+};
 use crate::taskfinisher::TechnicalTaskArtifact;
 
-pub fn display_welcome() {
+/// Line width used when stdout is not a terminal
+const FALLBACK_WIDTH: usize = 80;
+
+/// Narrowest a wrapped value gets, however long its label
+const MIN_WRAP_WIDTH: usize = 20;
+
+/// Indent of continuation lines of a labeled value, including the `│` border
+const WRAP_INDENT: usize = 4;
+
+/// Confidence above which an answer gets a note that the score is likely overstated,
+/// unless `--warn-overconfident` sets another threshold
+pub const DEFAULT_OVERCONFIDENT_THRESHOLD: f32 = 0.99;
+
+/// Width of the terminal stdout is attached to, or 80 columns without one
+fn terminal_width() -> usize {
+    terminal_size()
+        .map(|(Width(width), _)| width as usize)
+        .unwrap_or(FALLBACK_WIDTH)
+}
+
+/// Split `text` into lines for a value that starts after a `first_width`-column prefix and
+/// continues on `width`-column lines. Breaks at whitespace, splits words longer than a line,
+/// and keeps existing line breaks.
+fn wrap_text(text: &str, first_width: usize, width: usize) -> Vec<String> {
+    let first_width = first_width.max(1);
+    let width = width.max(1);
+    let mut lines = Vec::new();
+    for paragraph in text.lines() {
+        let mut line = String::new();
+        let mut line_len = 0;
+        for word in paragraph.split_whitespace() {
+            let chars: Vec<char> = word.chars().collect();
+            let mut rest = &chars[..];
+            while !rest.is_empty() {
+                let limit = if lines.is_empty() { first_width } else { width };
+                // Pieces of a split word each fill a line of their own
+                if line_len > 0 && (rest.len() < chars.len() || line_len + 1 + rest.len() > limit) {
+                    lines.push(std::mem::take(&mut line));
+                    line_len = 0;
+                    continue;
+                }
+                if line_len > 0 {
+                    line.push(' ');
+                    line_len += 1;
+                }
+                let (chunk, tail) = rest.split_at(rest.len().min(limit));
+                line.extend(chunk);
+                line_len += chunk.len();
+                rest = tail;
+            }
+        }
+        lines.push(line);
+    }
+    if lines.is_empty() {
+        lines.push(String::new());
+    }
+    lines
+}
+
+/// Print `prefix` followed by `value` wrapped to the terminal width. Continuation lines keep
+/// the `│` box border and are indented to column `indent`.
+fn print_wrapped(
+    theme: &Theme,
+    prefix: impl Display,
+    prefix_width: usize,
+    indent: usize,
+    value: &str,
+    paint: impl Fn(&Theme, &str) -> ColoredString,
+) {
+    let width = terminal_width();
+    let first_width = width.saturating_sub(prefix_width + 1).max(MIN_WRAP_WIDTH);
+    let rest_width = width.saturating_sub(indent).max(MIN_WRAP_WIDTH);
+    let lines = wrap_text(value, first_width, rest_width);
+
+    println!("{} {}", prefix, paint(theme, &lines[0]));
+    let border = format!("{:<width$}", "│", width = indent);
+    for line in &lines[1..] {
+        println!("{}{}", theme.label(&border), paint(theme, line));
+    }
+}
+
+/// Print a `│ label: value` box line, wrapping long values
+fn print_field(
+    theme: &Theme,
+    label: &str,
+    value: &str,
+    paint: impl Fn(&Theme, &str) -> ColoredString,
+) {
+    print_wrapped(
+        theme,
+        theme.label(label),
+        label.chars().count(),
+        WRAP_INDENT,
+        value,
+        paint,
+    );
+}
+
+pub fn display_welcome(theme: &Theme) {
+    println!("{}", theme.heading("🤖 DeepSeek JSON Chat Application"));
     println!(
         "{}",
-        "🤖 DeepSeek JSON Chat Application".bright_blue().bold()
+        theme.label(
+            "This application sends your queries to DeepSeek and returns structured JSON responses."
+        )
     );
     println!(
         "{}",
-        "This application sends your queries to DeepSeek and returns structured JSON responses."
-            .blue()
+        theme.label("Make sure to set DEEPSEEK_API_KEY environment variable.")
     );
     println!(
         "{}",
-        "Make sure to set DEEPSEEK_API_KEY environment variable.".blue()
+        theme.label("Type '/help' for the list of commands, '/quit' or '/exit' to stop.\n")
     );
-    println!("{}", "Type '/quit' or '/exit' to stop.\n".blue());
 }
 
-pub fn display_loading() {
-    println!("{}", "🔄 Sending request to DeepSeek...".blue().italic());
+pub fn display_help(theme: &Theme) {
+    println!("\n{}", theme.heading("📖 Available commands:"));
+    for (usage, effect) in COMMANDS {
+        println!(
+            "  {} {}",
+            theme.title(&format!("{:<18}", usage)),
+            theme.muted(effect)
+        );
+    }
+    println!();
+}
+
+/// How often the loading spinner advances a frame
+const SPINNER_TICK: Duration = Duration::from_millis(80);
+
+/// Animated "sending request" line on stderr, cleared as soon as it is dropped, whether the
+/// request finished or its future was cancelled. Hidden when stderr is not a terminal
+#[must_use = "the spinner is cleared as soon as it is dropped"]
+pub struct LoadingSpinner(ProgressBar);
+
+impl Drop for LoadingSpinner {
+    fn drop(&mut self) {
+        self.0.finish_and_clear();
+    }
+}
+
+/// Start the loading spinner; it runs until the returned guard is dropped
+pub fn display_loading() -> LoadingSpinner {
+    let spinner = ProgressBar::new_spinner().with_message("Sending request to DeepSeek...");
+    if let Ok(style) = ProgressStyle::with_template("{spinner:.blue} {msg:.blue.italic}") {
+        spinner.set_style(style);
+    }
+    spinner.enable_steady_tick(SPINNER_TICK);
+    LoadingSpinner(spinner)
 }
 
-pub fn display_response(response: &DeepSeekResponse) {
-    println!("\n{}", "📋 Structured Response:".bright_green().bold());
+/// Await `request` with the loading spinner running, clearing it before anything is printed
+/// about the reply
+pub async fn with_loading_spinner<F: Future>(request: F) -> F::Output {
+    let _spinner = display_loading();
+    request.await
+}
+
+/// Shown under a confidence score above the overconfidence threshold
+pub const OVERCONFIDENT_NOTE: &str =
+    "⚠️  Scores this close to certain are often unreliable from language models";
+
+/// Number one of several alternative answers, printed above it
+pub fn display_choice_header(number: usize, total: usize, theme: &Theme) {
     println!(
-        "{}",
-        "┌─────────────────────────────────────────────────────────────".green()
+        "\n{}",
+        theme.heading(&format!("🔢 Choice {} of {}", number, total))
     );
+}
+
+/// Render a structured response. A confidence above `overconfident_above` is flagged,
+/// since near-certain scores from language models are often unreliable.
+pub fn display_response(response: &DeepSeekResponse, theme: &Theme, overconfident_above: f32) {
+    println!("\n{}", theme.success("📋 Structured Response:").bold());
     println!(
-        "{} {}",
-        "│ 🏷️  Title:".green(),
-        response.title.bright_white().bold()
+        "{}",
+        theme.label("┌─────────────────────────────────────────────────────────────")
     );
-    println!(
-        "{} {}",
-        "│ 📝 Description:".green(),
-        response.description.white()
+    print_field(theme, "│ 🏷️  Title:", &response.title, Theme::title);
+    print_field(
+        theme,
+        "│ 📝 Description:",
+        &response.description,
+        Theme::value,
     );
-    println!("{} {}", "│ 📄 Content:".green(), response.content.white());
+    print_field(theme, "│ 📄 Content:", &response.content, Theme::value);
     if let Some(category) = &response.category {
-        println!("{} {}", "│ 🏪 Category:".green(), category.white());
+        println!(
+            "{} {}",
+            theme.label("│ 🏪 Category:"),
+            theme.value(category)
+        );
     }
     if let Some(timestamp) = &response.timestamp {
-        println!("{} {}", "│ ⏰ Timestamp:".green(), timestamp.white());
+        println!(
+            "{} {}",
+            theme.label("│ ⏰ Timestamp:"),
+            theme.value(timestamp)
+        );
     }
     if let Some(confidence) = response.confidence {
         println!(
             "{} {}",
-            "│ 🎯 Confidence:".green(),
-            format!("{:.2}", confidence).white()
+            theme.label("│ 🎯 Confidence:"),
+            theme.value(&format!("{:.2}", confidence))
         );
+        if confidence > overconfident_above {
+            println!("{} {}", theme.label("│"), theme.warning(OVERCONFIDENT_NOTE));
+        }
     }
     println!(
         "{}",
-        "└─────────────────────────────────────────────────────────────\n".green()
+        theme.label("└─────────────────────────────────────────────────────────────\n")
     );
 }
 
-pub fn display_taskfinisher_artifact(artifact: &TechnicalTaskArtifact) {
+pub fn display_taskfinisher_artifact(artifact: &TechnicalTaskArtifact, theme: &Theme) {
+    const BORDER: &str = "─────────────────────────────────────────────────────────────";
+    let none = || println!("{}", theme.muted("│   (none)"));
+
     println!(
         "\n{}",
-        "📦 Technical Task (Artifact):".bright_green().bold()
-    );
-    println!(
-        "{}",
-        "┌─────────────────────────────────────────────────────────────".green()
+        theme.success("📦 Technical Task (Artifact):").bold()
     );
+    println!("{}", theme.label(&format!("┌{}", BORDER)));
     println!(
         "{} {}",
-        "│ 🏷️  Title:".green(),
-        artifact.title.bright_white().bold()
+        theme.label("│ 🏷️  Title:"),
+        theme.title(&artifact.title)
     );
     println!(
         "{} {} ({})",
-        "│ 🧩 Artifact:".green(),
-        artifact.artifact_name.bright_cyan(),
-        format!("v{}", artifact.version).cyan().italic()
+        theme.label("│ 🧩 Artifact:"),
+        theme.heading(&artifact.artifact_name),
+        theme.muted(&format!("v{}", artifact.version)).italic()
     );
-    println!("{} {}", "│ 📝 Summary:".green(), artifact.summary.white());
+    print_field(theme, "│ 📝 Summary:", &artifact.summary, Theme::value);
 
-    println!("{}", "│ — Stakeholders".bright_cyan().bold());
+    println!("{}", theme.heading("│ — Stakeholders"));
     if artifact.stakeholders.is_empty() {
-        println!("{}", "│   (none)".truecolor(180, 180, 180));
+        none();
     } else {
         for stakeholder in &artifact.stakeholders {
             println!(
                 "{} {} — {}",
-                "│   •".cyan(),
-                stakeholder.role.bright_white().bold(),
-                stakeholder.description.white()
+                theme.label("│   •"),
+                theme.title(&stakeholder.role),
+                theme.value(&stakeholder.description)
             );
         }
     }
 
-    println!("{}", "│ — Scope".bright_cyan().bold());
+    println!("{}", theme.heading("│ — Scope"));
     if !artifact.scope.in_scope.is_empty() {
-        println!("{}", "│   In-scope:".green());
+        println!("{}", theme.label("│   In-scope:"));
         for item in &artifact.scope.in_scope {
-            println!("{} {}", "│     ✔".green(), item.white());
+            println!("{} {}", theme.success("│     ✔"), theme.value(item));
         }
     }
     if !artifact.scope.out_of_scope.is_empty() {
-        println!("{}", "│   Out-of-scope:".bright_yellow());
+        println!("{}", theme.warning("│   Out-of-scope:"));
         for item in &artifact.scope.out_of_scope {
-            println!("{} {}", "│     ✖".yellow(), item.white());
+            println!("{} {}", theme.warning("│     ✖"), theme.value(item));
         }
     }
 
-    println!("{}", "│ — Requirements".bright_cyan().bold());
+    println!("{}", theme.heading("│ — Requirements"));
     if artifact.requirements.functional.is_empty() {
-        println!("{}", "│   Functional: (none)".truecolor(180, 180, 180));
+        println!("{}", theme.muted("│   Functional: (none)"));
     } else {
-        println!("{}", "│   Functional:".green());
+        println!("{}", theme.label("│   Functional:"));
         for fr in &artifact.requirements.functional {
-            println!(
-                "{} {} {}",
-                "│     •".green(),
-                fr.id.bright_white().bold(),
-                fr.statement.white()
+            print_wrapped(
+                theme,
+                format!("{} {}", theme.label("│     •"), theme.title(&fr.id)),
+                format!("│     • {}", fr.id).chars().count(),
+                "│       ".chars().count(),
+                &fr.statement,
+                Theme::value,
             );
             if let Some(rationale) = &fr.rationale
-                && !rationale.is_empty() {
-                    println!(
-                        "{} {}",
-                        "│       ↳ rationale:".truecolor(150, 150, 255),
-                        rationale.truecolor(170, 170, 255).italic()
-                    );
-                }
+                && !rationale.is_empty()
+            {
+                println!(
+                    "{} {}",
+                    theme.muted("│       ↳ rationale:"),
+                    theme.muted(rationale).italic()
+                );
+            }
         }
     }
     if artifact.requirements.non_functional.is_empty() {
-        println!("{}", "│   Non-functional: (none)".truecolor(180, 180, 180));
+        println!("{}", theme.muted("│   Non-functional: (none)"));
     } else {
-        println!("{}", "│   Non-functional:".green());
+        println!("{}", theme.label("│   Non-functional:"));
         for nfr in &artifact.requirements.non_functional {
-            println!(
-                "{} {} [{}] → {}",
-                "│     •".green(),
-                nfr.id.bright_white().bold(),
-                nfr.category.bright_cyan(),
-                nfr.target.white()
+            print_wrapped(
+                theme,
+                format!(
+                    "{} {} [{}] →",
+                    theme.label("│     •"),
+                    theme.title(&nfr.id),
+                    theme.heading(&nfr.category)
+                ),
+                format!("│     • {} [{}] →", nfr.id, nfr.category)
+                    .chars()
+                    .count(),
+                "│       ".chars().count(),
+                &nfr.target,
+                Theme::value,
             );
         }
     }
 
-    println!("{}", "│ — Data Integrations".bright_cyan().bold());
-    if !artifact
-        .data_integrations
-        .rpc_providers
-        .selection
-        .is_empty()
-    {
+    println!("{}", theme.heading("│ — Data Integrations"));
+    let integrations = &artifact.data_integrations;
+    if !integrations.rpc_providers.selection.is_empty() {
         println!(
             "{} {}",
-            "│   RPC providers:".green(),
-            format!("{:?}", artifact.data_integrations.rpc_providers.selection).white()
+            theme.label("│   RPC providers:"),
+            theme.value(&format!("{:?}", integrations.rpc_providers.selection))
         );
     }
-    if !artifact
-        .data_integrations
-        .rpc_providers
-        .endpoints
-        .is_empty()
-    {
-        println!("{}", "│   Endpoints:".green());
-        for (name, value) in &artifact.data_integrations.rpc_providers.endpoints {
+    if !integrations.rpc_providers.endpoints.is_empty() {
+        println!("{}", theme.label("│   Endpoints:"));
+        for (name, value) in &integrations.rpc_providers.endpoints {
             println!(
                 "{} {} = {}",
-                "│     •".green(),
-                name.bright_white(),
-                value.to_string().white()
+                theme.label("│     •"),
+                theme.title(name),
+                theme.value(&value.to_string())
             );
         }
     }
     println!(
         "{} {}{}",
-        "│   Price source:".green(),
-        artifact
-            .data_integrations
-            .price_source
-            .provider
-            .bright_white(),
-        match artifact.data_integrations.price_source.ttl_seconds {
-            Some(ttl) => format!(" (ttl={}s)", ttl).truecolor(180, 180, 180),
+        theme.label("│   Price source:"),
+        theme.title(&integrations.price_source.provider),
+        match integrations.price_source.ttl_seconds {
+            Some(ttl) => theme.muted(&format!(" (ttl={}s)", ttl)),
             None => "".normal(),
         }
     );
 
-    println!("{}", "│ — Constraints".bright_cyan().bold());
+    println!("{}", theme.heading("│ — Constraints"));
     if artifact.constraints.is_empty() {
-        println!("{}", "│   (none)".truecolor(180, 180, 180));
+        none();
     } else {
         for c in &artifact.constraints {
-            println!("{} {}", "│   •".green(), c.white());
+            println!("{} {}", theme.label("│   •"), theme.value(c));
         }
     }
 
-    println!("{}", "│ — Assumptions".bright_cyan().bold());
+    println!("{}", theme.heading("│ — Assumptions"));
     if artifact.assumptions.is_empty() {
-        println!("{}", "│   (none)".truecolor(180, 180, 180));
+        none();
     } else {
         for a in &artifact.assumptions {
-            println!("{} {}", "│   •".green(), a.white());
+            println!("{} {}", theme.label("│   •"), theme.value(a));
         }
     }
 
-    println!("{}", "│ — Risks".bright_cyan().bold());
+    println!("{}", theme.heading("│ — Risks"));
     if artifact.risks.is_empty() {
-        println!("{}", "│   (none)".truecolor(180, 180, 180));
+        none();
     } else {
         for r in &artifact.risks {
             println!(
                 "{} {}: {}",
-                "│   ⚠".bright_yellow(),
-                r.id.bright_yellow().bold(),
-                r.description.white()
+                theme.warning("│   ⚠"),
+                theme.warning(&r.id).bold(),
+                theme.value(&r.description)
             );
             println!(
                 "{} {}",
-                "│     mitigation:".green(),
-                r.mitigation.bright_green()
+                theme.label("│     mitigation:"),
+                theme.success(&r.mitigation)
             );
         }
     }
 
-    println!("{}", "│ — Milestones".bright_cyan().bold());
+    println!("{}", theme.heading("│ — Milestones"));
     if artifact.milestones.is_empty() {
-        println!("{}", "│   (none)".truecolor(180, 180, 180));
+        none();
     } else {
         for m in &artifact.milestones {
             println!(
                 "{} {} — {}",
-                "│   ⏳".cyan(),
-                m.id.bright_white().bold(),
-                m.name.white()
+                theme.label("│   ⏳"),
+                theme.title(&m.id),
+                theme.value(&m.name)
             );
             if !m.deliverables.is_empty() {
-                println!("{}", "│     deliverables:".green());
+                println!("{}", theme.label("│     deliverables:"));
                 for d in &m.deliverables {
-                    println!("{} {}", "│       •".green(), d.white());
+                    println!("{} {}", theme.label("│       •"), theme.value(d));
                 }
             }
         }
     }
 
-    println!("{}", "│ — Acceptance criteria".bright_cyan().bold());
+    println!("{}", theme.heading("│ — Acceptance criteria"));
     if artifact.acceptance_criteria.is_empty() {
-        println!("{}", "│   (none)".truecolor(180, 180, 180));
+        none();
     } else {
         for ac in &artifact.acceptance_criteria {
-            println!("{} {}", "│   ✅".green(), ac.id.bright_white().bold());
-            println!(
-                "{} {}",
-                "│     Given:".truecolor(180, 180, 255),
-                ac.given.white()
-            );
-            println!(
-                "{} {}",
-                "│     When:".truecolor(180, 180, 255),
-                ac.when.white()
-            );
-            println!(
-                "{} {}",
-                "│     Then:".truecolor(180, 180, 255),
-                ac.then.white()
-            );
+            println!("{} {}", theme.label("│   ✅"), theme.title(&ac.id));
+            println!("{} {}", theme.muted("│     Given:"), theme.value(&ac.given));
+            println!("{} {}", theme.muted("│     When:"), theme.value(&ac.when));
+            println!("{} {}", theme.muted("│     Then:"), theme.value(&ac.then));
         }
     }
 
-    println!("{}", "│ — Open questions".bright_cyan().bold());
+    println!("{}", theme.heading("│ — Open questions"));
     if artifact.open_questions.is_empty() {
-        println!("{}", "│   (none)".truecolor(180, 180, 180));
+        none();
     } else {
         for q in &artifact.open_questions {
-            println!("{} {}", "│   •".bright_yellow(), q.bright_yellow());
+            println!("{} {}", theme.warning("│   •"), theme.warning(q));
         }
     }
 
     println!(
         "{} {} {} {}",
-        "│ Status:".green(),
-        artifact.status.bright_white().bold(),
-        "End:".truecolor(180, 180, 180),
-        artifact.end_token.truecolor(180, 180, 180)
-    );
-    println!(
-        "{}",
-        "└─────────────────────────────────────────────────────────────".green()
+        theme.label("│ Status:"),
+        theme.title(&artifact.status),
+        theme.muted("End:"),
+        theme.muted(&artifact.end_token)
     );
+    println!("{}", theme.label(&format!("└{}", BORDER)));
 }
 
 pub fn display_error(error: &Error) {
@@ -313,7 +464,7 @@ pub fn display_error(error: &Error) {
 pub fn display_deepseek_error(error: &DeepSeekError) {
     let user_message = error.user_message();
     match error {
-        DeepSeekError::ServerBusy => {
+        DeepSeekError::ServerBusy { .. } => {
             println!("{}", user_message.bright_yellow().bold());
             println!(
                 "{}",
@@ -334,8 +485,16 @@ pub fn display_deepseek_error(error: &DeepSeekError) {
                 "💡 Tip: The server might be overloaded. Try again later.".yellow()
             );
         }
-        DeepSeekError::ApiError { status, .. } => {
+        DeepSeekError::ApiError {
+            status,
+            error_type,
+            code,
+            ..
+        } => {
             println!("{}", user_message.bright_red().bold());
+            if let Some(details) = api_error_details(error_type.as_deref(), code.as_deref()) {
+                println!("{}", details.red());
+            }
             match *status {
                 401 => println!(
                     "{}",
@@ -362,6 +521,14 @@ pub fn display_deepseek_error(error: &DeepSeekError) {
                 "💡 Tip: The server response was unexpected. Try rephrasing your query.".magenta()
             );
         }
+        DeepSeekError::EmptyResponse => {
+            println!("{}", user_message.bright_yellow().bold());
+            println!(
+                "{}",
+                "💡 Tip: Send the query again, or raise --max-tokens if it keeps happening."
+                    .yellow()
+            );
+        }
         DeepSeekError::ConfigError { .. } => {
             println!("{}", user_message.bright_red().bold());
             println!(
@@ -369,10 +536,252 @@ pub fn display_deepseek_error(error: &DeepSeekError) {
                 "💡 Tip: Check your environment variables and configuration.".red()
             );
         }
+        DeepSeekError::Truncated { .. } => {
+            println!("{}", user_message.bright_yellow().bold());
+            println!(
+                "{}",
+                "💡 Tip: Increase --max-tokens or ask for a shorter answer.".yellow()
+            );
+        }
+        DeepSeekError::BudgetExceeded { .. } => {
+            println!("{}", user_message.bright_red().bold());
+            println!(
+                "{}",
+                "💡 Tip: Start a new session or raise --token-budget.".red()
+            );
+        }
+        DeepSeekError::InputRejected { .. } => {
+            println!("{}", user_message.bright_red().bold());
+            println!(
+                "{}",
+                "💡 Tip: This deployment blocks certain queries. Rephrase your request.".red()
+            );
+        }
     }
     println!();
 }
 
-pub fn display_goodbye() {
-    println!("{}", "👋 Goodbye!".bright_yellow().bold());
+pub fn display_token_budget(used: u64, budget: u64) {
+    println!("{}", token_budget_line(used, budget));
+}
+
+pub fn token_budget_line(used: u64, budget: u64) -> ColoredString {
+    let remaining = budget.saturating_sub(used);
+    format!(
+        "🪙 Tokens used: {} / {} ({} remaining)",
+        used, budget, remaining
+    )
+    .truecolor(150, 150, 150)
+}
+
+pub fn display_reasoning(reasoning: &str, theme: &Theme) {
+    println!("\n{}", theme.heading("🧠 Reasoning"));
+    for line in reasoning.trim().lines() {
+        println!("{} {}", theme.label("│"), theme.muted(line));
+    }
+}
+
+pub fn display_timing(meta: &ResponseMeta, theme: &Theme) {
+    println!(
+        "{}",
+        theme.muted(&format!("⏱ {:.1}s", meta.latency.as_secs_f64()))
+    );
+}
+
+pub fn display_raw_reply(raw: &str, theme: &Theme) {
+    println!("\n{}", theme.success("💬 Reply:").bold());
+    println!("{}\n", theme.value(raw));
+}
+
+/// Reindent `text` and color its keys, strings, numbers and literals when it is JSON;
+/// anything else is returned unchanged
+pub fn pretty_print_json(text: &str) -> String {
+    match serde_json::from_str::<serde_json::Value>(text.trim()) {
+        Ok(value) => {
+            let mut out = String::new();
+            write_colored_json(&value, 0, &mut out);
+            out
+        }
+        Err(_) => text.to_string(),
+    }
+}
+
+/// Append `value` to `out` as indented JSON, nested `depth` levels deep
+fn write_colored_json(value: &serde_json::Value, depth: usize, out: &mut String) {
+    use serde_json::Value;
+
+    const INDENT: &str = "  ";
+    let quoted = |text: &str| Value::String(text.to_string()).to_string();
+    match value {
+        Value::Null | Value::Bool(_) => out.push_str(&value.to_string().magenta().to_string()),
+        Value::Number(number) => out.push_str(&number.to_string().yellow().to_string()),
+        Value::String(text) => out.push_str(&quoted(text).green().to_string()),
+        Value::Array(items) if items.is_empty() => out.push_str("[]"),
+        Value::Object(fields) if fields.is_empty() => out.push_str("{}"),
+        Value::Array(items) => {
+            out.push_str("[\n");
+            for (index, item) in items.iter().enumerate() {
+                out.push_str(&INDENT.repeat(depth + 1));
+                write_colored_json(item, depth + 1, out);
+                out.push_str(if index + 1 < items.len() { ",\n" } else { "\n" });
+            }
+            out.push_str(&INDENT.repeat(depth));
+            out.push(']');
+        }
+        Value::Object(fields) => {
+            out.push_str("{\n");
+            for (index, (key, field)) in fields.iter().enumerate() {
+                out.push_str(&INDENT.repeat(depth + 1));
+                out.push_str(&quoted(key).cyan().to_string());
+                out.push_str(": ");
+                write_colored_json(field, depth + 1, out);
+                out.push_str(if index + 1 < fields.len() {
+                    ",\n"
+                } else {
+                    "\n"
+                });
+            }
+            out.push_str(&INDENT.repeat(depth));
+            out.push('}');
+        }
+    }
+}
+
+pub fn display_error_explanation(error: Option<&DeepSeekError>, theme: &Theme) {
+    let Some(error) = error else {
+        println!("{}", theme.muted("ℹ️ No errors so far in this session."));
+        return;
+    };
+    println!("\n{}", theme.heading("🔍 About the last error:"));
+    for step in error.explain() {
+        println!("  {} {}", theme.label("•"), theme.value(&step));
+    }
+    println!();
+}
+
+pub fn display_check_result(
+    model: &str,
+    base_url: &str,
+    result: &Result<Duration, DeepSeekError>,
+    theme: &Theme,
+) {
+    println!(
+        "\n{} {}",
+        theme.heading("🩺 API check:"),
+        theme.title(base_url)
+    );
+    println!("  {} {}", theme.label("model:"), theme.value(model));
+    match result {
+        Ok(latency) => println!(
+            "{} ({:.1}s)\n",
+            theme.success("✅ The API key was accepted and the endpoint answered."),
+            latency.as_secs_f64()
+        ),
+        Err(DeepSeekError::ApiError { status: 401, .. }) => println!(
+            "{}\n",
+            theme
+                .warning("❌ Invalid API key: the endpoint rejected DEEPSEEK_API_KEY (401).")
+                .bold()
+        ),
+        Err(e) => println!("{} {}\n", theme.warning("❌ Check failed:").bold(), e),
+    }
+}
+
+pub fn display_compatibility_report(report: &CompatibilityReport, theme: &Theme) {
+    println!(
+        "\n{} {}",
+        theme.heading("🔎 Compatibility probe:"),
+        theme.title(&report.endpoint)
+    );
+    for check in &report.checks {
+        let kind = if check.required {
+            "required"
+        } else {
+            "optional"
+        };
+        if check.present {
+            println!(
+                "  {} {} ({})",
+                theme.success("✅"),
+                theme.value(&check.path),
+                theme.muted(kind)
+            );
+        } else if check.required {
+            println!(
+                "  {} {} ({})",
+                theme.warning("❌"),
+                theme.warning(&check.path).bold(),
+                theme.muted(kind)
+            );
+        } else {
+            println!(
+                "  {} {} ({})",
+                theme.warning("⚠️"),
+                theme.warning(&check.path),
+                theme.muted(kind)
+            );
+        }
+    }
+    if report.is_compatible() {
+        println!(
+            "{}\n",
+            theme.success("✅ The gateway returns OpenAI-compatible chat completions.")
+        );
+    } else {
+        println!(
+            "{}\n",
+            theme
+                .warning("❌ The gateway is missing fields this client needs.")
+                .bold()
+        );
+    }
+}
+
+pub fn display_goodbye(theme: &Theme) {
+    println!("{}", theme.heading("👋 Goodbye!"));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn loading_spinner_passes_the_request_output_through() {
+        assert_eq!(with_loading_spinner(async { 42 }).await, 42);
+    }
+
+    #[test]
+    fn pretty_print_json_reindents_json_and_passes_other_text_through() {
+        let ansi = regex::Regex::new("\x1b\\[[0-9;]*m").unwrap();
+        let pretty = pretty_print_json(r#" {"a":[1,"two\"",null],"b":{},"c":{"d":true}} "#);
+        assert_eq!(
+            ansi.replace_all(&pretty, ""),
+            "{\n  \"a\": [\n    1,\n    \"two\\\"\",\n    null\n  ],\n  \"b\": {},\n  \"c\": {\n    \"d\": true\n  }\n}"
+        );
+
+        assert_eq!(pretty_print_json("not json {"), "not json {");
+    }
+
+    #[test]
+    fn wrap_text_breaks_at_spaces_with_a_shorter_first_line() {
+        assert_eq!(
+            wrap_text("one two three four five", 7, 10),
+            ["one two", "three four", "five"]
+        );
+    }
+
+    #[test]
+    fn wrap_text_splits_long_words_and_keeps_line_breaks() {
+        assert_eq!(
+            wrap_text("abcdefghij\nxy", 4, 4),
+            ["abcd", "efgh", "ij", "xy"]
+        );
+        assert_eq!(wrap_text("", 10, 10), [""]);
+    }
+
+    #[test]
+    fn wrap_text_splits_words_at_the_first_line_width() {
+        assert_eq!(wrap_text("abcdefgh ij", 4, 10), ["abcd", "efgh ij"]);
+        assert_eq!(wrap_text("ab cdefgh", 4, 10), ["ab", "cdefgh"]);
+    }
 }
@@ -0,0 +1,52 @@
+use colored::*;
+
+use super::Console;
+
+impl Console {
+    /// Handle `/model [name]`: switch the model for the following requests, or show the
+    /// current one when no name is given
+    pub(super) fn change_model(&self, name: &str) {
+        if name.is_empty() {
+            display_setting("Model", &self.client.model());
+            return;
+        }
+        match self.client.set_model(name) {
+            Ok(()) => display_changed("Model", &self.client.model()),
+            Err(e) => Self::display_deepseek_error(&e),
+        }
+    }
+
+    /// Handle `/temp [value]`: change the sampling temperature for the following requests,
+    /// or show the current one when no value is given
+    pub(super) fn change_temperature(&self, value: &str) {
+        if value.is_empty() {
+            display_setting("Temperature", &self.client.temperature().to_string());
+            return;
+        }
+        let Ok(temperature) = value.parse::<f32>() else {
+            println!(
+                "{}",
+                format!("⚠️ '{}' is not a number; try e.g. /temp 0.3", value).yellow()
+            );
+            return;
+        };
+        match self.client.set_temperature(temperature) {
+            Ok(()) => display_changed("Temperature", &self.client.temperature().to_string()),
+            Err(e) => Self::display_deepseek_error(&e),
+        }
+    }
+}
+
+/// Print a setting's current value
+fn display_setting(name: &str, value: &str) {
+    println!("{} {}", format!("{}:", name).blue(), value.bright_white());
+}
+
+/// Confirm a changed setting
+fn display_changed(name: &str, value: &str) {
+    println!(
+        "{} {}",
+        format!("✅ {} set to", name).bright_green(),
+        value.bright_white().bold()
+    );
+}
@@ -1,35 +1,83 @@
-use anyhow::Result;
+use anyhow::{Context, Result};
 use colored::*;
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{self, Write};
+use std::path::Path;
 
 use crate::deepseek::ChatMessage;
+use crate::summary::TaskFinisherSummary;
 use crate::taskfinisher::{
-    build_system_prompt, parse_taskfinisher_response, AnswerItem, AnswersPayload,
-    ClarifyingQuestion, TaskFinisherResult,
+    AnswerItem, AnswersPayload, ArtifactChange, ArtifactDiff, ChecklistItem, ChecklistProgress,
+    ClarifyingQuestion, END_TOKEN, QuestionLog, TaskFinisherOutput, TaskFinisherResult,
+    TaskFinisherSession, TechnicalTaskArtifact, artifact_completeness, artifact_to_markdown,
+    build_system_prompt, checklist_completion, diff_artifacts, incomplete_fields,
+    parse_taskfinisher_response, parse_taskfinisher_response_lenient, validate_artifact,
 };
 
 use super::Console;
+use super::render::{pretty_print_json, with_loading_spinner};
+
+/// `println!` for TaskFinisher status output, which goes to stderr when `--json` keeps stdout
+/// for the artifact
+macro_rules! status {
+    ($console:expr) => {
+        if $console.options.json {
+            eprintln!()
+        } else {
+            println!()
+        }
+    };
+    ($console:expr, $($arg:tt)*) => {
+        if $console.options.json {
+            eprintln!($($arg)*)
+        } else {
+            println!($($arg)*)
+        }
+    };
+}
 
 impl Console {
     /// Collect answers for clarifying questions interactively.
-    /// Users enter answers one-by-one; empty input skips a question; typing '/proceed' finalizes early.
+    /// Questions covered by the `--answers` file are answered from it without prompting; when
+    /// it covers them all, nothing is asked. Otherwise users enter answers one-by-one; empty
+    /// input skips a question; typing '/proceed' stops asking. The answers are then reviewed,
+    /// and can be corrected with `/edit <id>` before `/submit` sends them.
     async fn collect_answers_interactively(
+        &self,
         questions: &[ClarifyingQuestion],
-    ) -> Result<AnswersPayload> {
-        println!(
+    ) -> Result<AnswerControl> {
+        let mut answers = preset_answers(self.options.answers.as_ref(), questions);
+        if answers.len() == questions.len() {
+            status!(
+                self,
+                "{}",
+                "📄 All questions answered from the answers file.".blue()
+            );
+            return Ok(AnswerControl::Submit(answers_in_order(questions, &answers)));
+        }
+
+        status!(
+            self,
             "{}",
-            "✍️ Answer the questions one-by-one. Press Enter to skip. Type '/proceed' to finalize now.".blue()
+            "✍️ Answer the questions one-by-one. Press Enter to skip. Type '/proceed' to finalize now, '/restart <request>' to start over or '/cancel' to stop.".blue()
         );
 
-        let mut answers: Vec<AnswerItem> = Vec::new();
-        for q in questions {
-            println!("\n{} {}", q.id.bright_white().bold(), q.text.white());
-            if let Some(opts) = &q.options
-                && !opts.is_empty() {
-                    println!("{} {:?}", "options:".white(), opts);
-                }
+        for (number, q) in (1..).zip(questions) {
+            status!(self);
+            self.display_question(number, q);
+            if let Some(answer) = answers.get(&q.id) {
+                status!(
+                    self,
+                    "   {} {}",
+                    "answer from file:".truecolor(150, 150, 150),
+                    answer
+                );
+                continue;
+            }
 
-            let prompt = format!("Your answer for {}: ", q.id);
-            let input = super::input::prompt_user(&prompt).await?;
+            let prompt = format!("Your answer for {} ({}): ", number, q.id);
+            let input = self.prompt(&prompt).await?;
 
             if input.is_empty() {
                 continue;
@@ -37,81 +85,542 @@ impl Console {
             if super::input::is_quit_command(&input) || input.eq_ignore_ascii_case("/proceed") {
                 break;
             }
+            if input.eq_ignore_ascii_case("/cancel") {
+                return Ok(AnswerControl::Cancel);
+            }
+            if let Some(request) = super::input::command_argument(&input, "/restart") {
+                let request = if request.is_empty() {
+                    self.prompt("💬 Enter your new technical task request: ")
+                        .await?
+                } else {
+                    request.to_string()
+                };
+                if !request.is_empty() {
+                    return Ok(AnswerControl::Restart(request));
+                }
+                status!(
+                    self,
+                    "{}",
+                    "⚠️ Restart needs a request; continuing.".bright_yellow()
+                );
+                continue;
+            }
 
-            answers.push(AnswerItem {
-                id: q.id.clone(),
-                answer: input,
-            });
+            answers.insert(q.id.clone(), input);
+        }
+
+        self.review_answers(questions, &mut answers).await?;
+        Ok(AnswerControl::Submit(answers_in_order(questions, &answers)))
+    }
+
+    /// Show the collected answers and let the user correct them until they submit
+    async fn review_answers(
+        &self,
+        questions: &[ClarifyingQuestion],
+        answers: &mut HashMap<String, String>,
+    ) -> Result<()> {
+        loop {
+            status!(self, "\n{}", "📝 Your answers:".bright_cyan().bold());
+            for (number, q) in (1..).zip(questions) {
+                let answer = match answers.get(&q.id) {
+                    Some(answer) => answer.white(),
+                    None => "(skipped)".truecolor(150, 150, 150),
+                };
+                status!(self, "{}. ({}) {}", number, q.id, answer);
+            }
+
+            let input = self
+                .prompt(
+                    "Type '/edit <id>' to change an answer, or press Enter / '/submit' to send: ",
+                )
+                .await?;
+            if input.is_empty() || input.eq_ignore_ascii_case("/submit") {
+                return Ok(());
+            }
+
+            let Some(target) = super::input::command_argument(&input, "/edit") else {
+                status!(
+                    self,
+                    "{}",
+                    "⚠️ Use '/edit <id>' or '/submit'.".bright_yellow()
+                );
+                continue;
+            };
+            let Some((number, q)) = find_question(questions, target) else {
+                status!(
+                    self,
+                    "{} '{}'",
+                    "⚠️ No question with id or number".bright_yellow(),
+                    target
+                );
+                continue;
+            };
+
+            status!(self);
+            self.display_question(number, q);
+            let prompt = format!("New answer for {} ({}, Enter = skip): ", number, q.id);
+            let answer = self.prompt(&prompt).await?;
+            if answer.is_empty() {
+                answers.remove(&q.id);
+            } else {
+                answers.insert(q.id.clone(), answer);
+            }
+        }
+    }
+
+    /// Print one clarifying question as a numbered form entry: `1. (q1) text *`, where the
+    /// red asterisk marks a required question
+    fn display_question(&self, number: usize, q: &ClarifyingQuestion) {
+        let marker = if q.required {
+            format!(" {}", "*".bright_red().bold())
+        } else {
+            String::new()
+        };
+        status!(
+            self,
+            "{} {} {}{}",
+            format!("{}.", number).bright_white().bold(),
+            format!("({})", q.id).truecolor(150, 150, 150),
+            q.text.white(),
+            marker
+        );
+        if let Some(opts) = &q.options
+            && !opts.is_empty()
+        {
+            status!(self, "   {} {}", "options:".white(), opts.join(" | "));
+        }
+    }
+
+    /// Print the checklist, hiding complete fields unless `--show-complete` was given
+    fn display_checklist(&self, checklist: &[ChecklistItem]) {
+        status!(self, "\n{}", "🧾 Checklist:".bright_cyan().bold());
+        let (shown, hidden) = visible_checklist(checklist, self.options.show_complete);
+        for item in shown {
+            status!(self, "- {} [{}]", item.field.white(), item.status.green());
+        }
+        if hidden > 0 {
+            status!(
+                self,
+                "{}",
+                format!(
+                    "  ({} complete field(s) hidden; use --show-complete)",
+                    hidden
+                )
+                .truecolor(150, 150, 150)
+            );
         }
+    }
 
-        Ok(AnswersPayload { answers })
+    /// Token the final artifact must end with: `--end-token`, or `【END】` by default
+    fn end_token(&self) -> &str {
+        self.options.end_token.as_deref().unwrap_or(END_TOKEN)
     }
 
-    /// Run TaskFinisher-JSON interactive flow.
+    /// Prompt for a line of input, on stderr when `--json` keeps stdout for the artifact
+    async fn prompt(&self, prompt_text: &str) -> Result<String> {
+        if self.options.json {
+            super::input::prompt_user_on_stderr(prompt_text).await
+        } else {
+            super::input::prompt_user(prompt_text).await
+        }
+    }
+
+    /// Load a previous session from the configured session file, warning and starting
+    /// fresh when the file cannot be read
+    fn load_session(&self) -> Option<TaskFinisherSession> {
+        let path = self.options.session_file.as_ref()?;
+        match TaskFinisherSession::load(path) {
+            Ok(session) => session.filter(|s| !s.history.is_empty()),
+            Err(e) => {
+                status!(
+                    self,
+                    "{} {:#}. Starting a fresh session.",
+                    "⚠️ Ignoring session file:".bright_yellow(),
+                    e
+                );
+                None
+            }
+        }
+    }
+
+    /// Persist the conversation, including the latest assistant reply, to the session file
+    fn save_session(&self, history: &[ChatMessage], raw: &str, round: u32) {
+        let Some(path) = &self.options.session_file else {
+            return;
+        };
+        let mut history = history.to_vec();
+        history.push(ChatMessage {
+            role: "assistant".to_string(),
+            content: raw.to_string(),
+        });
+        let session = TaskFinisherSession { round, history };
+        if let Err(e) = session.save(path) {
+            status!(
+                self,
+                "{} {:#}",
+                "⚠️ Could not save session:".bright_yellow(),
+                e
+            );
+        }
+    }
+
+    /// Open the `--checklist-log` destination; `-` means stdout
+    fn open_checklist_log(path: &Path) -> Result<Box<dyn Write>> {
+        if path == Path::new("-") {
+            return Ok(Box::new(io::stdout()));
+        }
+        let file = File::create(path)
+            .with_context(|| format!("Failed to create checklist log {}", path.display()))?;
+        Ok(Box::new(file))
+    }
+
+    /// Build the opening system + user messages for a fresh TaskFinisher session
+    pub(crate) fn initial_history(
+        max_questions: u32,
+        end_token: &str,
+        user_prompt: &str,
+    ) -> Vec<ChatMessage> {
+        vec![
+            ChatMessage {
+                role: "system".to_string(),
+                content: build_system_prompt(max_questions, end_token),
+            },
+            ChatMessage {
+                role: "user".to_string(),
+                content: format!(
+                    "Describe the result to collect and provide the answer accordingly. Example domain: technical specifications. User request: {}",
+                    user_prompt
+                ),
+            },
+        ]
+    }
+
+    /// Run TaskFinisher-JSON interactive flow and show how it ended: the artifact (also
+    /// exported when configured), or the last reply when no artifact was produced within
+    /// `max_rounds` rounds of clarifying questions.
     pub async fn run_taskfinisher(
         &self,
         initial_prompt: Option<&str>,
         max_questions: u32,
+        max_rounds: u32,
+    ) -> Result<TaskFinisherOutput> {
+        let (output, previous) = self
+            .collect_taskfinisher_output(initial_prompt, max_questions, max_rounds)
+            .await?;
+        self.display_taskfinisher_output(&output, previous.as_deref())?;
+        Ok(output)
+    }
+
+    /// Show the end of a TaskFinisher run. When the session file held an earlier artifact,
+    /// a new artifact is followed by what changed since then.
+    fn display_taskfinisher_output(
+        &self,
+        output: &TaskFinisherOutput,
+        previous: Option<&TechnicalTaskArtifact>,
     ) -> Result<()> {
+        match output {
+            TaskFinisherOutput::Artifact(artifact) => {
+                if let Err(violations) = validate_artifact(artifact, self.end_token()) {
+                    status!(
+                        self,
+                        "\n{}",
+                        "⚠️ The artifact has integrity problems:"
+                            .bright_yellow()
+                            .bold()
+                    );
+                    for violation in &violations {
+                        status!(self, "  {} {}", "•".yellow(), violation.yellow());
+                    }
+                }
+                if self.options.json {
+                    println!("{}", serde_json::to_string_pretty(artifact)?);
+                } else {
+                    self.display_taskfinisher_artifact(artifact);
+                }
+                if let Some(previous) = previous {
+                    self.display_artifact_diff(&diff_artifacts(previous, artifact));
+                }
+                if let Some(path) = &self.options.export_md {
+                    super::export::write_export(
+                        path,
+                        &artifact_to_markdown(artifact),
+                        "Markdown",
+                        self.options.open_exports,
+                    )?;
+                }
+            }
+            TaskFinisherOutput::MaxRoundsReached(raw) => self.display_max_rounds_reply(raw)?,
+            TaskFinisherOutput::ParseFailed { error, raw } => {
+                status!(self, "{} {}", "❌ Parse error:".bright_red().bold(), error);
+                status!(self, "{}", pretty_print_json(raw));
+            }
+            TaskFinisherOutput::Cancelled => {
+                status!(self, "{}", "🛑 TaskFinisher cancelled.".bright_yellow());
+            }
+        }
+        Ok(())
+    }
+
+    /// Send the conversation to the TaskFinisher model and save the session with its reply
+    async fn request_taskfinisher_reply(
+        &self,
+        history: &[ChatMessage],
+        round: u32,
+    ) -> Result<String> {
+        let raw = with_loading_spinner(
+            self.client
+                .send_messages_raw_with_model(history.to_vec(), &self.client.taskfinisher_model()),
+        )
+        .await
+        .map_err(|e| anyhow::anyhow!(e.to_string()))?;
+        self.save_session(history, &raw, round);
+        self.display_token_budget();
+        Ok(raw)
+    }
+
+    /// Make the most of the last reply when the rounds ran out: the checklist and the fields
+    /// still open for more questions, a possibly unfinished artifact with a warning, or the
+    /// raw reply when it does not parse at all
+    fn display_max_rounds_reply(&self, raw: &str) -> Result<()> {
+        status!(
+            self,
+            "{}",
+            "⚠️ Reached maximum clarification rounds.".bright_yellow()
+        );
+        match parse_taskfinisher_response_lenient(raw) {
+            Ok((TaskFinisherResult::Clarifying(payload, _), _)) => {
+                self.display_checklist(&payload.checklist);
+                let remaining: Vec<String> = incomplete_fields(&payload.checklist)
+                    .iter()
+                    .map(|item| format!("{} ({})", item.field, item.status))
+                    .collect();
+                if remaining.is_empty() {
+                    status!(
+                        self,
+                        "{}",
+                        "All checklist fields are complete; run again to get the artifact.".green()
+                    );
+                } else {
+                    status!(
+                        self,
+                        "{} {}",
+                        "Still missing:".bright_yellow().bold(),
+                        remaining.join(", ")
+                    );
+                }
+            }
+            Ok((TaskFinisherResult::Artifact(artifact, _), _)) => {
+                if !artifact.status.trim().eq_ignore_ascii_case("final") {
+                    status!(
+                        self,
+                        "{}",
+                        format!("⚠ incomplete (status: {})", artifact.status)
+                            .bright_yellow()
+                            .bold()
+                    );
+                }
+                if self.options.json {
+                    println!("{}", serde_json::to_string_pretty(&artifact)?);
+                } else {
+                    self.display_taskfinisher_artifact(&artifact);
+                }
+            }
+            Err(_) => {
+                status!(
+                    self,
+                    "{}",
+                    "Showing latest assistant output.".bright_yellow()
+                );
+                status!(self, "{}", pretty_print_json(raw));
+            }
+        }
+        Ok(())
+    }
+
+    /// Print the changes between the previous and the regenerated artifact
+    fn display_artifact_diff(&self, diff: &ArtifactDiff) {
+        status!(
+            self,
+            "\n{}",
+            "🔀 Changes since the previous artifact:"
+                .bright_cyan()
+                .bold()
+        );
+        if diff.is_empty() {
+            status!(self, "  {}", "(no changes)".truecolor(150, 150, 150));
+        }
+        for change in &diff.changes {
+            match change {
+                ArtifactChange::Added { field, item } => {
+                    status!(self, "  {}", format!("+ {}: {}", field, item).green())
+                }
+                ArtifactChange::Removed { field, item } => {
+                    status!(self, "  {}", format!("- {}: {}", field, item).red())
+                }
+                ArtifactChange::Changed { field, old, new } => {
+                    status!(self, "  {}", format!("~ {}:", field).yellow());
+                    status!(self, "    {}", format!("- {}", old).red());
+                    status!(self, "    {}", format!("+ {}", new).green());
+                }
+            }
+        }
+    }
+
+    /// Ask and answer clarifying questions until the model returns the artifact, the round
+    /// limit is reached, or a reply cannot be parsed. Also returns the artifact a finished
+    /// session in the session file ended with, which this run regenerates.
+    async fn collect_taskfinisher_output(
+        &self,
+        initial_prompt: Option<&str>,
+        max_questions: u32,
+        max_rounds: u32,
+    ) -> Result<(TaskFinisherOutput, Option<Box<TechnicalTaskArtifact>>)> {
         let max_q = if max_questions == 0 {
             crate::taskfinisher::DEFAULT_MAX_QUESTIONS
         } else {
             max_questions
         };
-        println!("{}", "🤖 TaskFinisher-JSON Mode".bright_blue().bold());
-        println!("{} {}", "Max clarifying questions:".blue(), max_q);
+        let max_rounds = max_rounds.max(1);
+        status!(self, "{}", "🤖 TaskFinisher-JSON Mode".bright_blue().bold());
+        status!(self, "{} {}", "Max clarifying questions:".blue(), max_q);
+        status!(
+            self,
+            "{} {}",
+            "Max clarification rounds:".blue(),
+            max_rounds
+        );
 
-        let user_prompt = if let Some(p) = initial_prompt {
-            p.to_string()
+        // A session that already ended with an artifact is run again from its opening request
+        let mut previous = None;
+        let mut opening = None;
+        let resumed = self
+            .load_session()
+            .and_then(|session| match session.final_artifact() {
+                Some(artifact) => {
+                    previous = Some(artifact);
+                    opening = Some(session.opening_messages().to_vec());
+                    None
+                }
+                None => Some(session),
+            });
+        let (mut history, mut round) = if let Some(session) = resumed {
+            status!(
+                self,
+                "{} ({} messages, round {})",
+                "♻️ Resuming saved TaskFinisher session".bright_green(),
+                session.history.len(),
+                session.round
+            );
+            (session.history, session.round)
+        } else if let (None, Some(opening)) = (initial_prompt, opening) {
+            status!(
+                self,
+                "{}",
+                "♻️ Regenerating the finished TaskFinisher session".bright_green()
+            );
+            (opening, 1u32)
         } else {
-            super::input::prompt_user("💬 Enter your technical task request: ").await?
+            let user_prompt = if let Some(p) = initial_prompt {
+                p.to_string()
+            } else {
+                self.prompt("💬 Enter your technical task request: ")
+                    .await?
+            };
+            (
+                Self::initial_history(max_q, self.end_token(), &user_prompt),
+                1u32,
+            )
         };
 
-        let system_prompt = build_system_prompt(max_q);
-        let mut history: Vec<ChatMessage> = vec![
-            ChatMessage { role: "system".to_string(), content: system_prompt.clone() },
-            ChatMessage { role: "user".to_string(), content: format!(
-                "Describe the result to collect and provide the answer accordingly. Example domain: technical specifications. User request: {}",
-                user_prompt
-            )},
-        ];
-
-        println!("{}", "🔄 Sending TaskFinisher request...".blue().italic());
-        let mut raw = self
-            .client
-            .send_messages_raw(history.clone())
-            .await
-            .map_err(|e| anyhow::anyhow!(e.to_string()))?;
+        // Continue from the last assistant reply when resuming; otherwise ask the model
+        let resumed_reply = match history.last() {
+            Some(last) if last.role == "assistant" => history.pop(),
+            _ => None,
+        };
+        let mut raw = match resumed_reply {
+            Some(reply) => reply.content,
+            None => {
+                status!(
+                    self,
+                    "{}",
+                    "🔄 Sending TaskFinisher request...".blue().italic()
+                );
+                self.request_taskfinisher_reply(&history, round).await?
+            }
+        };
 
-        let max_rounds = 5u32;
-        let mut round = 1u32;
+        let mut question_log = QuestionLog::default();
+        let mut outcome = TaskFinisherSummary {
+            artifact_produced: false,
+            completeness_percent: 0.0,
+        };
+        let mut checklist_log = self
+            .options
+            .checklist_log
+            .as_deref()
+            .map(Self::open_checklist_log)
+            .transpose()?;
 
-        loop {
+        let output = loop {
             match parse_taskfinisher_response(&raw) {
                 Ok(TaskFinisherResult::Artifact(artifact, _)) => {
-                    super::render::display_taskfinisher_artifact(&artifact);
-                    break;
+                    outcome = TaskFinisherSummary {
+                        artifact_produced: true,
+                        completeness_percent: artifact_completeness(&artifact),
+                    };
+                    break TaskFinisherOutput::Artifact(artifact);
+                }
+                // Only a reply still asking questions after the last round runs out of rounds
+                Ok(TaskFinisherResult::Clarifying(payload, _)) if round > max_rounds => {
+                    outcome.completeness_percent = checklist_completion(&payload.checklist);
+                    break TaskFinisherOutput::MaxRoundsReached(raw);
                 }
                 Ok(TaskFinisherResult::Clarifying(payload, _)) => {
-                    println!(
+                    status!(
+                        self,
                         "\n{} (round {})",
                         "❓ Clarifying Questions:".bright_yellow().bold(),
                         round
                     );
-                    for q in &payload.questions {
-                        println!("- {} {}", q.id.bright_white().bold(), q.text.white());
-                        if let Some(opts) = &q.options {
-                            println!("  options: {:?}", opts);
-                        }
+                    for (number, q) in (1..).zip(&payload.questions) {
+                        self.display_question(number, q);
                     }
-                    println!("\n{}", "🧾 Checklist:".bright_cyan().bold());
-                    for item in &payload.checklist {
-                        println!("- {} [{}]", item.field.white(), item.status.green());
+                    self.display_checklist(&payload.checklist);
+                    outcome.completeness_percent = checklist_completion(&payload.checklist);
+                    if let Some(log) = checklist_log.as_mut() {
+                        let progress = ChecklistProgress::new(round, &payload.checklist);
+                        writeln!(log, "{}", serde_json::to_string(&progress)?)
+                            .and_then(|_| log.flush())
+                            .context("Failed to write checklist log")?;
                     }
-                    println!("\n{}", "💬 Enter answers one-by-one below (Enter = skip, '/proceed' = finalize now).".blue());
+                    status!(self, "\n{}", "💬 Enter answers one-by-one below (Enter = skip, '/proceed' = finalize now).".blue());
 
-                    let answers_payload =
-                        Self::collect_answers_interactively(&payload.questions).await?;
+                    let answers_payload = match self
+                        .collect_answers_interactively(&payload.questions)
+                        .await?
+                    {
+                        AnswerControl::Submit(answers) => answers,
+                        AnswerControl::Cancel => break TaskFinisherOutput::Cancelled,
+                        AnswerControl::Restart(request) => {
+                            status!(
+                                self,
+                                "\n{}",
+                                "🔁 Starting over with the new request".bright_green()
+                            );
+                            history = Self::initial_history(max_q, self.end_token(), &request);
+                            round = 1;
+                            question_log = QuestionLog::default();
+                            outcome.completeness_percent = 0.0;
+                            status!(
+                                self,
+                                "{}",
+                                "🔄 Sending TaskFinisher request...".blue().italic()
+                            );
+                            raw = self.request_taskfinisher_reply(&history, round).await?;
+                            continue;
+                        }
+                    };
+                    question_log.record(round, &payload, &answers_payload);
                     history.push(ChatMessage {
                         role: "assistant".to_string(),
                         content: raw,
@@ -121,28 +630,174 @@ impl Console {
                         content: serde_json::to_string(&answers_payload).unwrap(),
                     });
 
-                    println!("{}", "🔄 Processing answers...".blue().italic());
-                    raw = self
-                        .client
-                        .send_messages_raw(history.clone())
-                        .await
-                        .map_err(|e| anyhow::anyhow!(e.to_string()))?;
-
+                    status!(self, "{}", "🔄 Processing answers...".blue().italic());
                     round += 1;
-                    if round > max_rounds {
-                        println!("{}", "⚠️ Reached maximum clarification rounds. Showing latest assistant output.".bright_yellow());
-                        println!("{}", raw);
-                        break;
-                    }
-                }
-                Err(e) => {
-                    println!("{} {}", "❌ Parse error:".bright_red().bold(), e);
-                    println!("{}", raw);
-                    break;
+                    raw = self.request_taskfinisher_reply(&history, round).await?;
                 }
+                Err(error) => break TaskFinisherOutput::ParseFailed { error, raw },
             }
+        };
+
+        if let Ok(mut stored) = self.taskfinisher_outcome.lock() {
+            *stored = Some(outcome);
         }
 
-        Ok(())
+        if let Some(path) = &self.options.question_log {
+            let json = serde_json::to_string_pretty(&question_log)?;
+            super::export::write_export(path, &json, "question log", self.options.open_exports)?;
+        }
+
+        Ok((output, previous))
+    }
+}
+
+/// What the user chose while answering a round of clarifying questions
+enum AnswerControl {
+    /// Send these answers (possibly none, after `/proceed`)
+    Submit(AnswersPayload),
+    /// Discard the session and start over with a new request
+    Restart(String),
+    /// Stop the run without an artifact
+    Cancel,
+}
+
+/// The question an `/edit` target refers to, by id or by its number in the form
+fn find_question<'a>(
+    questions: &'a [ClarifyingQuestion],
+    target: &str,
+) -> Option<(usize, &'a ClarifyingQuestion)> {
+    (1..).zip(questions).find(|(number, q)| {
+        q.id.eq_ignore_ascii_case(target) || target.parse::<usize>() == Ok(*number)
+    })
+}
+
+/// Answers from the `--answers` file for the questions of this round, by id
+fn preset_answers(
+    preset: Option<&AnswersPayload>,
+    questions: &[ClarifyingQuestion],
+) -> HashMap<String, String> {
+    let Some(preset) = preset else {
+        return HashMap::new();
+    };
+    preset
+        .answers
+        .iter()
+        .filter(|item| questions.iter().any(|q| q.id == item.id))
+        .map(|item| (item.id.clone(), item.answer.clone()))
+        .collect()
+}
+
+/// Answers payload in question order, leaving out skipped questions
+fn answers_in_order(
+    questions: &[ClarifyingQuestion],
+    answers: &HashMap<String, String>,
+) -> AnswersPayload {
+    let answers = questions
+        .iter()
+        .filter_map(|q| {
+            answers.get(&q.id).map(|answer| AnswerItem {
+                id: q.id.clone(),
+                answer: answer.clone(),
+            })
+        })
+        .collect();
+    AnswersPayload { answers }
+}
+
+/// Checklist items to display and the number of complete ones left out
+fn visible_checklist(
+    checklist: &[ChecklistItem],
+    show_complete: bool,
+) -> (Vec<&ChecklistItem>, usize) {
+    let (complete, pending): (Vec<_>, Vec<_>) = checklist
+        .iter()
+        .partition(|item| item.status.eq_ignore_ascii_case("complete"));
+    if show_complete {
+        (checklist.iter().collect(), 0)
+    } else {
+        (pending, complete.len())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn item(field: &str, status: &str) -> ChecklistItem {
+        ChecklistItem {
+            field: field.to_string(),
+            status: status.to_string(),
+        }
+    }
+
+    fn question(id: &str) -> ClarifyingQuestion {
+        ClarifyingQuestion {
+            id: id.to_string(),
+            text: format!("Question {}", id),
+            required: true,
+            options: None,
+        }
+    }
+
+    #[test]
+    fn edited_answers_are_sent_in_question_order() {
+        let questions = vec![question("q1"), question("q2"), question("q3")];
+        let mut answers = HashMap::new();
+        answers.insert("q3".to_string(), "typo".to_string());
+        answers.insert("q1".to_string(), "first".to_string());
+
+        let (number, target) = find_question(&questions, "Q3").unwrap();
+        assert_eq!(number, 3);
+        answers.insert(target.id.clone(), "fixed".to_string());
+        assert_eq!(find_question(&questions, "2").unwrap().1.id, "q2");
+        assert!(find_question(&questions, "q9").is_none());
+
+        let payload = answers_in_order(&questions, &answers);
+        let sent: Vec<(&str, &str)> = payload
+            .answers
+            .iter()
+            .map(|a| (a.id.as_str(), a.answer.as_str()))
+            .collect();
+        assert_eq!(sent, [("q1", "first"), ("q3", "fixed")]);
+    }
+
+    #[test]
+    fn preset_answers_cover_only_asked_questions() {
+        let questions = vec![question("q1"), question("q2")];
+        let preset = AnswersPayload {
+            answers: vec![
+                AnswerItem {
+                    id: "q2".to_string(),
+                    answer: "Postgres".to_string(),
+                },
+                AnswerItem {
+                    id: "q7".to_string(),
+                    answer: "unused".to_string(),
+                },
+            ],
+        };
+
+        let answers = preset_answers(Some(&preset), &questions);
+        assert_eq!(answers.len(), 1);
+        assert_eq!(answers["q2"], "Postgres");
+        assert!(preset_answers(None, &questions).is_empty());
+    }
+
+    #[test]
+    fn complete_fields_are_hidden_unless_requested() {
+        let checklist = vec![
+            item("goal", "complete"),
+            item("stack", "missing"),
+            item("deadline", "partial"),
+        ];
+
+        let (shown, hidden) = visible_checklist(&checklist, false);
+        let fields: Vec<&str> = shown.iter().map(|i| i.field.as_str()).collect();
+        assert_eq!(fields, ["stack", "deadline"]);
+        assert_eq!(hidden, 1);
+
+        let (shown, hidden) = visible_checklist(&checklist, true);
+        assert_eq!(shown.len(), 3);
+        assert_eq!(hidden, 0);
     }
 }
@@ -0,0 +1,220 @@
+use anyhow::{Context, Result};
+use colored::{Color, ColoredString, Colorize};
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::path::Path;
+
+/// Built-in color themes, selected with `--theme`
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum ThemeName {
+    /// No colors, for colorblind users or terminals that render colors poorly
+    Plain,
+    /// Bright colors for dark terminal backgrounds
+    #[default]
+    Dark,
+    /// Darker colors that stay readable on light backgrounds
+    Light,
+}
+
+/// Colors for the semantic roles of rendered responses and artifacts.
+/// A role without a color is printed in the terminal's default color.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Theme {
+    /// Titles and IDs
+    pub title: Option<Color>,
+    /// Section headings
+    pub heading: Option<Color>,
+    /// Field labels, bullets and box borders
+    pub label: Option<Color>,
+    /// Field values
+    pub value: Option<Color>,
+    /// Secondary text: empty sections, timing, reasoning
+    pub muted: Option<Color>,
+    /// Risks, out-of-scope items and open questions
+    pub warning: Option<Color>,
+    /// Box headers, in-scope items and mitigations
+    pub success: Option<Color>,
+}
+
+/// Roles that can be set in the `[theme]` section of a theme file
+const ROLES: &[&str] = &[
+    "title", "heading", "label", "value", "muted", "warning", "success",
+];
+
+/// Grey used for secondary text in the dark theme
+const GREY: Color = Color::TrueColor {
+    r: 150,
+    g: 150,
+    b: 150,
+};
+
+impl Default for Theme {
+    fn default() -> Self {
+        Self::named(ThemeName::default())
+    }
+}
+
+impl Theme {
+    /// One of the built-in themes
+    pub fn named(name: ThemeName) -> Self {
+        match name {
+            ThemeName::Plain => Self {
+                title: None,
+                heading: None,
+                label: None,
+                value: None,
+                muted: None,
+                warning: None,
+                success: None,
+            },
+            ThemeName::Dark => Self {
+                title: Some(Color::BrightWhite),
+                heading: Some(Color::BrightCyan),
+                label: Some(Color::Green),
+                value: Some(Color::White),
+                muted: Some(GREY),
+                warning: Some(Color::BrightYellow),
+                success: Some(Color::BrightGreen),
+            },
+            ThemeName::Light => Self {
+                title: Some(Color::Black),
+                heading: Some(Color::Blue),
+                label: Some(Color::Green),
+                value: Some(Color::Black),
+                muted: Some(Color::BrightBlack),
+                warning: Some(Color::Magenta),
+                success: Some(Color::Green),
+            },
+        }
+    }
+
+    /// Load the `[theme]` section of a TOML file on top of `base`. Each key is a role and
+    /// each value a color name such as `"bright blue"`, or `"none"` for no color:
+    ///
+    /// ```toml
+    /// [theme]
+    /// title = "bright blue"
+    /// warning = "red"
+    /// ```
+    pub fn load(path: &Path, base: Theme) -> Result<Self> {
+        #[derive(Deserialize)]
+        struct ThemeFile {
+            #[serde(default)]
+            theme: BTreeMap<String, String>,
+        }
+
+        let data = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read theme file {}", path.display()))?;
+        let file: ThemeFile = toml::from_str(&data)
+            .with_context(|| format!("Invalid theme file {}", path.display()))?;
+
+        let mut theme = base;
+        for (role, color) in &file.theme {
+            let color = parse_color(color)
+                .with_context(|| format!("Invalid color for '{}' in {}", role, path.display()))?;
+            match role.as_str() {
+                "title" => theme.title = color,
+                "heading" => theme.heading = color,
+                "label" => theme.label = color,
+                "value" => theme.value = color,
+                "muted" => theme.muted = color,
+                "warning" => theme.warning = color,
+                "success" => theme.success = color,
+                _ => anyhow::bail!(
+                    "Unknown theme role '{}' in {} (expected one of: {})",
+                    role,
+                    path.display(),
+                    ROLES.join(", ")
+                ),
+            }
+        }
+        Ok(theme)
+    }
+
+    pub fn title(&self, text: &str) -> ColoredString {
+        paint(text, self.title).bold()
+    }
+
+    pub fn heading(&self, text: &str) -> ColoredString {
+        paint(text, self.heading).bold()
+    }
+
+    pub fn label(&self, text: &str) -> ColoredString {
+        paint(text, self.label)
+    }
+
+    pub fn value(&self, text: &str) -> ColoredString {
+        paint(text, self.value)
+    }
+
+    pub fn muted(&self, text: &str) -> ColoredString {
+        paint(text, self.muted)
+    }
+
+    pub fn warning(&self, text: &str) -> ColoredString {
+        paint(text, self.warning)
+    }
+
+    pub fn success(&self, text: &str) -> ColoredString {
+        paint(text, self.success)
+    }
+}
+
+fn paint(text: &str, color: Option<Color>) -> ColoredString {
+    match color {
+        Some(color) => text.color(color),
+        None => text.normal(),
+    }
+}
+
+/// Parse a color name as accepted by `colored` (`"red"`, `"bright blue"`, ...), or `"none"`
+fn parse_color(name: &str) -> Result<Option<Color>> {
+    if name.eq_ignore_ascii_case("none") {
+        return Ok(None);
+    }
+    name.parse()
+        .map(Some)
+        .map_err(|_| anyhow::anyhow!("unknown color '{}'", name))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn write_theme_file(name: &str, contents: &str) -> std::path::PathBuf {
+        let path = std::env::temp_dir().join(format!(
+            "deepseek_json_theme_{}_{}.toml",
+            std::process::id(),
+            name
+        ));
+        std::fs::write(&path, contents).unwrap();
+        path
+    }
+
+    #[test]
+    fn theme_file_overrides_roles_of_the_base_theme() {
+        let path = write_theme_file(
+            "override",
+            "[theme]\ntitle = \"bright blue\"\nwarning = \"none\"\n",
+        );
+        let theme = Theme::load(&path, Theme::named(ThemeName::Light)).unwrap();
+        std::fs::remove_file(&path).ok();
+
+        assert_eq!(theme.title, Some(Color::BrightBlue));
+        assert_eq!(theme.warning, None);
+        assert_eq!(theme.label, Theme::named(ThemeName::Light).label);
+    }
+
+    #[test]
+    fn theme_file_rejects_unknown_roles_and_colors() {
+        let path = write_theme_file("role", "[theme]\nborder = \"red\"\n");
+        let err = Theme::load(&path, Theme::default()).unwrap_err();
+        std::fs::remove_file(&path).ok();
+        assert!(err.to_string().contains("Unknown theme role 'border'"));
+
+        let path = write_theme_file("color", "[theme]\ntitle = \"chartreuse\"\n");
+        let err = Theme::load(&path, Theme::default()).unwrap_err();
+        std::fs::remove_file(&path).ok();
+        assert!(format!("{:#}", err).contains("unknown color 'chartreuse'"));
+    }
+}
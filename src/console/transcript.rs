@@ -0,0 +1,179 @@
+use anyhow::{Context, Result};
+use chrono::Utc;
+use colored::*;
+use serde::{Deserialize, Serialize};
+use std::fs::{File, OpenOptions};
+use std::io::Write;
+use std::path::Path;
+
+use crate::deepseek::{DeepSeekResponse, parse_response};
+use crate::taskfinisher::TechnicalTaskArtifact;
+
+use super::render;
+use super::theme::Theme;
+
+/// One line of the `--transcript` file
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TranscriptRecord {
+    /// `user` for a question, `assistant` for its answer
+    pub role: String,
+    /// The question, or the answer as JSON text
+    pub content: String,
+    /// When the line was written (RFC 3339)
+    pub timestamp: String,
+}
+
+/// A saved answer, in the shape it was displayed in
+#[derive(Debug)]
+enum SavedAnswer {
+    Artifact(Box<TechnicalTaskArtifact>),
+    Choices(Vec<DeepSeekResponse>),
+    Response(DeepSeekResponse),
+    Raw,
+}
+
+impl SavedAnswer {
+    fn parse(content: &str) -> Self {
+        if let Ok(artifact) = serde_json::from_str::<TechnicalTaskArtifact>(content) {
+            return Self::Artifact(Box::new(artifact));
+        }
+        if let Ok(responses) = serde_json::from_str::<Vec<DeepSeekResponse>>(content) {
+            return Self::Choices(responses);
+        }
+        match parse_response(content, &[]) {
+            Ok(response) => Self::Response(response),
+            Err(_) => Self::Raw,
+        }
+    }
+}
+
+/// Open the transcript for appending, creating it when missing
+pub(super) fn open_transcript(path: &Path) -> Result<File> {
+    OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .with_context(|| format!("Failed to open transcript {}", path.display()))
+}
+
+/// Append a question and its answer as two JSON lines, flushed so a crash keeps them
+pub(super) fn record_turn(writer: &mut impl Write, question: &str, answer: &str) -> Result<()> {
+    for (role, content) in [("user", question), ("assistant", answer)] {
+        let entry = TranscriptRecord {
+            role: role.to_string(),
+            content: content.to_string(),
+            timestamp: Utc::now().to_rfc3339(),
+        };
+        writeln!(writer, "{}", serde_json::to_string(&entry)?)?;
+    }
+    writer.flush().context("Failed to write transcript")
+}
+
+/// Read all records of a transcript file, skipping blank lines
+pub fn read_transcript(path: &Path) -> Result<Vec<TranscriptRecord>> {
+    let text = std::fs::read_to_string(path)
+        .with_context(|| format!("Failed to read transcript {}", path.display()))?;
+    text.lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty())
+        .map(|(index, line)| {
+            serde_json::from_str(line).with_context(|| {
+                format!(
+                    "Line {} of {} is not a transcript record",
+                    index + 1,
+                    path.display()
+                )
+            })
+        })
+        .collect()
+}
+
+/// Show the questions and answers of a saved transcript as the console did, without
+/// calling the API. Returns the number of records shown.
+pub fn replay_transcript(path: &Path, theme: &Theme, overconfident_above: f32) -> Result<usize> {
+    let records = read_transcript(path)?;
+    for record in &records {
+        if record.role != "assistant" {
+            println!(
+                "{}{}",
+                "💬 Enter your question: ".bright_cyan().bold(),
+                record.content
+            );
+            continue;
+        }
+
+        match SavedAnswer::parse(&record.content) {
+            SavedAnswer::Artifact(artifact) => {
+                render::display_taskfinisher_artifact(&artifact, theme)
+            }
+            SavedAnswer::Choices(responses) => {
+                for (number, response) in (1..).zip(&responses) {
+                    render::display_choice_header(number, responses.len(), theme);
+                    render::display_response(response, theme, overconfident_above);
+                }
+            }
+            SavedAnswer::Response(response) => {
+                render::display_response(&response, theme, overconfident_above)
+            }
+            SavedAnswer::Raw => render::display_raw_reply(&record.content, theme),
+        }
+    }
+    Ok(records.len())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn turn_is_written_as_two_json_lines() {
+        let mut buffer = Vec::new();
+        record_turn(&mut buffer, "What is Rust?", r#"{"title":"Rust"}"#).unwrap();
+
+        let text = String::from_utf8(buffer).unwrap();
+        let lines: Vec<serde_json::Value> = text
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        assert_eq!(lines.len(), 2);
+        assert_eq!(lines[0]["role"], "user");
+        assert_eq!(lines[0]["content"], "What is Rust?");
+        assert_eq!(lines[1]["role"], "assistant");
+        assert!(lines[1]["timestamp"].is_string());
+    }
+
+    #[test]
+    fn recorded_turns_read_back_as_records() {
+        let path = std::env::temp_dir().join(format!(
+            "deepseek_transcript_replay_{}.jsonl",
+            std::process::id()
+        ));
+        let mut file = File::create(&path).unwrap();
+        record_turn(&mut file, "q1", r#"{"n":1}"#).unwrap();
+        writeln!(file).unwrap();
+        record_turn(&mut file, "q2", "plain text").unwrap();
+
+        let records = read_transcript(&path).unwrap();
+        std::fs::remove_file(&path).ok();
+        let roles: Vec<&str> = records.iter().map(|r| r.role.as_str()).collect();
+        assert_eq!(roles, ["user", "assistant", "user", "assistant"]);
+        assert_eq!(records[2].content, "q2");
+        assert!(matches!(
+            SavedAnswer::parse(&records[3].content),
+            SavedAnswer::Raw
+        ));
+    }
+
+    #[test]
+    fn saved_answers_are_shown_in_their_original_shape() {
+        let response = r#"{"title":"T","description":"D","content":"C","category":"x","timestamp":"now","confidence":0.5}"#;
+        assert!(matches!(
+            SavedAnswer::parse(response),
+            SavedAnswer::Response(_)
+        ));
+        assert!(matches!(
+            SavedAnswer::parse(&format!("[{},{}]", response, response)),
+            SavedAnswer::Choices(choices) if choices.len() == 2
+        ));
+    }
+}
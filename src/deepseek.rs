@@ -1,18 +1,40 @@
-use std::time::Duration;
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+use std::future::Future;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, RwLock};
+use std::time::{Duration, Instant};
 
 use anyhow::Result;
 use chrono::Utc;
-use reqwest::{Client, StatusCode};
+use futures::future::{BoxFuture, FutureExt, Shared};
+use regex::Regex;
+use reqwest::Client;
+use reqwest::header::{HeaderMap, HeaderName, HeaderValue};
 use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
 use thiserror::Error;
-
-use crate::config::Config;
+use tokio::sync::Semaphore;
+use tracing::Instrument;
+
+use crate::cache::ResponseCache;
+use crate::config::{
+    API_KEY_HEADER, API_KEY_QUERY_PARAM, AuthStyle, Config, DEFAULT_CHAT_PATH, DEFAULT_USER_AGENT,
+    Mode,
+};
+use crate::extract::{from_str_or_embedded, strip_bom, strip_code_fences};
+use crate::metrics;
+use crate::transport::{ChatTransport, HttpTransport};
 
 /// Custom error types for DeepSeek API interactions
-#[derive(Error, Debug)]
+///
+/// The CLI exits with a distinct code per failure type (see [`crate::cli::exit_code`]):
+/// `2` config, `3` network, `4` timeout, `5` API error or server busy, `6` unparsable, empty or
+/// truncated response, `7` input rejected, `8` token budget exhausted, `1` anything else.
+#[derive(Error, Debug, Clone)]
 pub enum DeepSeekError {
     #[error("DeepSeek servers are currently busy. Please try again in a few moments.")]
-    ServerBusy,
+    ServerBusy { retry_after: Option<Duration> },
 
     #[error("Network connection failed: {message}")]
     NetworkError { message: String },
@@ -21,19 +43,46 @@ pub enum DeepSeekError {
     Timeout { seconds: u64 },
 
     #[error("API error ({status}): {message}")]
-    ApiError { status: u16, message: String },
+    ApiError {
+        status: u16,
+        message: String,
+        /// Error `type` from the JSON error body, e.g. `invalid_request_error`
+        error_type: Option<String>,
+        /// Error `code` from the JSON error body, e.g. `invalid_api_key`
+        code: Option<String>,
+    },
 
     #[error("Failed to parse response: {message}")]
     ParseError { message: String },
 
+    #[error("The model returned an empty response")]
+    EmptyResponse,
+
     #[error("Configuration error: {message}")]
     ConfigError { message: String },
+
+    #[error("Input rejected by deny-list pattern: {pattern}")]
+    InputRejected { pattern: String },
+
+    #[error("Response truncated at the {tokens}-token limit")]
+    Truncated { tokens: u32 },
+
+    #[error("Token budget exhausted: {used} of {budget} tokens used")]
+    BudgetExceeded { used: u64, budget: u64 },
 }
 
 impl DeepSeekError {
     /// Check if the error indicates server is busy
     pub fn is_server_busy(&self) -> bool {
-        matches!(self, DeepSeekError::ServerBusy)
+        matches!(self, DeepSeekError::ServerBusy { .. })
+    }
+
+    /// Server-requested delay before retrying (from the `Retry-After` header), if any
+    pub fn retry_after(&self) -> Option<Duration> {
+        match self {
+            DeepSeekError::ServerBusy { retry_after } => *retry_after,
+            _ => None,
+        }
     }
 
     /// Check if the error is a network-related issue
@@ -41,10 +90,15 @@ impl DeepSeekError {
         matches!(self, DeepSeekError::NetworkError { .. })
     }
 
+    /// Check if the model replied without any content
+    pub fn is_empty_response(&self) -> bool {
+        matches!(self, DeepSeekError::EmptyResponse)
+    }
+
     /// Get user-friendly error message
     pub fn user_message(&self) -> String {
         match self {
-            DeepSeekError::ServerBusy => {
+            DeepSeekError::ServerBusy { .. } => {
                 "🚫 DeepSeek servers are currently busy. Please try again in a few moments."
                     .to_string()
             }
@@ -71,18 +125,163 @@ impl DeepSeekError {
             DeepSeekError::ParseError { .. } => {
                 "⚠️ Failed to parse server response. Please try again.".to_string()
             }
+            DeepSeekError::EmptyResponse => {
+                "📭 The model returned an empty response. Please try again.".to_string()
+            }
             DeepSeekError::ConfigError { message } => {
                 format!("⚙️ Configuration error: {}", message)
             }
+            DeepSeekError::InputRejected { pattern } => {
+                format!(
+                    "⛔ Your input was blocked by the deny-list pattern `{}`.",
+                    pattern
+                )
+            }
+            DeepSeekError::Truncated { tokens } => {
+                format!(
+                    "✂️ The response was cut off at the {}-token limit. Try a higher --max-tokens.",
+                    tokens
+                )
+            }
+            DeepSeekError::BudgetExceeded { used, budget } => {
+                format!(
+                    "💸 Token budget exhausted ({} of {} tokens used). No further requests will be sent.",
+                    used, budget
+                )
+            }
+        }
+    }
+
+    /// Short machine-readable name of the error kind, used in session summaries
+    pub fn kind(&self) -> &'static str {
+        match self {
+            DeepSeekError::ServerBusy { .. } => "server_busy",
+            DeepSeekError::NetworkError { .. } => "network",
+            DeepSeekError::Timeout { .. } => "timeout",
+            DeepSeekError::ApiError { .. } => "api",
+            DeepSeekError::ParseError { .. } => "parse",
+            DeepSeekError::EmptyResponse => "empty_response",
+            DeepSeekError::ConfigError { .. } => "config",
+            DeepSeekError::InputRejected { .. } => "input_rejected",
+            DeepSeekError::Truncated { .. } => "truncated",
+            DeepSeekError::BudgetExceeded { .. } => "budget_exceeded",
+        }
+    }
+
+    /// Check if the error means the session token budget is used up
+    pub fn is_budget_exceeded(&self) -> bool {
+        matches!(self, DeepSeekError::BudgetExceeded { .. })
+    }
+
+    /// Detailed remediation steps for this error, shown on demand (e.g. via `/why`)
+    pub fn explain(&self) -> Vec<String> {
+        match self {
+            DeepSeekError::ServerBusy { retry_after } => {
+                let mut steps = vec![
+                    "The API answered with 429, 500, 502, 503 or 504 on every retry attempt (500 only counts while DEEPSEEK_RETRY_ON_500 is on).".to_string(),
+                ];
+                if let Some(delay) = retry_after {
+                    steps.push(format!(
+                        "The server asked to wait {} seconds before retrying.",
+                        delay.as_secs()
+                    ));
+                }
+                steps.push(
+                    "Raise DEEPSEEK_RETRY_ATTEMPTS or DEEPSEEK_RETRY_BACKOFF_MS to wait longer automatically."
+                        .to_string(),
+                );
+                steps.push("Check https://status.deepseek.com for ongoing incidents.".to_string());
+                steps
+            }
+            DeepSeekError::NetworkError { message } => vec![
+                format!("The request never reached the API: {}.", message),
+                "Verify DEEPSEEK_BASE_URL points to a reachable host.".to_string(),
+                "Check DNS resolution, proxies and firewall rules for outbound HTTPS.".to_string(),
+            ],
+            DeepSeekError::Timeout { seconds } => vec![
+                format!("No complete response arrived within {} seconds.", seconds),
+                "Long answers take longer; lower --max-tokens or raise DEEPSEEK_TIMEOUT / --timeout."
+                    .to_string(),
+                "Persistent timeouts usually mean the service is overloaded.".to_string(),
+            ],
+            DeepSeekError::ApiError {
+                status,
+                message,
+                error_type,
+                code,
+            } => {
+                let mut steps = vec![format!("The API returned HTTP {}: {}", status, message)];
+                if let Some(details) = api_error_details(error_type.as_deref(), code.as_deref()) {
+                    steps.push(format!("Error details: {}.", details));
+                }
+                steps.push(
+                    match *status {
+                        400 => "The request was malformed; check --model, --max-tokens and --temperature.",
+                        401 => "DEEPSEEK_API_KEY is missing, mistyped or revoked.",
+                        402 => "The account balance is insufficient; top it up in the DeepSeek console.",
+                        403 => "The API key is not allowed to use this model or endpoint.",
+                        404 => "The endpoint or model was not found; check DEEPSEEK_BASE_URL and DEEPSEEK_MODEL.",
+                        422 => "A request parameter was rejected; check the configured values.",
+                        _ => "Consult the DeepSeek API documentation for this status code.",
+                    }
+                    .to_string(),
+                );
+                steps
+            }
+            DeepSeekError::ParseError { message } => vec![
+                format!("The response could not be decoded: {}.", message),
+                "The model may have produced invalid JSON; rephrasing the query often helps."
+                    .to_string(),
+                "Gateways other than DeepSeek may use a different schema; try the probe subcommand.".to_string(),
+            ],
+            DeepSeekError::EmptyResponse => vec![
+                "The model answered, but its message content was empty or only whitespace."
+                    .to_string(),
+                "This is usually transient; sending the query again often helps.".to_string(),
+                "Reasoning models can spend the whole limit thinking; raise --max-tokens."
+                    .to_string(),
+            ],
+            DeepSeekError::ConfigError { message } => vec![
+                format!("The configuration is invalid: {}.", message),
+                "Check the DEEPSEEK_* variables in your environment or .env file.".to_string(),
+                "Command-line flags override environment values.".to_string(),
+            ],
+            DeepSeekError::InputRejected { pattern } => vec![
+                format!("Your input matched the deny-list pattern `{}`.", pattern),
+                "The request was not sent to the API.".to_string(),
+                "Ask the operator of this deployment if you think the block is a mistake."
+                    .to_string(),
+            ],
+            DeepSeekError::Truncated { tokens } => vec![
+                format!(
+                    "The model stopped after {} tokens (finish_reason \"length\"), leaving incomplete JSON.",
+                    tokens
+                ),
+                "Raise the limit with --max-tokens or DEEPSEEK_MAX_TOKENS.".to_string(),
+                "Asking for a shorter answer also helps.".to_string(),
+            ],
+            DeepSeekError::BudgetExceeded { used, budget } => vec![
+                format!(
+                    "This session has used {} tokens, reaching its budget of {}.",
+                    used, budget
+                ),
+                "Requests are refused from now on to cap spending.".to_string(),
+                "Start a new session, or raise the limit with --token-budget.".to_string(),
+            ],
         }
     }
 }
 
-/// Define the expected JSON response structure from DeepSeek
+/// Define the expected JSON response structure from DeepSeek.
+/// Missing text fields deserialize as empty; [`parse_response`] decides which are required.
+/// Names some compatible backends use instead are accepted (see [`RESPONSE_FIELD_ALIASES`]).
 #[derive(Debug, Serialize, Deserialize)]
 pub struct DeepSeekResponse {
+    #[serde(default)]
     pub title: String,
+    #[serde(default, alias = "desc")]
     pub description: String,
+    #[serde(default, alias = "text")]
     pub content: String,
     pub category: Option<String>,
     pub timestamp: Option<String>,
@@ -96,19 +295,48 @@ pub struct ChatMessage {
     pub content: String,
 }
 
-#[derive(Debug, Serialize)]
-struct ChatRequest {
-    model: String,
-    messages: Vec<ChatMessage>,
-    response_format: ResponseFormat,
-    max_tokens: u32,
-    temperature: f32,
+/// Body of a chat completions request, as handed to a [`ChatTransport`]
+#[derive(Debug, Clone, Serialize)]
+pub struct ChatRequest {
+    pub(crate) model: String,
+    pub(crate) messages: Vec<ChatMessage>,
     #[serde(skip_serializing_if = "Option::is_none")]
-    stop: Option<Vec<String>>,
+    pub(crate) response_format: Option<ResponseFormat>,
+    pub(crate) max_tokens: u32,
+    pub(crate) temperature: f32,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) top_p: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) frequency_penalty: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) presence_penalty: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) stop: Option<Vec<String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) tools: Option<Vec<ToolDef>>,
+    /// Number of alternative completions; the API default of one applies when omitted
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) n: Option<u32>,
+}
+
+impl ChatRequest {
+    /// SHA-256 hex digest of the whole request with the per-call timestamp from
+    /// [`build_json_prompt`] blanked out, so otherwise identical requests share it
+    pub(crate) fn digest(&self) -> String {
+        let keyed = ChatRequest {
+            messages: without_timestamps(&self.messages),
+            ..self.clone()
+        };
+        let body = serde_json::to_string(&keyed).unwrap_or_else(|_| format!("{:?}", keyed));
+        Sha256::digest(body.as_bytes())
+            .iter()
+            .map(|byte| format!("{:02x}", byte))
+            .collect()
+    }
 }
 
-#[derive(Debug, Serialize)]
-struct ResponseFormat {
+#[derive(Debug, Clone, Serialize)]
+pub(crate) struct ResponseFormat {
     #[serde(rename = "type")]
     format_type: String,
 }
@@ -116,18 +344,305 @@ struct ResponseFormat {
 #[derive(Debug, Deserialize)]
 struct ApiResponse {
     choices: Vec<Choice>,
+    #[serde(default)]
+    usage: Option<Usage>,
+}
+
+/// Token accounting reported by the API for a single request
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Usage {
+    #[serde(default)]
+    pub prompt_tokens: u64,
+    #[serde(default)]
+    pub completion_tokens: u64,
+    #[serde(default)]
+    pub total_tokens: u64,
+}
+
+impl std::ops::Add for Usage {
+    type Output = Usage;
+
+    fn add(self, other: Usage) -> Usage {
+        Usage {
+            prompt_tokens: self.prompt_tokens + other.prompt_tokens,
+            completion_tokens: self.completion_tokens + other.completion_tokens,
+            total_tokens: self.total_tokens + other.total_tokens,
+        }
+    }
+}
+
+/// Sum usage across API calls, treating a missing report as zero when the other has one
+fn add_usage(a: Option<Usage>, b: Option<Usage>) -> Option<Usage> {
+    match (a, b) {
+        (Some(a), Some(b)) => Some(a + b),
+        (a, b) => a.or(b),
+    }
+}
+
+/// Timing, token usage and reasoning for one answered request
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ResponseMeta {
+    /// Wall-clock time until the answer arrived, including retries and backoff delays
+    pub latency: Duration,
+    /// Tokens used by all API calls made for the answer, when the API reported usage
+    pub usage: Option<Usage>,
+    /// Chain-of-thought behind the answer, returned by reasoning models such as
+    /// `deepseek-reasoner`
+    pub reasoning: Option<String>,
+}
+
+impl ResponseMeta {
+    /// Usage and reasoning of a single API reply; the latency is filled in by the caller
+    fn from_reply(api_response: &ApiResponse) -> Self {
+        Self {
+            latency: Duration::ZERO,
+            usage: api_response.usage,
+            reasoning: api_response.choices[0].message.reasoning_content.clone(),
+        }
+    }
+
+    /// Combine with the meta of a follow-up call: usage adds up, the later reasoning wins
+    fn followed_by(self, later: ResponseMeta) -> Self {
+        Self {
+            latency: self.latency + later.latency,
+            usage: add_usage(self.usage, later.usage),
+            reasoning: later.reasoning.or(self.reasoning),
+        }
+    }
 }
 
 #[derive(Debug, Deserialize)]
 struct Choice {
-    message: ChatMessage,
+    message: ResponseMessage,
+    /// Why generation stopped: "stop", "length", "tool_calls", ...
+    #[serde(default)]
+    finish_reason: Option<String>,
+}
+
+/// Assistant message as returned by the API; `content` is null when the model calls tools
+#[derive(Debug, Deserialize)]
+struct ResponseMessage {
+    #[serde(default)]
+    content: Option<String>,
+    /// Chain-of-thought returned by reasoning models such as `deepseek-reasoner`
+    #[serde(default)]
+    reasoning_content: Option<String>,
+    #[serde(default)]
+    tool_calls: Option<Vec<ApiToolCall>>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ApiToolCall {
+    id: String,
+    function: ApiFunctionCall,
+}
+
+#[derive(Debug, Deserialize)]
+struct ApiFunctionCall {
+    name: String,
+    /// JSON-encoded argument object, as produced by the model
+    arguments: String,
+}
+
+/// A tool the model may call, in the OpenAI-compatible `tools` request format
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ToolDef {
+    #[serde(rename = "type")]
+    pub tool_type: String,
+    pub function: FunctionDef,
+}
+
+/// Name, description and JSON Schema parameters of a callable function
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FunctionDef {
+    pub name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    pub parameters: serde_json::Value,
+}
+
+impl ToolDef {
+    /// Define a function tool whose arguments follow the given JSON Schema
+    pub fn function(
+        name: impl Into<String>,
+        description: impl Into<String>,
+        parameters: serde_json::Value,
+    ) -> Self {
+        Self {
+            tool_type: "function".to_string(),
+            function: FunctionDef {
+                name: name.into(),
+                description: Some(description.into()),
+                parameters,
+            },
+        }
+    }
+}
+
+/// A function call requested by the model, with its arguments already parsed
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ToolCall {
+    pub id: String,
+    pub name: String,
+    pub arguments: serde_json::Value,
+}
+
+/// Outcome of a tool-enabled request: either a plain reply or calls to execute
+#[derive(Debug, Clone, PartialEq)]
+pub enum ToolCallResult {
+    Text(String),
+    ToolCalls(Vec<ToolCall>),
+}
+
+/// Result of checking one expected field in a gateway response
+#[derive(Debug, Clone, Serialize)]
+pub struct FieldCheck {
+    pub path: String,
+    pub required: bool,
+    pub present: bool,
+}
+
+/// Which OpenAI-compatible response fields a gateway returned for a probe request
+#[derive(Debug, Clone, Serialize)]
+pub struct CompatibilityReport {
+    pub endpoint: String,
+    pub checks: Vec<FieldCheck>,
+}
+
+impl CompatibilityReport {
+    /// Check a raw response body for the fields this client relies on
+    fn from_body(endpoint: String, body: &serde_json::Value) -> Self {
+        let has = |pointer: &str| body.pointer(pointer).is_some_and(|v| !v.is_null());
+        let checks = [
+            (
+                "choices",
+                true,
+                body["choices"].as_array().is_some_and(|c| !c.is_empty()),
+            ),
+            (
+                "choices[].message",
+                true,
+                body.pointer("/choices/0/message")
+                    .is_some_and(|m| m.is_object()),
+            ),
+            (
+                "choices[].message.content",
+                true,
+                body.pointer("/choices/0/message/content")
+                    .is_some_and(|c| c.is_string()),
+            ),
+            (
+                "choices[].finish_reason",
+                false,
+                has("/choices/0/finish_reason"),
+            ),
+            ("usage", false, body["usage"].is_object()),
+            (
+                "usage.total_tokens",
+                false,
+                body.pointer("/usage/total_tokens")
+                    .is_some_and(|t| t.is_u64()),
+            ),
+            ("model", false, has("/model")),
+        ]
+        .into_iter()
+        .map(|(path, required, present)| FieldCheck {
+            path: path.to_string(),
+            required,
+            present,
+        })
+        .collect();
+
+        Self { endpoint, checks }
+    }
+
+    /// True when every required field is present
+    pub fn is_compatible(&self) -> bool {
+        self.checks
+            .iter()
+            .all(|check| check.present || !check.required)
+    }
+
+    /// Fields that were expected but not found
+    pub fn missing(&self) -> impl Iterator<Item = &FieldCheck> {
+        self.checks.iter().filter(|check| !check.present)
+    }
+}
+
+/// Progress of a request, reported by
+/// [`send_request_with_progress`](DeepSeekClient::send_request_with_progress)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ProgressEvent {
+    /// An attempt is being sent; `attempt` counts from 1
+    Sending { attempt: u32 },
+    /// An attempt failed with a transient error; `attempt` is sent after `delay`
+    Retrying { attempt: u32, delay: Duration },
+    /// The answer arrived and parsed
+    Received,
+}
+
+/// Receiver of [`ProgressEvent`]s
+type ProgressCallback<'a> = dyn Fn(ProgressEvent) + Send + Sync + 'a;
+
+/// Human-readable retry delay: milliseconds below a second, else seconds
+fn format_delay(delay: Duration) -> String {
+    if delay < Duration::from_secs(1) {
+        format!("{}ms", delay.as_millis())
+    } else if delay.subsec_millis() == 0 {
+        format!("{}s", delay.as_secs())
+    } else {
+        format!("{:.1}s", delay.as_secs_f64())
+    }
 }
 
 /// DeepSeek API client
-#[derive(Clone, Debug)]
+#[derive(Clone)]
 pub struct DeepSeekClient {
-    client: Client,
+    /// Delivers requests to the API; shared between clones
+    transport: Arc<dyn ChatTransport>,
     config: Config,
+    denylist: Vec<Regex>,
+    /// Running totals for the session; shared between clones
+    stats: Arc<ClientStats>,
+    /// Settings that can be changed mid-session; shared between clones
+    settings: Arc<RwLock<LiveSettings>>,
+    /// Caps HTTP requests in flight when `max_concurrent_requests` is set; shared between clones
+    limiter: Option<Arc<Semaphore>>,
+    /// Disk cache for raw replies when `cache_dir` is set
+    cache: Option<ResponseCache>,
+    /// Requests being sent, keyed by a digest of their body, when `dedup_requests` is set;
+    /// shared between clones
+    in_flight: Option<Arc<Mutex<HashMap<String, InFlightRequest>>>>,
+}
+
+/// Response body of a request in flight, awaitable by every identical request
+type InFlightRequest = Shared<BoxFuture<'static, Result<String, DeepSeekError>>>;
+
+/// Model and temperature used for requests, initialized from the config
+#[derive(Debug, Clone)]
+struct LiveSettings {
+    model: String,
+    temperature: f32,
+}
+
+// Written by hand to leave out the HTTP client; the config redacts the API key itself
+impl fmt::Debug for DeepSeekClient {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("DeepSeekClient")
+            .field("config", &self.config)
+            .field("denylist", &self.denylist)
+            .field("stats", &self.stats)
+            .field("settings", &self.settings)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Requests sent, tokens consumed and errors returned by a client so far
+#[derive(Debug, Default)]
+struct ClientStats {
+    requests: AtomicU64,
+    tokens: AtomicU64,
+    errors: Mutex<BTreeMap<&'static str, u64>>,
 }
 
 impl DeepSeekClient {
@@ -137,476 +652,3434 @@ impl DeepSeekClient {
             message: e.to_string(),
         })?;
 
-        let client = Client::builder()
+        let mut headers = HeaderMap::new();
+        for (name, value) in &config.extra_headers {
+            let header_name = HeaderName::from_bytes(name.as_bytes()).map_err(|e| {
+                DeepSeekError::ConfigError {
+                    message: format!("Invalid header name '{}': {}", name, e),
+                }
+            })?;
+            let header_value =
+                HeaderValue::from_str(value).map_err(|e| DeepSeekError::ConfigError {
+                    message: format!("Invalid value for header '{}': {}", name, e),
+                })?;
+            headers.insert(header_name, header_value);
+        }
+
+        let mut builder = Client::builder()
             .timeout(Duration::from_secs(config.timeout))
-            .user_agent("deepseek_json/0.1.0")
-            .build()
-            .map_err(|e| DeepSeekError::ConfigError {
-                message: format!("Failed to create HTTP client: {}", e),
+            .user_agent(config.user_agent.as_deref().unwrap_or(DEFAULT_USER_AGENT))
+            .default_headers(headers);
+
+        if let Some(seconds) = config.connect_timeout {
+            builder = builder.connect_timeout(Duration::from_secs(seconds));
+        }
+
+        if let Some(proxy_url) = &config.proxy_url {
+            let proxy = reqwest::Proxy::all(proxy_url).map_err(|e| DeepSeekError::ConfigError {
+                message: format!("Invalid proxy URL '{}': {}", proxy_url, e),
             })?;
+            builder = builder.proxy(proxy);
+        }
 
-        Ok(Self { client, config })
+        let client = builder.build().map_err(|e| DeepSeekError::ConfigError {
+            message: format!("Failed to create HTTP client: {}", e),
+        })?;
+
+        let transport = HttpTransport::new(client, chat_url(&config), config.clone());
+        Self::assemble(config, Arc::new(transport))
     }
-    /// Send a request to the DeepSeek API with retry logic
-    pub async fn send_request(&self, user_input: &str) -> Result<DeepSeekResponse, DeepSeekError> {
-        let mut attempts = 0;
-        let max_attempts = 3;
-        let mut backoff = Duration::from_millis(500);
 
-        loop {
-            match self.send_request_once(user_input).await {
-                Ok(response) => return Ok(response),
-                Err(e)
-                    if (e.is_server_busy() || e.is_network_error())
-                        && attempts < max_attempts - 1 =>
-                {
-                    attempts += 1;
-                    tracing::warn!(
-                        "Request attempt {} failed: {}, retrying in {:?}",
-                        attempts,
-                        e,
-                        backoff
-                    );
-                    tokio::time::sleep(backoff).await;
-                    backoff = backoff.saturating_mul(2);
-                }
-                Err(e) => return Err(e),
-            }
-        }
+    /// Create a client that sends its requests through `transport` instead of HTTP, e.g.
+    /// a fake returning canned replies in tests
+    pub fn with_transport(
+        config: Config,
+        transport: Arc<dyn ChatTransport>,
+    ) -> Result<Self, DeepSeekError> {
+        config.validate().map_err(|e| DeepSeekError::ConfigError {
+            message: e.to_string(),
+        })?;
+        Self::assemble(config, transport)
     }
 
-    /// Send a single request to the DeepSeek API and return a structured response
-    async fn send_request_once(&self, user_input: &str) -> Result<DeepSeekResponse, DeepSeekError> {
-        let current_timestamp = Utc::now().to_rfc3339();
-
-        let json_format_prompt = format!(
-            r#"
-                Please respond with a JSON object containing the following fields:
-                {{
-                "title": "A concise title for the topic (string)",
-                "description": "A brief description or summary (string)",
-                "content": "The main content or detailed response (string)",
-                "category": "Optional category classification (string or null)",
-                "timestamp": "Current response timestamp: {} (string)",
-                "confidence": "Optional confidence score between 0.0 and 1.0 (number or null)"
-                }}
-
-                Make sure to provide valid JSON format in your response. Use the provided timestamp as the current response time.
-                Do not include any other text or comments in your response.
-            "#,
-            current_timestamp
-        );
+    /// Compile the config's patterns and schemas into a client around `transport`; the
+    /// config must already be validated
+    fn assemble(config: Config, transport: Arc<dyn ChatTransport>) -> Result<Self, DeepSeekError> {
+        let denylist = config
+            .input_denylist
+            .iter()
+            .map(|pattern| {
+                Regex::new(pattern).map_err(|e| DeepSeekError::ConfigError {
+                    message: format!("Invalid deny-list pattern '{}': {}", pattern, e),
+                })
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        for (category, schema) in &config.category_schemas {
+            jsonschema::validator_for(schema).map_err(|e| DeepSeekError::ConfigError {
+                message: format!("Invalid JSON Schema for category '{}': {}", category, e),
+            })?;
+        }
 
-        let combined_prompt = format!("{}\n\n{}", user_input, json_format_prompt);
+        let limiter = config
+            .max_concurrent_requests
+            .map(|permits| Arc::new(Semaphore::new(permits)));
 
-        let request = ChatRequest {
-            model: self.config.model.clone(),
-            messages: vec![
-                ChatMessage {
-                    role: "system".to_string(),
-                    content: "You are a helpful assistant that always responds with valid JSON in the specified format.".to_string(),
-                },
-                ChatMessage {
-                    role: "user".to_string(),
-                    content: combined_prompt,
-                },
-            ],
-            response_format: ResponseFormat {
-                format_type: "json_object".to_string(),
-            },
-            max_tokens: self.config.max_tokens,
-            temperature: self.config.temperature,
-            stop: None,
+        let cache = config
+            .cache_dir
+            .as_ref()
+            .map(|dir| ResponseCache::new(dir, Duration::from_secs(config.cache_ttl)));
+
+        let settings = LiveSettings {
+            model: config.model.clone(),
+            temperature: config.mode.temperature(config.temperature),
         };
+        let config_dedup = config.dedup_requests;
+
+        Ok(Self {
+            transport,
+            config,
+            denylist,
+            stats: Arc::default(),
+            settings: Arc::new(RwLock::new(settings)),
+            limiter,
+            cache,
+            in_flight: config_dedup.then(Arc::default),
+        })
+    }
 
-        // Send the request
-        let response = self
-            .client
-            .post(format!("{}/chat/completions", self.config.base_url))
-            .header("Authorization", format!("Bearer {}", self.config.api_key))
-            .header("Content-Type", "application/json")
-            .json(&request)
-            .send()
-            .await
-            .map_err(|e| self.map_reqwest_error(e))?;
+    /// Total tokens consumed by this client so far, as reported by the API
+    pub fn tokens_used(&self) -> u64 {
+        self.stats.tokens.load(Ordering::Relaxed)
+    }
 
-        // Handle HTTP status codes
-        let status = response.status();
-        if !status.is_success() {
-            return Err(self.handle_error_response(status, response).await);
-        }
+    /// Number of HTTP requests sent to the API so far, retries included
+    pub fn requests_sent(&self) -> u64 {
+        self.stats.requests.load(Ordering::Relaxed)
+    }
 
-        // Parse the response
-        let api_response: ApiResponse =
-            response
-                .json()
-                .await
-                .map_err(|e| DeepSeekError::ParseError {
-                    message: format!("Failed to parse API response: {}", e),
-                })?;
+    /// Errors returned to callers so far, counted by [`DeepSeekError::kind`]
+    pub fn error_counts(&self) -> BTreeMap<String, u64> {
+        self.stats
+            .errors
+            .lock()
+            .map(|errors| {
+                errors
+                    .iter()
+                    .map(|(kind, count)| (kind.to_string(), *count))
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
 
-        if api_response.choices.is_empty() {
-            return Err(DeepSeekError::ParseError {
-                message: "No choices in API response".to_string(),
-            });
+    /// Count an error returned to the caller
+    fn record_error(&self, error: &DeepSeekError) {
+        if let Ok(mut errors) = self.stats.errors.lock() {
+            *errors.entry(error.kind()).or_default() += 1;
         }
+    }
 
-        let content = &api_response.choices[0].message.content;
-        let parsed_response: DeepSeekResponse =
-            serde_json::from_str(content).map_err(|e| DeepSeekError::ParseError {
-                message: format!("Failed to parse JSON response from DeepSeek: {}", e),
-            })?;
+    /// Configured session token budget, if any
+    pub fn token_budget(&self) -> Option<u64> {
+        self.config.token_budget
+    }
 
-        Ok(parsed_response)
+    /// Tokens left before the budget is exhausted, if a budget is configured
+    pub fn remaining_budget(&self) -> Option<u64> {
+        self.config
+            .token_budget
+            .map(|budget| budget.saturating_sub(self.tokens_used()))
     }
 
-    /// Map reqwest errors to our custom error types
-    fn map_reqwest_error(&self, error: reqwest::Error) -> DeepSeekError {
-        if error.is_timeout() {
-            return DeepSeekError::Timeout {
-                seconds: self.config.timeout,
-            };
+    /// Refuse to send once the session has used up its token budget
+    fn check_budget(&self) -> Result<(), DeepSeekError> {
+        match self.config.token_budget {
+            Some(budget) if self.tokens_used() >= budget => Err(DeepSeekError::BudgetExceeded {
+                used: self.tokens_used(),
+                budget,
+            }),
+            _ => Ok(()),
         }
+    }
 
-        if error.is_connect() {
-            return DeepSeekError::NetworkError {
-                message: "Failed to connect to server".to_string(),
-            };
-        }
+    /// Model used for requests; starts as the configured model
+    pub fn model(&self) -> String {
+        self.settings
+            .read()
+            .map(|settings| settings.model.clone())
+            .unwrap_or_else(|_| self.config.model.clone())
+    }
 
-        if error.is_request() {
-            return DeepSeekError::NetworkError {
-                message: "Request failed".to_string(),
-            };
+    /// Send subsequent requests to another model, for this client and all its clones
+    pub fn set_model(&self, model: &str) -> Result<(), DeepSeekError> {
+        let model = model.trim();
+        if model.is_empty() {
+            return Err(DeepSeekError::ConfigError {
+                message: "Model name cannot be empty".to_string(),
+            });
+        }
+        if let Ok(mut settings) = self.settings.write() {
+            settings.model = model.to_string();
         }
+        Ok(())
+    }
 
-        // Check for specific network-related errors
-        let error_msg = error.to_string().to_lowercase();
-        if error_msg.contains("dns") {
-            return DeepSeekError::NetworkError {
-                message: "DNS resolution failed".to_string(),
-            };
+    /// Sampling temperature used for requests; starts as the configured temperature
+    pub fn temperature(&self) -> f32 {
+        self.settings
+            .read()
+            .map(|settings| settings.temperature)
+            .unwrap_or(self.config.temperature)
+    }
+
+    /// Change the sampling temperature (0.0-2.0) of subsequent requests, for this client
+    /// and all its clones
+    pub fn set_temperature(&self, temperature: f32) -> Result<(), DeepSeekError> {
+        if !(0.0..=2.0).contains(&temperature) {
+            return Err(DeepSeekError::ConfigError {
+                message: "Temperature must be between 0.0 and 2.0".to_string(),
+            });
+        }
+        if let Ok(mut settings) = self.settings.write() {
+            settings.temperature = temperature;
         }
+        Ok(())
+    }
 
-        if error_msg.contains("connection refused") {
-            return DeepSeekError::NetworkError {
-                message: "Connection refused by server".to_string(),
-            };
+    /// `DeepSeekResponse` fields the model may leave out (see `Config::optional_fields`)
+    pub fn optional_fields(&self) -> &[String] {
+        &self.config.optional_fields
+    }
+
+    /// Configured reply field names read as `DeepSeekResponse` fields (alias to field)
+    pub fn field_aliases(&self) -> &HashMap<String, String> {
+        &self.config.field_aliases
+    }
+
+    /// System prompt for structured queries: the configured override or the built-in default.
+    /// In [`Mode::Extraction`], stricter JSON instructions are appended.
+    pub fn system_prompt(&self) -> String {
+        let prompt = self
+            .config
+            .system_prompt
+            .as_deref()
+            .unwrap_or(JSON_SYSTEM_PROMPT);
+        match self.config.mode {
+            Mode::Creative => prompt.to_string(),
+            Mode::Extraction => format!("{}\n\n{}", prompt, EXTRACTION_INSTRUCTIONS),
         }
+    }
 
-        if error_msg.contains("network") || error_msg.contains("connection") {
-            return DeepSeekError::NetworkError {
-                message: error.to_string(),
-            };
+    /// Configured stop sequences for the request, or `None` to omit the field
+    fn stop_sequences(&self) -> Option<Vec<String>> {
+        (!self.config.stop_sequences.is_empty()).then(|| self.config.stop_sequences.clone())
+    }
+
+    /// Reject input matching any configured deny-list pattern, before it reaches the API
+    fn check_denylist(&self, input: &str) -> Result<(), DeepSeekError> {
+        match self.denylist.iter().find(|regex| regex.is_match(input)) {
+            Some(regex) => {
+                let error = DeepSeekError::InputRejected {
+                    pattern: regex.as_str().to_string(),
+                };
+                self.record_error(&error);
+                Err(error)
+            }
+            None => Ok(()),
         }
+    }
+
+    /// Send a request to the DeepSeek API with retry logic
+    pub async fn send_request(&self, user_input: &str) -> Result<DeepSeekResponse, DeepSeekError> {
+        self.send_request_with_meta(user_input)
+            .await
+            .map(|(response, _)| response)
+    }
+
+    /// Like [`send_request`](Self::send_request), also returning the latency and the
+    /// tokens used, including any retries and corrective requests
+    pub async fn send_request_with_meta(
+        &self,
+        user_input: &str,
+    ) -> Result<(DeepSeekResponse, ResponseMeta), DeepSeekError> {
+        let started = Instant::now();
+        let result = self
+            .send_request_with_meta_inner(user_input, started, None)
+            .await;
+        metrics::global().observe(started.elapsed(), result.as_ref().err());
+        result
+    }
 
-        DeepSeekError::NetworkError {
-            message: format!("Request error: {}", error),
+    /// Like [`send_request`](Self::send_request), reporting each attempt, retry and the
+    /// final reply to `on_event`, so embedders can show their own progress
+    pub async fn send_request_with_progress(
+        &self,
+        user_input: &str,
+        on_event: impl Fn(ProgressEvent) + Send + Sync,
+    ) -> Result<DeepSeekResponse, DeepSeekError> {
+        let started = Instant::now();
+        let result = self
+            .send_request_with_meta_inner(user_input, started, Some(&on_event))
+            .await;
+        metrics::global().observe(started.elapsed(), result.as_ref().err());
+        if result.is_ok() {
+            on_event(ProgressEvent::Received);
         }
+        result.map(|(response, _)| response)
     }
 
-    /// Handle error responses from the server
-    async fn handle_error_response(
+    async fn send_request_with_meta_inner(
         &self,
-        status: StatusCode,
-        response: reqwest::Response,
-    ) -> DeepSeekError {
-        let error_text = response
-            .text()
-            .await
-            .unwrap_or_else(|_| "Unknown error".to_string());
-
-        match status {
-            StatusCode::TOO_MANY_REQUESTS => DeepSeekError::ServerBusy,
-            StatusCode::SERVICE_UNAVAILABLE => DeepSeekError::ServerBusy,
-            StatusCode::BAD_GATEWAY | StatusCode::GATEWAY_TIMEOUT => DeepSeekError::ServerBusy,
-            _ => DeepSeekError::ApiError {
-                status: status.as_u16(),
-                message: error_text,
-            },
+        user_input: &str,
+        started: Instant,
+        progress: Option<&ProgressCallback<'_>>,
+    ) -> Result<(DeepSeekResponse, ResponseMeta), DeepSeekError> {
+        self.check_denylist(user_input)?;
+        let (mut response, mut meta) = self
+            .with_retry_reporting(|| self.send_request_once(user_input), progress)
+            .await?;
+
+        if self.config.require_confidence && response.confidence.is_none() {
+            let (corrected, extra) = self.retry_for_confidence(user_input, &response).await?;
+            response = corrected;
+            meta = meta.followed_by(extra);
         }
+        if let Some((category, violations)) = self.category_schema_violations(&response) {
+            let (corrected, extra) = self
+                .retry_for_category_schema(user_input, &response, &category, &violations)
+                .await?;
+            response = corrected;
+            meta = meta.followed_by(extra);
+        }
+
+        meta.latency = started.elapsed();
+        Ok((response, meta))
     }
 
-    /// Send arbitrary chat messages and return the raw assistant content string.
-    /// The response is requested as a JSON object to encourage strict JSON outputs.
-    pub async fn send_messages_raw(
+    /// Like [`send_request_with_meta`](Self::send_request_with_meta), but ask for `n`
+    /// alternative answers in one request, to compare them. Fails if any alternative does
+    /// not parse; corrective retries (`require_confidence`, category schemas) are not sent.
+    pub async fn send_request_multi(
         &self,
-        messages: Vec<ChatMessage>,
-    ) -> Result<String, DeepSeekError> {
+        user_input: &str,
+        n: u32,
+    ) -> Result<(Vec<DeepSeekResponse>, ResponseMeta), DeepSeekError> {
+        let started = Instant::now();
+        self.check_denylist(user_input)?;
+        let messages = self.structured_messages(user_input);
+        let (responses, mut meta) = self
+            .with_retry(|| self.send_structured_multi_once(&messages, n))
+            .await?;
+
+        meta.latency = started.elapsed();
+        Ok((responses, meta))
+    }
+
+    /// Send messages once asking for `n` completions, and parse every choice
+    async fn send_structured_multi_once(
+        &self,
+        messages: &[ChatMessage],
+        n: u32,
+    ) -> Result<(Vec<DeepSeekResponse>, ResponseMeta), DeepSeekError> {
         let request = ChatRequest {
-            model: self.config.model.clone(),
-            messages,
-            response_format: ResponseFormat {
-                format_type: "json_object".to_string(),
-            },
-            max_tokens: self.config.max_tokens,
-            temperature: self.config.temperature,
-            stop: None,
+            n: Some(n),
+            ..self.build_chat_request(messages, &self.model())
         };
 
-        let response = self
-            .client
-            .post(format!("{}/chat/completions", self.config.base_url))
-            .header("Authorization", format!("Bearer {}", self.config.api_key))
-            .header("Content-Type", "application/json")
-            .json(&request)
-            .send()
-            .await
-            .map_err(|e| self.map_reqwest_error(e))?;
-
-        let status = response.status();
-        if !status.is_success() {
-            return Err(self.handle_error_response(status, response).await);
-        }
+        let api_response = self.send_chat(&request).await?;
+        let responses = api_response
+            .choices
+            .iter()
+            .map(|choice| {
+                parse_response_with_aliases(
+                    choice.message.content.as_deref().unwrap_or_default(),
+                    self.optional_fields(),
+                    self.field_aliases(),
+                )
+            })
+            .collect::<Result<Vec<_>, _>>()?;
 
-        let api_response: ApiResponse =
-            response
-                .json()
-                .await
-                .map_err(|e| DeepSeekError::ParseError {
-                    message: format!("Failed to parse API response: {}", e),
-                })?;
+        Ok((responses, ResponseMeta::from_reply(&api_response)))
+    }
 
-        if api_response.choices.is_empty() {
+    /// Ask the model once more to repeat its answer with a numeric confidence.
+    /// Fails when the corrected response still has none.
+    async fn retry_for_confidence(
+        &self,
+        user_input: &str,
+        previous: &DeepSeekResponse,
+    ) -> Result<(DeepSeekResponse, ResponseMeta), DeepSeekError> {
+        tracing::warn!("Response is missing a confidence score; sending a corrective request");
+
+        let (response, meta) = self
+            .send_correction(user_input, previous, CONFIDENCE_CORRECTION_PROMPT)
+            .await?;
+        if response.confidence.is_none() {
             return Err(DeepSeekError::ParseError {
-                message: "No choices in API response".to_string(),
+                message: "Response has no confidence score, even after a corrective retry"
+                    .to_string(),
             });
         }
-
-        Ok(api_response.choices[0].message.content.clone())
+        Ok((response, meta))
+    }
+
+    /// Ask the model once more for content matching its category's schema.
+    /// Fails, naming the category, when the corrected response still does not match.
+    async fn retry_for_category_schema(
+        &self,
+        user_input: &str,
+        previous: &DeepSeekResponse,
+        category: &str,
+        violations: &[String],
+    ) -> Result<(DeepSeekResponse, ResponseMeta), DeepSeekError> {
+        tracing::warn!(
+            "Content does not match the schema for category '{}'; sending a corrective request",
+            category
+        );
+
+        let schema = &self.config.category_schemas[category];
+        let correction = build_category_correction_prompt(category, schema, violations);
+        let (response, meta) = self
+            .send_correction(user_input, previous, &correction)
+            .await?;
+
+        if let Some((category, violations)) = self.category_schema_violations(&response) {
+            return Err(DeepSeekError::ParseError {
+                message: format!(
+                    "Content does not match the schema for category '{}', even after a corrective retry: {}",
+                    category,
+                    violations.join("; ")
+                ),
+            });
+        }
+        Ok((response, meta))
+    }
+
+    /// Check the response content against the schema configured for its category.
+    /// Returns the configured category name and the violations on mismatch; responses
+    /// in categories without a schema always pass.
+    fn category_schema_violations(
+        &self,
+        response: &DeepSeekResponse,
+    ) -> Option<(String, Vec<String>)> {
+        let returned = response.category.as_deref()?;
+        let (category, schema) = self
+            .config
+            .category_schemas
+            .iter()
+            .find(|(category, _)| category.eq_ignore_ascii_case(returned))?;
+        // Schemas are checked in `new`, so compiling again cannot fail
+        let validator = jsonschema::validator_for(schema).ok()?;
+
+        // Structured content arrives as a JSON string; anything else is checked as plain text
+        let content = serde_json::from_str(&response.content)
+            .unwrap_or_else(|_| serde_json::Value::String(response.content.clone()));
+        let violations: Vec<String> = validator
+            .iter_errors(&content)
+            .map(|e| format!("{}: {}", e.instance_path, e))
+            .collect();
+
+        if violations.is_empty() {
+            None
+        } else {
+            Some((category.clone(), violations))
+        }
+    }
+
+    /// Resend the structured query with the previous answer and a correction appended
+    async fn send_correction(
+        &self,
+        user_input: &str,
+        previous: &DeepSeekResponse,
+        correction: &str,
+    ) -> Result<(DeepSeekResponse, ResponseMeta), DeepSeekError> {
+        let previous = serde_json::to_string(previous).map_err(|e| DeepSeekError::ParseError {
+            message: format!("Failed to serialize previous response: {}", e),
+        })?;
+        let mut messages = self.structured_messages(user_input);
+        messages.push(ChatMessage {
+            role: "assistant".to_string(),
+            content: previous,
+        });
+        messages.push(ChatMessage {
+            role: "user".to_string(),
+            content: correction.to_string(),
+        });
+
+        self.with_retry(|| self.send_structured_once(&messages))
+            .await
+    }
+
+    /// Like [`send_request`](Self::send_request), also returning the wall-clock time spent,
+    /// including any retries and backoff delays
+    pub async fn send_request_timed(
+        &self,
+        user_input: &str,
+    ) -> Result<(DeepSeekResponse, Duration), DeepSeekError> {
+        let (response, meta) = self.send_request_with_meta(user_input).await?;
+        Ok((response, meta.latency))
+    }
+
+    /// Run `operation`, retrying transient failures (server busy, network errors) with
+    /// exponential backoff according to the configured attempt count and base delay
+    async fn with_retry<T, F, Fut>(&self, operation: F) -> Result<T, DeepSeekError>
+    where
+        F: FnMut() -> Fut,
+        Fut: Future<Output = Result<T, DeepSeekError>>,
+    {
+        self.with_retry_reporting(operation, None).await
+    }
+
+    /// [`with_retry`](Self::with_retry), also reporting attempts and retries to `progress`
+    async fn with_retry_reporting<T, F, Fut>(
+        &self,
+        mut operation: F,
+        progress: Option<&ProgressCallback<'_>>,
+    ) -> Result<T, DeepSeekError>
+    where
+        F: FnMut() -> Fut,
+        Fut: Future<Output = Result<T, DeepSeekError>>,
+    {
+        if let Err(e) = self.check_budget() {
+            self.record_error(&e);
+            return Err(e);
+        }
+
+        let mut attempts = 0;
+        let max_attempts = self.config.retry_max_attempts.max(1);
+        let mut backoff = Duration::from_millis(self.config.retry_base_backoff_ms);
+        // With a deadline, elapsed time rather than the attempt count bounds retrying
+        let deadline = self.config.retry_deadline.map(Duration::from_secs);
+        let started = Instant::now();
+
+        loop {
+            if let Some(progress) = progress {
+                progress(ProgressEvent::Sending {
+                    attempt: attempts + 1,
+                });
+            }
+            match operation().await {
+                Ok(response) => return Ok(response),
+                Err(e)
+                    if (e.is_server_busy() || e.is_network_error() || e.is_empty_response())
+                        && (deadline.is_some() || attempts < max_attempts - 1) =>
+                {
+                    // Never retry sooner than the server asked us to
+                    let delay = e.retry_after().map_or(backoff, |wait| wait.max(backoff));
+                    if let Some(deadline) = deadline
+                        && started.elapsed() + delay > deadline
+                    {
+                        tracing::warn!(
+                            "Giving up after {} attempt(s): the retry deadline of {:?} would pass",
+                            attempts + 1,
+                            deadline
+                        );
+                        self.record_error(&e);
+                        return Err(e);
+                    }
+                    attempts += 1;
+                    tracing::warn!(
+                        "Request attempt {} failed: {}, retrying in {:?}",
+                        attempts,
+                        e,
+                        delay
+                    );
+                    if let Some(progress) = progress {
+                        progress(ProgressEvent::Retrying {
+                            attempt: attempts + 1,
+                            delay,
+                        });
+                    }
+                    if self.config.verbose {
+                        let attempt = match deadline {
+                            Some(_) => (attempts + 1).to_string(),
+                            None => format!("{}/{}", attempts + 1, max_attempts),
+                        };
+                        eprintln!(
+                            "⟳ retrying (attempt {}) after {}...",
+                            attempt,
+                            format_delay(delay)
+                        );
+                    }
+                    tokio::time::sleep(delay).await;
+                    backoff = backoff.saturating_mul(2);
+                }
+                Err(e) => {
+                    self.record_error(&e);
+                    return Err(e);
+                }
+            }
+        }
+    }
+
+    /// Send a request whose answer must follow a caller-supplied JSON Schema instead of
+    /// the `DeepSeekResponse` shape. Returns the validated JSON value.
+    pub async fn send_request_schema(
+        &self,
+        user_input: &str,
+        schema: &serde_json::Value,
+    ) -> Result<serde_json::Value, DeepSeekError> {
+        self.check_denylist(user_input)?;
+        let validator =
+            jsonschema::validator_for(schema).map_err(|e| DeepSeekError::ConfigError {
+                message: format!("Invalid JSON Schema: {}", e),
+            })?;
+        let messages = self.schema_messages(user_input, schema);
+
+        self.with_retry(|| self.send_request_schema_once(&messages, &validator))
+            .await
+    }
+
+    /// Send a schema-constrained request once, without retrying
+    async fn send_request_schema_once(
+        &self,
+        messages: &[ChatMessage],
+        validator: &jsonschema::Validator,
+    ) -> Result<serde_json::Value, DeepSeekError> {
+        self.send_messages_checked_once(messages, &self.model(), |raw| {
+            ensure_content(raw)?;
+
+            let value: serde_json::Value =
+                from_str_or_embedded(strip_code_fences(raw)).map_err(|e| {
+                    DeepSeekError::ParseError {
+                        message: format!("Failed to parse JSON response from DeepSeek: {}", e),
+                    }
+                })?;
+
+            let violations: Vec<String> = validator
+                .iter_errors(&value)
+                .map(|e| format!("{}: {}", e.instance_path, e))
+                .collect();
+            if !violations.is_empty() {
+                return Err(DeepSeekError::ParseError {
+                    message: format!(
+                        "Response does not match the schema: {}",
+                        violations.join("; ")
+                    ),
+                });
+            }
+
+            Ok(value)
+        })
+        .await
+        .map(|(value, _)| value)
+    }
+
+    /// Send a single request to the DeepSeek API and return a structured response
+    async fn send_request_once(
+        &self,
+        user_input: &str,
+    ) -> Result<(DeepSeekResponse, ResponseMeta), DeepSeekError> {
+        self.send_structured_once(&self.structured_messages(user_input))
+            .await
+    }
+
+    /// System and user messages for a query answered in a caller-supplied JSON Schema
+    pub fn schema_messages(
+        &self,
+        user_input: &str,
+        schema: &serde_json::Value,
+    ) -> Vec<ChatMessage> {
+        vec![
+            ChatMessage {
+                role: "system".to_string(),
+                content: self.system_prompt(),
+            },
+            ChatMessage {
+                role: "user".to_string(),
+                content: build_schema_prompt(user_input, schema),
+            },
+        ]
+    }
+
+    /// Chat request for `messages` with the configured sampling settings, asking for a
+    /// JSON object reply
+    fn build_chat_request(&self, messages: &[ChatMessage], model: &str) -> ChatRequest {
+        ChatRequest {
+            model: model.to_string(),
+            messages: messages.to_vec(),
+            response_format: Some(ResponseFormat {
+                format_type: "json_object".to_string(),
+            }),
+            max_tokens: self.config.max_tokens,
+            temperature: self.temperature(),
+            top_p: self.config.top_p,
+            frequency_penalty: self.config.frequency_penalty,
+            presence_penalty: self.config.presence_penalty,
+            stop: self.stop_sequences(),
+            tools: None,
+            n: None,
+        }
+    }
+
+    /// The HTTP request that sending `messages` to `model` would make, as JSON, without
+    /// sending it. The API key is redacted; the body is exactly what would be posted.
+    pub fn preview_request(&self, messages: &[ChatMessage], model: &str) -> serde_json::Value {
+        let mut url = self.chat_url();
+        let mut headers = serde_json::Map::new();
+        match self.config.auth_style {
+            AuthStyle::Bearer => {
+                headers.insert(
+                    "Authorization".to_string(),
+                    format!("Bearer {}", REDACTED_API_KEY).into(),
+                );
+            }
+            AuthStyle::ApiKeyHeader => {
+                headers.insert(API_KEY_HEADER.to_string(), REDACTED_API_KEY.into());
+            }
+            AuthStyle::QueryParam => {
+                url = format!("{}?{}={}", url, API_KEY_QUERY_PARAM, REDACTED_API_KEY);
+            }
+        }
+        headers.insert("Content-Type".to_string(), "application/json".into());
+        for (name, value) in &self.config.extra_headers {
+            headers.insert(name.clone(), value.clone().into());
+        }
+
+        serde_json::json!({
+            "method": "POST",
+            "url": url,
+            "headers": headers,
+            "body": self.build_chat_request(messages, model),
+        })
+    }
+
+    /// System and user messages for a structured `DeepSeekResponse` query
+    pub fn structured_messages(&self, user_input: &str) -> Vec<ChatMessage> {
+        vec![
+            ChatMessage {
+                role: "system".to_string(),
+                content: self.system_prompt(),
+            },
+            ChatMessage {
+                role: "user".to_string(),
+                content: build_json_prompt(user_input, self.optional_fields()),
+            },
+        ]
+    }
+
+    /// Send messages once and parse the reply as a `DeepSeekResponse`
+    async fn send_structured_once(
+        &self,
+        messages: &[ChatMessage],
+    ) -> Result<(DeepSeekResponse, ResponseMeta), DeepSeekError> {
+        self.send_messages_checked_once(messages, &self.model(), |raw| {
+            parse_response_with_aliases(raw, self.optional_fields(), self.field_aliases())
+        })
+        .await
+    }
+
+    /// Send arbitrary chat messages and return the raw assistant content string.
+    /// The response is requested as a JSON object to encourage strict JSON outputs.
+    pub async fn send_messages_raw(
+        &self,
+        messages: Vec<ChatMessage>,
+    ) -> Result<String, DeepSeekError> {
+        self.send_messages_raw_with_model(messages, &self.model())
+            .await
+    }
+
+    /// Like [`send_messages_raw`](Self::send_messages_raw), but sent to a specific model
+    pub async fn send_messages_raw_with_model(
+        &self,
+        messages: Vec<ChatMessage>,
+        model: &str,
+    ) -> Result<String, DeepSeekError> {
+        self.send_messages_raw_timed(messages, model)
+            .await
+            .map(|(content, _)| content)
+    }
+
+    /// Like [`send_messages_raw`](Self::send_messages_raw), also returning the latency and
+    /// the tokens used, including any retries
+    pub async fn send_messages_raw_with_meta(
+        &self,
+        messages: Vec<ChatMessage>,
+    ) -> Result<(String, ResponseMeta), DeepSeekError> {
+        self.send_messages_raw_timed(messages, &self.model()).await
+    }
+
+    /// Like [`send_messages_raw`](Self::send_messages_raw), also returning the model's
+    /// chain-of-thought when it is a reasoning model such as `deepseek-reasoner`
+    pub async fn send_messages_raw_with_reasoning(
+        &self,
+        messages: Vec<ChatMessage>,
+    ) -> Result<(String, Option<String>), DeepSeekError> {
+        let (content, meta) = self.send_messages_raw_with_meta(messages).await?;
+        Ok((content, meta.reasoning))
+    }
+
+    /// Send raw chat messages with retries, measuring the time until the reply arrived
+    async fn send_messages_raw_timed(
+        &self,
+        messages: Vec<ChatMessage>,
+        model: &str,
+    ) -> Result<(String, ResponseMeta), DeepSeekError> {
+        let started = Instant::now();
+        let result = async {
+            for message in messages.iter().filter(|m| m.role == "user") {
+                self.check_denylist(&message.content)?;
+            }
+            self.with_retry(|| self.send_messages_raw_once(&messages, model))
+                .await
+        }
+        .await;
+        metrics::global().observe(started.elapsed(), result.as_ref().err());
+
+        let (content, mut meta) = result?;
+        meta.latency = started.elapsed();
+        Ok((content, meta))
+    }
+
+    /// Send a structured query like [`send_request`](Self::send_request), but return the
+    /// model's reply exactly as received instead of parsing it
+    pub async fn send_request_raw(&self, user_input: &str) -> Result<String, DeepSeekError> {
+        self.send_messages_raw(self.structured_messages(user_input))
+            .await
+    }
+
+    /// Model used for TaskFinisher requests: the dedicated override or the main model
+    pub fn taskfinisher_model(&self) -> String {
+        self.config
+            .taskfinisher_model
+            .clone()
+            .unwrap_or_else(|| self.model())
+    }
+
+    /// Send chat messages once, without retrying
+    async fn send_messages_raw_once(
+        &self,
+        messages: &[ChatMessage],
+        model: &str,
+    ) -> Result<(String, ResponseMeta), DeepSeekError> {
+        self.send_messages_checked_once(messages, model, |raw| Ok(raw.to_string()))
+            .await
+    }
+
+    /// Send chat messages once, without retrying, and turn the reply into `T` with `check`.
+    /// With a cache configured, a reply is stored only once `check` accepts it and it is
+    /// not blank, so an empty or malformed answer is never replayed from disk.
+    async fn send_messages_checked_once<T>(
+        &self,
+        messages: &[ChatMessage],
+        model: &str,
+        check: impl Fn(&str) -> Result<T, DeepSeekError>,
+    ) -> Result<(T, ResponseMeta), DeepSeekError> {
+        let request = self.build_chat_request(messages, model);
+
+        // With a cache configured, an identical earlier request is answered from disk
+        let cache = self
+            .cache
+            .as_ref()
+            .map(|cache| (cache, ResponseCache::key(&request)));
+        if let Some((cache, key)) = &cache
+            && let Some(content) = cache.get(key)
+            && let Ok(checked) = check(&content)
+        {
+            tracing::debug!(key = %key, "Answered from the response cache");
+            return Ok((checked, ResponseMeta::default()));
+        }
+
+        let api_response = self.send_chat(&request).await?;
+        let content = strip_bom(
+            api_response.choices[0]
+                .message
+                .content
+                .as_deref()
+                .unwrap_or_default(),
+        );
+        let checked = check(content)?;
+
+        if let Some((cache, key)) = &cache
+            && ensure_content(content).is_ok()
+            && let Err(e) = cache.put(key, content)
+        {
+            tracing::warn!("Failed to write the response cache: {}", e);
+        }
+
+        Ok((checked, ResponseMeta::from_reply(&api_response)))
+    }
+
+    /// Send chat messages along with tool definitions. Returns the tool calls the model
+    /// requested, or its text reply when it answered directly.
+    pub async fn send_messages_with_tools(
+        &self,
+        messages: Vec<ChatMessage>,
+        tools: Vec<ToolDef>,
+    ) -> Result<ToolCallResult, DeepSeekError> {
+        for message in messages.iter().filter(|m| m.role == "user") {
+            self.check_denylist(&message.content)?;
+        }
+        self.with_retry(|| self.send_messages_with_tools_once(&messages, &tools))
+            .await
+    }
+
+    /// Send a tool-enabled request once, without retrying
+    async fn send_messages_with_tools_once(
+        &self,
+        messages: &[ChatMessage],
+        tools: &[ToolDef],
+    ) -> Result<ToolCallResult, DeepSeekError> {
+        let request = ChatRequest {
+            response_format: None,
+            tools: Some(tools.to_vec()),
+            ..self.build_chat_request(messages, &self.model())
+        };
+
+        let mut api_response = self.send_chat(&request).await?;
+        let message = api_response.choices.swap_remove(0).message;
+
+        match message.tool_calls {
+            Some(calls) if !calls.is_empty() => calls
+                .into_iter()
+                .map(|call| {
+                    let arguments =
+                        serde_json::from_str(&call.function.arguments).map_err(|e| {
+                            DeepSeekError::ParseError {
+                                message: format!(
+                                    "Invalid arguments for tool '{}': {}",
+                                    call.function.name, e
+                                ),
+                            }
+                        })?;
+                    Ok(ToolCall {
+                        id: call.id,
+                        name: call.function.name,
+                        arguments,
+                    })
+                })
+                .collect::<Result<Vec<_>, _>>()
+                .map(ToolCallResult::ToolCalls),
+            _ => Ok(ToolCallResult::Text(message.content.unwrap_or_default())),
+        }
+    }
+
+    /// Send a tiny request and report which OpenAI-compatible response fields come back.
+    /// Useful for checking a custom `base_url` before relying on it.
+    pub async fn probe_compatibility(&self) -> Result<CompatibilityReport, DeepSeekError> {
+        let request = ChatRequest {
+            model: self.model(),
+            messages: vec![ChatMessage {
+                role: "user".to_string(),
+                content: r#"Reply with the JSON object {"ok": true}."#.to_string(),
+            }],
+            response_format: Some(ResponseFormat {
+                format_type: "json_object".to_string(),
+            }),
+            max_tokens: 16,
+            temperature: 0.0,
+            top_p: None,
+            frequency_penalty: None,
+            presence_penalty: None,
+            stop: None,
+            tools: None,
+            n: None,
+        };
+
+        let (body, _) = self.post_chat(&request).await?;
+        let body: serde_json::Value =
+            serde_json::from_str(strip_bom(&body)).map_err(|e| DeepSeekError::ParseError {
+                message: format!("Gateway response is not JSON: {}", e),
+            })?;
+
+        Ok(CompatibilityReport::from_body(self.chat_url(), &body))
+    }
+
+    /// Send a minimal request ("ping", at most one token) to verify that the API key and
+    /// endpoint work. Returns the round-trip latency.
+    pub async fn check(&self) -> Result<Duration, DeepSeekError> {
+        let messages = [ChatMessage {
+            role: "user".to_string(),
+            content: "ping".to_string(),
+        }];
+        let request = ChatRequest {
+            response_format: None,
+            max_tokens: 1,
+            ..self.build_chat_request(&messages, &self.model())
+        };
+
+        let started = Instant::now();
+        self.post_chat(&request).await?;
+        Ok(started.elapsed())
+    }
+
+    /// IDs of the models the endpoint offers (`GET {base_url}/models`), in the order listed
+    pub async fn list_models(&self) -> Result<Vec<String>, DeepSeekError> {
+        #[derive(Deserialize)]
+        struct ModelList {
+            data: Vec<ModelEntry>,
+        }
+        #[derive(Deserialize)]
+        struct ModelEntry {
+            id: String,
+        }
+
+        self.stats.requests.fetch_add(1, Ordering::Relaxed);
+        let body = self.transport.get_models().await?;
+        let list: ModelList =
+            serde_json::from_str(strip_bom(&body)).map_err(|e| DeepSeekError::ParseError {
+                message: format!("Failed to parse model list: {}", e),
+            })?;
+        Ok(list.data.into_iter().map(|model| model.id).collect())
+    }
+
+    /// Base URL requests are sent to, without a trailing slash
+    pub fn base_url(&self) -> &str {
+        self.config.base_url.trim_end_matches('/')
+    }
+
+    /// Full URL of the chat completions endpoint
+    fn chat_url(&self) -> String {
+        chat_url(&self.config)
+    }
+
+    /// Send a chat request through the transport and return the response body, and whether
+    /// this call sent it. With `dedup_requests`, a request identical to one still in flight
+    /// waits for that one's response instead of being sent again.
+    async fn post_chat(&self, request: &ChatRequest) -> Result<(String, bool), DeepSeekError> {
+        let Some(in_flight) = &self.in_flight else {
+            return self.deliver(request).await.map(|body| (body, true));
+        };
+        // Keyed without the per-call prompt timestamp, so identical queries still match
+        let key = request.digest();
+
+        let (shared, sent) = {
+            let mut requests = in_flight.lock().unwrap_or_else(|e| e.into_inner());
+            match requests.get(&key) {
+                Some(shared) => {
+                    tracing::debug!("Joining an identical request already in flight");
+                    (shared.clone(), false)
+                }
+                None => {
+                    let shared = self.deliver(request).shared();
+                    requests.insert(key.clone(), shared.clone());
+                    (shared, true)
+                }
+            }
+        };
+        let result = shared.clone().await;
+
+        // Forget the request once settled, unless a newer one has taken its place
+        let mut requests = in_flight.lock().unwrap_or_else(|e| e.into_inner());
+        if requests
+            .get(&key)
+            .is_some_and(|current| current.ptr_eq(&shared))
+        {
+            requests.remove(&key);
+        }
+        result.map(|body| (body, sent))
+    }
+
+    /// Count a request and send it through the transport once a request slot is free.
+    /// The returned future owns everything it needs, so identical requests can share it.
+    fn deliver(&self, request: &ChatRequest) -> BoxFuture<'static, Result<String, DeepSeekError>> {
+        self.stats.requests.fetch_add(1, Ordering::Relaxed);
+        let transport = Arc::clone(&self.transport);
+        let limiter = self.limiter.clone();
+        let request = request.clone();
+        async move {
+            // Held until the body has been read, so it covers the whole HTTP exchange
+            let _permit = match &limiter {
+                Some(limiter) => {
+                    Some(
+                        limiter
+                            .acquire()
+                            .await
+                            .map_err(|e| DeepSeekError::ConfigError {
+                                message: format!("Request limiter closed: {}", e),
+                            })?,
+                    )
+                }
+                None => None,
+            };
+            transport.post(&request).await
+        }
+        .boxed()
+    }
+
+    /// Send a chat request and parse the API envelope, ensuring at least one choice is present.
+    /// Each attempt is logged under [`REQUEST_LOG_TARGET`] with its model, token usage and
+    /// latency; the API key and message contents are never logged.
+    async fn send_chat(&self, request: &ChatRequest) -> Result<ApiResponse, DeepSeekError> {
+        let span = tracing::info_span!(
+            target: REQUEST_LOG_TARGET,
+            "chat_request",
+            model = %request.model,
+            messages = request.messages.len(),
+            prompt_tokens = tracing::field::Empty,
+            completion_tokens = tracing::field::Empty,
+            http.status = tracing::field::Empty
+        );
+        let started = Instant::now();
+        let result = self.send_chat_once(request).instrument(span.clone()).await;
+        let latency_ms = started.elapsed().as_millis() as u64;
+
+        let _entered = span.enter();
+        match &result {
+            Ok(api_response) => {
+                let usage = api_response.usage.as_ref();
+                if let Some(usage) = usage {
+                    span.record("prompt_tokens", usage.prompt_tokens);
+                    span.record("completion_tokens", usage.completion_tokens);
+                }
+                tracing::info!(
+                    target: REQUEST_LOG_TARGET,
+                    latency_ms,
+                    prompt_tokens = usage.map(|u| u.prompt_tokens),
+                    completion_tokens = usage.map(|u| u.completion_tokens),
+                    total_tokens = usage.map(|u| u.total_tokens),
+                    "request completed"
+                );
+            }
+            Err(e) => {
+                tracing::warn!(
+                    target: REQUEST_LOG_TARGET,
+                    latency_ms,
+                    error_kind = e.kind(),
+                    error = %e,
+                    "request failed"
+                );
+            }
+        }
+        result
+    }
+
+    /// Body of [`send_chat`](Self::send_chat), without the request log entry
+    async fn send_chat_once(&self, request: &ChatRequest) -> Result<ApiResponse, DeepSeekError> {
+        let (body, sent) = self.post_chat(request).await?;
+
+        // Some proxies prepend a UTF-8 BOM, which serde_json rejects
+        let api_response: ApiResponse =
+            serde_json::from_str(strip_bom(&body)).map_err(|e| DeepSeekError::ParseError {
+                message: format!("Failed to parse API response: {}", e),
+            })?;
+
+        if api_response.choices.is_empty() {
+            return Err(DeepSeekError::ParseError {
+                message: "No choices in API response".to_string(),
+            });
+        }
+
+        // A reply shared with an identical request was paid for only once
+        if sent && let Some(usage) = api_response.usage {
+            self.stats
+                .tokens
+                .fetch_add(usage.total_tokens, Ordering::Relaxed);
+        }
+
+        // A length cut leaves truncated JSON; report it instead of a confusing parse error
+        if api_response.choices[0].finish_reason.as_deref() == Some("length") {
+            return Err(DeepSeekError::Truncated {
+                tokens: request.max_tokens,
+            });
+        }
+
+        Ok(api_response)
     }
 }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use tokio::time::advance;
-    use wiremock::matchers::{method, path};
-    use wiremock::{Mock, MockServer, ResponseTemplate};
+/// Placeholder for the API key in request previews
+const REDACTED_API_KEY: &str = "***";
+
+/// Tracing target for the per-request audit log (model, token usage, latency, errors)
+pub const REQUEST_LOG_TARGET: &str = "deepseek_json::requests";
+
+/// System prompt used for structured `DeepSeekResponse` requests
+pub const JSON_SYSTEM_PROMPT: &str =
+    "You are a helpful assistant that always responds with valid JSON in the specified format.";
+
+/// Appended to the system prompt in [`Mode::Extraction`]
+const EXTRACTION_INSTRUCTIONS: &str = "Extract facts only from the user's input and your knowledge; \
+do not embellish or speculate. Reply with exactly one JSON object and nothing else: no Markdown, \
+no comments, no text before or after it. Set optional fields to null rather than guessing.";
+
+/// Follow-up sent when `require_confidence` is set and the model omitted the score
+const CONFIDENCE_CORRECTION_PROMPT: &str = "Your previous response did not include a confidence score. \
+Reply again with the same JSON object, and always set \"confidence\" to a number between 0.0 and 1.0.";
+
+/// `DeepSeekResponse` fields that must be present in every answer unless listed in
+/// `Config::optional_fields`
+pub const REQUIRED_RESPONSE_FIELDS: &[&str] = &["title", "description", "content"];
+
+/// All `DeepSeekResponse` fields, the names `Config::field_aliases` may map to
+pub const RESPONSE_FIELDS: &[&str] = &[
+    "title",
+    "description",
+    "content",
+    "category",
+    "timestamp",
+    "confidence",
+];
+
+/// Names some OpenAI-compatible backends use for `DeepSeekResponse` fields, as
+/// `(alias, field)` pairs; always accepted
+pub const RESPONSE_FIELD_ALIASES: &[(&str, &str)] = &[("desc", "description"), ("text", "content")];
+
+/// Parse a model reply as a `DeepSeekResponse`, tolerating Markdown fences and surrounding
+/// prose. Fails naming each required field (see [`REQUIRED_RESPONSE_FIELDS`]) that is
+/// missing or null, unless it is listed in `optional_fields`.
+pub fn parse_response(
+    raw: &str,
+    optional_fields: &[String],
+) -> Result<DeepSeekResponse, DeepSeekError> {
+    parse_response_with_aliases(raw, optional_fields, &HashMap::new())
+}
+
+/// [`parse_response`], also reading the fields named in `field_aliases` (alias to field,
+/// as in `Config::field_aliases`) as the `DeepSeekResponse` fields they map to
+pub fn parse_response_with_aliases(
+    raw: &str,
+    optional_fields: &[String],
+    field_aliases: &HashMap<String, String>,
+) -> Result<DeepSeekResponse, DeepSeekError> {
+    ensure_content(raw)?;
+
+    // Models sometimes wrap the JSON in a Markdown fence despite being told not to
+    let mut value: serde_json::Value = from_str_or_embedded(strip_code_fences(strip_bom(raw)))
+        .map_err(|e| DeepSeekError::ParseError {
+            message: format!("Failed to parse JSON response from DeepSeek: {}", e),
+        })?;
+    normalize_field_names(&mut value, field_aliases);
+
+    let missing: Vec<&str> = REQUIRED_RESPONSE_FIELDS
+        .iter()
+        .copied()
+        .filter(|field| !optional_fields.iter().any(|optional| optional == field))
+        .filter(|field| value.get(field).is_none_or(|v| v.is_null()))
+        .collect();
+    if !missing.is_empty() {
+        return Err(DeepSeekError::ParseError {
+            message: format!(
+                "Response is missing required field(s): {}",
+                missing.join(", ")
+            ),
+        });
+    }
+
+    serde_json::from_value(value).map_err(|e| DeepSeekError::ParseError {
+        message: format!("Failed to parse JSON response from DeepSeek: {}", e),
+    })
+}
+
+/// Rename aliased fields of a reply object to their `DeepSeekResponse` names: first the
+/// configured `field_aliases`, then [`RESPONSE_FIELD_ALIASES`]. A field already present
+/// under its own name wins, and the alias is dropped either way.
+fn normalize_field_names(value: &mut serde_json::Value, field_aliases: &HashMap<String, String>) {
+    let Some(object) = value.as_object_mut() else {
+        return;
+    };
+    let aliases = field_aliases
+        .iter()
+        .map(|(alias, field)| (alias.as_str(), field.as_str()))
+        .chain(RESPONSE_FIELD_ALIASES.iter().copied());
+    for (alias, field) in aliases {
+        if let Some(aliased) = object.remove(alias) {
+            object.entry(field).or_insert(aliased);
+        }
+    }
+}
+
+/// Fail with [`DeepSeekError::EmptyResponse`] when the model sent no content, instead of
+/// the opaque parse error an empty string would produce
+fn ensure_content(raw: &str) -> Result<(), DeepSeekError> {
+    if strip_bom(raw).trim().is_empty() {
+        return Err(DeepSeekError::EmptyResponse);
+    }
+    Ok(())
+}
+
+/// Precedes the per-call timestamp in the [`build_json_prompt`] instructions
+const PROMPT_TIMESTAMP_PREFIX: &str = "Current response timestamp: ";
+
+/// `messages` with the per-call timestamp from [`build_json_prompt`] blanked out, so
+/// requests differing only in when they were built share a cache entry and an in-flight call
+fn without_timestamps(messages: &[ChatMessage]) -> Vec<ChatMessage> {
+    messages
+        .iter()
+        .map(|message| {
+            let mut message = message.clone();
+            if let Some(at) = message.content.find(PROMPT_TIMESTAMP_PREFIX) {
+                let start = at + PROMPT_TIMESTAMP_PREFIX.len();
+                let end = message.content[start..]
+                    .find(' ')
+                    .map_or(message.content.len(), |len| start + len);
+                message.content.replace_range(start..end, "");
+            }
+            message
+        })
+        .collect()
+}
+
+/// Append the `DeepSeekResponse` JSON format instructions to a user query. Fields in
+/// `optional_fields` are described as optional, so the model may leave them empty.
+pub fn build_json_prompt(user_input: &str, optional_fields: &[String]) -> String {
+    let current_timestamp = Utc::now().to_rfc3339();
+    let kind = |field: &str| {
+        if optional_fields.iter().any(|optional| optional == field) {
+            "string, may be empty"
+        } else {
+            "string"
+        }
+    };
+
+    let json_format_prompt = format!(
+        r#"
+            Please respond with a JSON object containing the following fields:
+            {{
+            "title": "A concise title for the topic ({})",
+            "description": "A brief description or summary ({})",
+            "content": "The main content or detailed response ({})",
+            "category": "Optional category classification (string or null)",
+            "timestamp": "{}{} (string)",
+            "confidence": "Optional confidence score between 0.0 and 1.0 (number or null)"
+            }}
+
+            Make sure to provide valid JSON format in your response. Use the provided timestamp as the current response time.
+            Do not include any other text or comments in your response.
+        "#,
+        kind("title"),
+        kind("description"),
+        kind("content"),
+        PROMPT_TIMESTAMP_PREFIX,
+        current_timestamp
+    );
+
+    format!("{}\n\n{}", user_input, json_format_prompt)
+}
+
+/// Append instructions to answer with JSON matching `schema` to a user query
+pub fn build_schema_prompt(user_input: &str, schema: &serde_json::Value) -> String {
+    let schema = serde_json::to_string_pretty(schema).unwrap_or_else(|_| schema.to_string());
+    format!(
+        "{}\n\nRespond with a single JSON object that validates against this JSON Schema:\n{}\n\nDo not include any other text or comments in your response.",
+        user_input, schema
+    )
+}
+
+/// Corrective follow-up asking for `content` that matches the schema of its category
+fn build_category_correction_prompt(
+    category: &str,
+    schema: &serde_json::Value,
+    violations: &[String],
+) -> String {
+    let schema = serde_json::to_string_pretty(schema).unwrap_or_else(|_| schema.to_string());
+    format!(
+        "For the category \"{}\", the \"content\" field must be a JSON document, encoded as a string, that validates against this JSON Schema:\n{}\n\nYour previous content did not: {}\n\nReply again with the same JSON object and corrected content.",
+        category,
+        schema,
+        violations.join("; ")
+    )
+}
+
+/// JSON error body returned by DeepSeek and OpenAI-compatible APIs
+#[derive(Debug, Deserialize)]
+struct ApiErrorBody {
+    error: ApiErrorDetail,
+}
+
+#[derive(Debug, Deserialize)]
+struct ApiErrorDetail {
+    message: Option<String>,
+    #[serde(rename = "type")]
+    error_type: Option<String>,
+    /// A string for DeepSeek, a number or null for some gateways
+    code: Option<serde_json::Value>,
+}
+
+/// Build an `ApiError` from a response body, taking message, type and code from the JSON
+/// error object when present and falling back to the raw text otherwise
+pub(crate) fn api_error(status: u16, body: &str) -> DeepSeekError {
+    let Ok(ApiErrorBody { error }) = serde_json::from_str::<ApiErrorBody>(body) else {
+        return DeepSeekError::ApiError {
+            status,
+            message: body.to_string(),
+            error_type: None,
+            code: None,
+        };
+    };
+
+    let code = error.code.and_then(|code| match code {
+        serde_json::Value::String(code) => Some(code),
+        serde_json::Value::Null => None,
+        other => Some(other.to_string()),
+    });
+    DeepSeekError::ApiError {
+        status,
+        message: error.message.unwrap_or_else(|| body.to_string()),
+        error_type: error.error_type,
+        code,
+    }
+}
+
+/// "type: ..., code: ..." summary of a structured API error, if it has either
+pub fn api_error_details(error_type: Option<&str>, code: Option<&str>) -> Option<String> {
+    let parts: Vec<String> = [("type", error_type), ("code", code)]
+        .into_iter()
+        .filter_map(|(label, value)| value.map(|value| format!("{}: {}", label, value)))
+        .collect();
+    (!parts.is_empty()).then(|| parts.join(", "))
+}
+
+/// Full URL of the chat completions endpoint for `config`, ignoring a trailing slash in the
+/// base URL
+fn chat_url(config: &Config) -> String {
+    let path = config.chat_path.as_deref().unwrap_or(DEFAULT_CHAT_PATH);
+    format!("{}{}", config.base_url.trim_end_matches('/'), path)
+}
+
+/// Longest server-requested wait honored before a retry, so a huge `Retry-After` cannot
+/// stall the client for hours
+const MAX_RETRY_AFTER: Duration = Duration::from_secs(60);
+
+/// Parse a `Retry-After` header value given either as delay-seconds or an HTTP date,
+/// capped at [`MAX_RETRY_AFTER`]
+pub(crate) fn parse_retry_after(value: &str) -> Option<Duration> {
+    let value = value.trim();
+    let wait = match value.parse::<u64>() {
+        Ok(seconds) => Duration::from_secs(seconds),
+        Err(_) => {
+            let date = chrono::DateTime::parse_from_rfc2822(value).ok()?;
+            (date.with_timezone(&Utc) - Utc::now())
+                .to_std()
+                .unwrap_or(Duration::ZERO)
+        }
+    };
+    Some(wait.min(MAX_RETRY_AFTER))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::time::advance;
+    use wiremock::matchers::{body_partial_json, header, method, path, query_param};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    fn build_config(base_url: &str) -> Config {
+        Config {
+            api_key: "test_key".to_string(),
+            base_url: base_url.to_string(),
+            model: "test-model".to_string(),
+            max_tokens: 256,
+            temperature: 0.1,
+            timeout: 2,
+            ..Config::default()
+        }
+    }
+
+    fn build_client(base_url: &str) -> DeepSeekClient {
+        DeepSeekClient::new(build_config(base_url)).expect("client should be created")
+    }
+
+    fn api_success_body(content_json: &str) -> serde_json::Value {
+        serde_json::json!({
+            "choices": [
+                { "message": { "role": "assistant", "content": content_json } }
+            ]
+        })
+    }
+
+    #[test]
+    fn new_with_invalid_config_returns_config_error() {
+        let bad_config = Config {
+            api_key: String::new(),
+            base_url: "http://localhost".to_string(),
+            model: "m".to_string(),
+            max_tokens: 1,
+            temperature: 0.0,
+            timeout: 1,
+            ..Config::default()
+        };
+
+        let err = DeepSeekClient::new(bad_config).unwrap_err();
+        match err {
+            DeepSeekError::ConfigError { message } => {
+                assert!(message.contains("API key cannot be empty"))
+            }
+            other => panic!("expected ConfigError, got {other}"),
+        }
+    }
+
+    #[tokio::test]
+    async fn send_request_success_parses_response() {
+        let server = MockServer::start().await;
+        let client = build_client(&server.uri());
+
+        let content = serde_json::json!({
+            "title": "Hello",
+            "description": "World",
+            "content": "Body",
+            "category": "demo",
+            "timestamp": "2024-01-01T00:00:00Z",
+            "confidence": 0.9
+        })
+        .to_string();
+
+        Mock::given(method("POST"))
+            .and(path("/chat/completions"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(api_success_body(&content)))
+            .mount(&server)
+            .await;
+
+        let response = client
+            .send_request("please respond in json object format")
+            .await
+            .expect("request should succeed");
+
+        assert_eq!(response.title, "Hello");
+        assert_eq!(response.description, "World");
+        assert_eq!(response.content, "Body");
+        assert_eq!(response.category.as_deref(), Some("demo"));
+        assert_eq!(response.timestamp.as_deref(), Some("2024-01-01T00:00:00Z"));
+        assert!((response.confidence.unwrap_or_default() - 0.9).abs() < f32::EPSILON);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn send_request_retries_and_returns_server_busy() {
+        let server = MockServer::start().await;
+        let client = build_client(&server.uri());
+
+        // Always return 503 to trigger retries and final failure
+        Mock::given(method("POST"))
+            .and(path("/chat/completions"))
+            .respond_with(ResponseTemplate::new(503).set_body_string("busy"))
+            .mount(&server)
+            .await;
+
+        let task = tokio::spawn({
+            let client = client.clone();
+            async move { client.send_request("x").await }
+        });
+
+        // First backoff: 500ms, second: 1000ms
+        advance(Duration::from_millis(500)).await;
+        tokio::task::yield_now().await;
+        advance(Duration::from_millis(1000)).await;
+        tokio::task::yield_now().await;
+
+        let err = task.await.expect("join ok").expect_err("should fail");
+        match err {
+            DeepSeekError::ServerBusy { .. } => {}
+            DeepSeekError::ApiError { status: 503, .. } => {}
+            DeepSeekError::Timeout { .. } => {}
+            other => panic!("expected ServerBusy, 503 ApiError, or Timeout, got {other}"),
+        }
+    }
+
+    #[tokio::test]
+    async fn send_request_raw_returns_unparsed_content() {
+        let server = MockServer::start().await;
+        let client = build_client(&server.uri());
+
+        let almost_json = "{\"title\": \"t\", \"description\": \"d\",}";
+        Mock::given(method("POST"))
+            .and(path("/chat/completions"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(api_success_body(almost_json)))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let raw = client.send_request_raw("hi").await.unwrap();
+        assert_eq!(raw, almost_json);
+
+        let requests = server.received_requests().await.unwrap();
+        let body: serde_json::Value = serde_json::from_slice(&requests[0].body).unwrap();
+        assert_eq!(body["messages"][0]["role"], "system");
+        assert!(
+            body["messages"][1]["content"]
+                .as_str()
+                .unwrap()
+                .contains("JSON object")
+        );
+    }
+
+    #[tokio::test]
+    async fn internal_server_error_is_retried() {
+        let server = MockServer::start().await;
+        let client = build_client(&server.uri());
+
+        Mock::given(method("POST"))
+            .and(path("/chat/completions"))
+            .respond_with(ResponseTemplate::new(500).set_body_string("oops"))
+            .up_to_n_times(1)
+            .expect(1)
+            .mount(&server)
+            .await;
+        let ok = serde_json::json!({ "title": "t", "description": "d", "content": "c" });
+        Mock::given(method("POST"))
+            .and(path("/chat/completions"))
+            .respond_with(
+                ResponseTemplate::new(200).set_body_json(api_success_body(&ok.to_string())),
+            )
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let response = client.send_request("hi").await.unwrap();
+        assert_eq!(response.title, "t");
+    }
+
+    #[tokio::test]
+    async fn internal_server_error_is_not_retried_when_disabled() {
+        let server = MockServer::start().await;
+        let mut cfg = build_config(&server.uri());
+        cfg.retry_on_500 = false;
+        let client = DeepSeekClient::new(cfg).unwrap();
+
+        Mock::given(method("POST"))
+            .and(path("/chat/completions"))
+            .respond_with(ResponseTemplate::new(500).set_body_string("oops"))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let err = client.send_request("hi").await.unwrap_err();
+        assert!(matches!(err, DeepSeekError::ApiError { status: 500, .. }));
+    }
+
+    #[tokio::test]
+    async fn json_error_body_is_parsed_into_api_error() {
+        let server = MockServer::start().await;
+        let client = build_client(&server.uri());
+
+        Mock::given(method("POST"))
+            .and(path("/chat/completions"))
+            .respond_with(ResponseTemplate::new(401).set_body_json(serde_json::json!({
+                "error": {
+                    "message": "Authentication Fails (no such user)",
+                    "type": "authentication_error",
+                    "code": "invalid_request_error"
+                }
+            })))
+            .mount(&server)
+            .await;
+
+        let err = client.send_request("hi").await.unwrap_err();
+        match err {
+            DeepSeekError::ApiError {
+                status,
+                message,
+                error_type,
+                code,
+            } => {
+                assert_eq!(status, 401);
+                assert_eq!(message, "Authentication Fails (no such user)");
+                assert_eq!(error_type.as_deref(), Some("authentication_error"));
+                assert_eq!(code.as_deref(), Some("invalid_request_error"));
+            }
+            other => panic!("expected ApiError, got {other}"),
+        }
+    }
+
+    #[tokio::test]
+    async fn reasoning_content_is_returned() {
+        let server = MockServer::start().await;
+        let client = build_client(&server.uri());
+
+        Mock::given(method("POST"))
+            .and(path("/chat/completions"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "choices": [{
+                    "message": {
+                        "role": "assistant",
+                        "content": "{}",
+                        "reasoning_content": "First, consider the question."
+                    }
+                }]
+            })))
+            .mount(&server)
+            .await;
+
+        let (content, reasoning) = client
+            .send_messages_raw_with_reasoning(vec![user_message("hi")])
+            .await
+            .unwrap();
+        assert_eq!(content, "{}");
+        assert_eq!(reasoning.as_deref(), Some("First, consider the question."));
+    }
+
+    #[tokio::test]
+    async fn send_messages_raw_maps_http_errors() {
+        let server = MockServer::start().await;
+        let client = build_client(&server.uri());
+
+        // 400 -> ApiError
+        Mock::given(method("POST"))
+            .and(path("/chat/completions"))
+            .respond_with(ResponseTemplate::new(400).set_body_string("bad req"))
+            .mount(&server)
+            .await;
+
+        let err = client
+            .send_messages_raw(vec![ChatMessage {
+                role: "user".to_string(),
+                content: "hi".to_string(),
+            }])
+            .await
+            .expect_err("should map to ApiError");
+
+        match err {
+            DeepSeekError::ApiError {
+                status,
+                message,
+                error_type,
+                ..
+            } => {
+                assert_eq!(status, 400);
+                assert!(message.contains("bad req"));
+                assert_eq!(error_type, None);
+            }
+            other => panic!("expected ApiError, got {other}"),
+        }
+    }
+
+    #[tokio::test]
+    async fn send_request_empty_choices_is_parse_error() {
+        let server = MockServer::start().await;
+        let client = build_client(&server.uri());
+
+        let body = serde_json::json!({ "choices": [] });
+        Mock::given(method("POST"))
+            .and(path("/chat/completions"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(body))
+            .mount(&server)
+            .await;
+
+        let err = client
+            .send_request("x")
+            .await
+            .expect_err("should be parse error");
+        assert!(matches!(err, DeepSeekError::ParseError { .. }));
+    }
+
+    #[tokio::test]
+    async fn send_request_invalid_json_in_content_is_parse_error() {
+        let server = MockServer::start().await;
+        let client = build_client(&server.uri());
+
+        let content = "not-json";
+        Mock::given(method("POST"))
+            .and(path("/chat/completions"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(api_success_body(content)))
+            .mount(&server)
+            .await;
+
+        let err = client
+            .send_request("x")
+            .await
+            .expect_err("should be parse error");
+        assert!(matches!(err, DeepSeekError::ParseError { .. }));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn send_messages_raw_times_out() {
+        let server = MockServer::start().await;
+        let mut cfg = build_config(&server.uri());
+        cfg.timeout = 1; // seconds
+        let client = DeepSeekClient::new(cfg).unwrap();
+
+        // Delay response beyond client timeout
+        Mock::given(method("POST"))
+            .and(path("/chat/completions"))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .set_delay(Duration::from_secs(2))
+                    .set_body_json(api_success_body(
+                        &serde_json::json!({
+                            "title": "t",
+                            "description": "d",
+                            "content": "c",
+                            "category": null,
+                            "timestamp": "2024-01-01T00:00:00Z",
+                            "confidence": 0.5
+                        })
+                        .to_string(),
+                    )),
+            )
+            .mount(&server)
+            .await;
+
+        let task = tokio::spawn({
+            let client = client.clone();
+            async move {
+                client
+                    .send_messages_raw(vec![ChatMessage {
+                        role: "user".to_string(),
+                        content: "hello".to_string(),
+                    }])
+                    .await
+            }
+        });
+
+        advance(Duration::from_secs(2)).await;
+        tokio::task::yield_now().await;
+
+        let err = task.await.unwrap().expect_err("should timeout");
+        match err {
+            DeepSeekError::Timeout { seconds } => assert_eq!(seconds, 1),
+            other => panic!("expected Timeout, got {other}"),
+        }
+    }
+
+    #[tokio::test]
+    async fn connect_timeout_separates_connect_failures_from_slow_responses() {
+        // Nothing listens on a port that was just released, so connecting fails quickly
+        let closed_port = std::net::TcpListener::bind("127.0.0.1:0")
+            .unwrap()
+            .local_addr()
+            .unwrap()
+            .port();
+        let mut config = build_config(&format!("http://127.0.0.1:{}", closed_port));
+        config.connect_timeout = Some(1);
+        config.retry_max_attempts = 1;
+        let client = DeepSeekClient::new(config).unwrap();
+        let err = client.send_request_raw("hello").await.unwrap_err();
+        assert!(
+            matches!(err, DeepSeekError::NetworkError { .. }),
+            "expected NetworkError, got {err}"
+        );
+
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/chat/completions"))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .set_body_json(api_success_body("{}"))
+                    .set_delay(Duration::from_secs(3)),
+            )
+            .mount(&server)
+            .await;
+        let mut config = build_config(&server.uri());
+        config.timeout = 1;
+        config.connect_timeout = Some(1);
+        config.retry_max_attempts = 1;
+        let client = DeepSeekClient::new(config).unwrap();
+        match client.send_request_raw("hello").await.unwrap_err() {
+            DeepSeekError::Timeout { seconds } => assert_eq!(seconds, 1),
+            other => panic!("expected Timeout, got {other}"),
+        }
+    }
+
+    #[tokio::test]
+    async fn user_agent_defaults_and_can_be_overridden() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(header("user-agent", DEFAULT_USER_AGENT))
+            .respond_with(ResponseTemplate::new(200).set_body_json(api_success_body("{}")))
+            .expect(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .and(header("user-agent", "acme-gateway/2.0"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(api_success_body("{}")))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        build_client(&server.uri())
+            .send_request_raw("hi")
+            .await
+            .unwrap();
+
+        let mut config = build_config(&server.uri());
+        config.user_agent = Some("acme-gateway/2.0".to_string());
+        DeepSeekClient::new(config)
+            .unwrap()
+            .send_request_raw("hi")
+            .await
+            .unwrap();
+    }
+
+    #[test]
+    fn parse_retry_after_accepts_seconds_and_http_dates() {
+        assert_eq!(parse_retry_after("2"), Some(Duration::from_secs(2)));
+        assert_eq!(
+            parse_retry_after("Wed, 21 Oct 2015 07:28:00 GMT"),
+            Some(Duration::ZERO)
+        );
+        let future = (Utc::now() + chrono::Duration::seconds(30)).to_rfc2822();
+        let wait = parse_retry_after(&future).expect("date should parse");
+        assert!(wait > Duration::from_secs(25) && wait <= Duration::from_secs(30));
+        assert_eq!(parse_retry_after("soon"), None);
+
+        // Far-off waits are capped
+        assert_eq!(parse_retry_after("86400"), Some(MAX_RETRY_AFTER));
+        let far = (Utc::now() + chrono::Duration::days(1)).to_rfc2822();
+        assert_eq!(parse_retry_after(&far), Some(MAX_RETRY_AFTER));
+    }
+
+    #[tokio::test]
+    async fn retry_after_header_of_429_is_reported() {
+        let server = MockServer::start().await;
+        let mut cfg = build_config(&server.uri());
+        cfg.retry_max_attempts = 1;
+        let client = DeepSeekClient::new(cfg).unwrap();
+
+        Mock::given(method("POST"))
+            .and(path("/chat/completions"))
+            .respond_with(ResponseTemplate::new(429).insert_header("Retry-After", "2"))
+            .mount(&server)
+            .await;
+
+        let err = client.send_request("x").await.unwrap_err();
+        assert_eq!(err.retry_after(), Some(Duration::from_secs(2)));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn send_request_waits_for_retry_after_when_server_busy() {
+        let mut cfg = build_config("http://localhost");
+        cfg.retry_base_backoff_ms = 1;
+        let transport = FakeTransport::new(vec![
+            Err(DeepSeekError::ServerBusy {
+                retry_after: Some(Duration::from_secs(2)),
+            }),
+            Ok(
+                api_success_body(r#"{"title": "t", "description": "d", "content": "c"}"#)
+                    .to_string(),
+            ),
+        ]);
+        let client = DeepSeekClient::with_transport(cfg, transport).unwrap();
+
+        let started = tokio::time::Instant::now();
+        let response = client
+            .send_request("x")
+            .await
+            .expect("retry should succeed");
+        assert_eq!(response.title, "t");
+        assert!(
+            started.elapsed() >= Duration::from_secs(2),
+            "retried after {:?}, before Retry-After elapsed",
+            started.elapsed()
+        );
+    }
+
+    #[tokio::test]
+    async fn single_retry_attempt_disables_retrying() {
+        let server = MockServer::start().await;
+        let mut cfg = build_config(&server.uri());
+        cfg.retry_max_attempts = 1;
+        let client = DeepSeekClient::new(cfg).unwrap();
+
+        Mock::given(method("POST"))
+            .and(path("/chat/completions"))
+            .respond_with(ResponseTemplate::new(503).set_body_string("busy"))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let err = client
+            .send_messages_raw(vec![ChatMessage {
+                role: "user".to_string(),
+                content: "hi".to_string(),
+            }])
+            .await
+            .expect_err("should fail without retrying");
+        assert!(err.is_server_busy());
+    }
+
+    #[tokio::test]
+    async fn retry_deadline_stops_retrying_before_it_passes() {
+        let server = MockServer::start().await;
+        let mut cfg = build_config(&server.uri());
+        cfg.retry_max_attempts = 1;
+        cfg.retry_base_backoff_ms = 400;
+        cfg.retry_deadline = Some(1);
+        let client = DeepSeekClient::new(cfg).unwrap();
+
+        // Backoffs of 400ms then 800ms: the second retry would end past the 1s deadline
+        Mock::given(method("POST"))
+            .and(path("/chat/completions"))
+            .respond_with(ResponseTemplate::new(503).set_body_string("busy"))
+            .expect(2)
+            .mount(&server)
+            .await;
+
+        let started = Instant::now();
+        let err = client
+            .send_messages_raw(vec![user_message("hi")])
+            .await
+            .expect_err("should give up at the deadline");
+        assert!(err.is_server_busy());
+        assert!(started.elapsed() < Duration::from_secs(1));
+    }
+
+    #[tokio::test]
+    async fn send_messages_raw_retries_transient_errors() {
+        let server = MockServer::start().await;
+        let mut cfg = build_config(&server.uri());
+        cfg.retry_base_backoff_ms = 1;
+        let client = DeepSeekClient::new(cfg).unwrap();
+
+        Mock::given(method("POST"))
+            .and(path("/chat/completions"))
+            .respond_with(ResponseTemplate::new(502))
+            .up_to_n_times(2)
+            .expect(2)
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/chat/completions"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(api_success_body("{}")))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let raw = client
+            .send_messages_raw(vec![ChatMessage {
+                role: "user".to_string(),
+                content: "hi".to_string(),
+            }])
+            .await
+            .expect("third attempt should succeed");
+        assert_eq!(raw, "{}");
+    }
+
+    #[tokio::test]
+    async fn probe_reports_all_fields_for_compatible_gateway() {
+        let server = MockServer::start().await;
+        let client = build_client(&server.uri());
+
+        Mock::given(method("POST"))
+            .and(path("/chat/completions"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "model": "test-model",
+                "choices": [{
+                    "message": { "role": "assistant", "content": "{\"ok\": true}" },
+                    "finish_reason": "stop"
+                }],
+                "usage": { "prompt_tokens": 5, "completion_tokens": 4, "total_tokens": 9 }
+            })))
+            .mount(&server)
+            .await;
+
+        let report = client
+            .probe_compatibility()
+            .await
+            .expect("probe should succeed");
+        assert!(report.is_compatible());
+        assert_eq!(report.missing().count(), 0);
+        assert_eq!(
+            report.endpoint,
+            format!("{}/chat/completions", server.uri())
+        );
+    }
+
+    #[tokio::test]
+    async fn check_sends_one_token_ping_and_surfaces_invalid_key() {
+        let server = MockServer::start().await;
+        let client = build_client(&server.uri());
+
+        Mock::given(method("POST"))
+            .and(path("/chat/completions"))
+            .and(body_partial_json(serde_json::json!({
+                "max_tokens": 1,
+                "messages": [{ "role": "user", "content": "ping" }]
+            })))
+            .respond_with(ResponseTemplate::new(200).set_body_json(api_success_body("pong")))
+            .up_to_n_times(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/chat/completions"))
+            .respond_with(ResponseTemplate::new(401).set_body_string("bad key"))
+            .mount(&server)
+            .await;
+
+        client.check().await.expect("check should pass");
+        match client.check().await.unwrap_err() {
+            DeepSeekError::ApiError { status, .. } => assert_eq!(status, 401),
+            other => panic!("expected 401 ApiError, got {other}"),
+        }
+    }
+
+    #[tokio::test]
+    async fn probe_lists_missing_fields() {
+        let server = MockServer::start().await;
+        let client = build_client(&server.uri());
+
+        Mock::given(method("POST"))
+            .and(path("/chat/completions"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "choices": [{ "text": "legacy completion" }]
+            })))
+            .mount(&server)
+            .await;
+
+        let report = client
+            .probe_compatibility()
+            .await
+            .expect("probe should succeed");
+        assert!(!report.is_compatible());
+        let missing: Vec<&str> = report.missing().map(|c| c.path.as_str()).collect();
+        assert!(missing.contains(&"choices[].message.content"));
+        assert!(missing.contains(&"usage"));
+        assert!(!missing.contains(&"choices"));
+    }
+
+    #[tokio::test]
+    async fn denylisted_input_is_rejected_without_calling_api() {
+        let server = MockServer::start().await;
+        let mut cfg = build_config(&server.uri());
+        cfg.input_denylist = vec![r"(?i)\bpassword\b".to_string(), "secret".to_string()];
+        let client = DeepSeekClient::new(cfg).unwrap();
+
+        Mock::given(method("POST"))
+            .and(path("/chat/completions"))
+            .respond_with(ResponseTemplate::new(200))
+            .expect(0)
+            .mount(&server)
+            .await;
+
+        let err = client
+            .send_request("what is the admin PASSWORD?")
+            .await
+            .expect_err("input should be rejected");
+        match err {
+            DeepSeekError::InputRejected { pattern } => {
+                assert_eq!(pattern, r"(?i)\bpassword\b");
+            }
+            other => panic!("expected InputRejected, got {other}"),
+        }
+
+        let err = client
+            .send_messages_raw(vec![ChatMessage {
+                role: "user".to_string(),
+                content: "tell me a secret".to_string(),
+            }])
+            .await
+            .expect_err("input should be rejected");
+        assert!(err.to_string().contains("secret"));
+        assert!(!err.to_string().contains("tell me"));
+    }
+
+    #[test]
+    fn invalid_denylist_pattern_is_config_error() {
+        let mut cfg = build_config("http://localhost");
+        cfg.input_denylist = vec!["(unclosed".to_string()];
+        let err = DeepSeekClient::new(cfg).unwrap_err();
+        assert!(matches!(err, DeepSeekError::ConfigError { .. }));
+    }
+
+    fn weather_tool() -> ToolDef {
+        ToolDef::function(
+            "get_weather",
+            "Current weather for a city",
+            serde_json::json!({
+                "type": "object",
+                "properties": { "city": { "type": "string" } },
+                "required": ["city"]
+            }),
+        )
+    }
+
+    fn user_message(content: &str) -> ChatMessage {
+        ChatMessage {
+            role: "user".to_string(),
+            content: content.to_string(),
+        }
+    }
+
+    #[tokio::test]
+    async fn send_messages_with_tools_parses_tool_calls() {
+        let server = MockServer::start().await;
+        let client = build_client(&server.uri());
+
+        let body = serde_json::json!({
+            "choices": [{
+                "message": {
+                    "role": "assistant",
+                    "content": null,
+                    "tool_calls": [{
+                        "id": "call_1",
+                        "type": "function",
+                        "function": { "name": "get_weather", "arguments": "{\"city\": \"Oslo\"}" }
+                    }]
+                }
+            }]
+        });
+
+        Mock::given(method("POST"))
+            .and(path("/chat/completions"))
+            .and(body_partial_json(serde_json::json!({
+                "tools": [{ "type": "function", "function": { "name": "get_weather" } }]
+            })))
+            .respond_with(ResponseTemplate::new(200).set_body_json(body))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let result = client
+            .send_messages_with_tools(vec![user_message("Weather in Oslo?")], vec![weather_tool()])
+            .await
+            .expect("tool call should parse");
+
+        assert_eq!(
+            result,
+            ToolCallResult::ToolCalls(vec![ToolCall {
+                id: "call_1".to_string(),
+                name: "get_weather".to_string(),
+                arguments: serde_json::json!({ "city": "Oslo" }),
+            }])
+        );
+    }
+
+    #[tokio::test]
+    async fn send_messages_with_tools_returns_text_without_calls() {
+        let server = MockServer::start().await;
+        let client = build_client(&server.uri());
+
+        Mock::given(method("POST"))
+            .and(path("/chat/completions"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(api_success_body("Hello!")))
+            .mount(&server)
+            .await;
+
+        let result = client
+            .send_messages_with_tools(vec![user_message("Hi")], vec![weather_tool()])
+            .await
+            .unwrap();
+        assert_eq!(result, ToolCallResult::Text("Hello!".to_string()));
+    }
+
+    #[tokio::test]
+    async fn send_messages_with_tools_rejects_malformed_arguments() {
+        let server = MockServer::start().await;
+        let client = build_client(&server.uri());
+
+        let body = serde_json::json!({
+            "choices": [{
+                "message": {
+                    "role": "assistant",
+                    "tool_calls": [{
+                        "id": "call_1",
+                        "type": "function",
+                        "function": { "name": "get_weather", "arguments": "{city: Oslo" }
+                    }]
+                }
+            }]
+        });
+
+        Mock::given(method("POST"))
+            .and(path("/chat/completions"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(body))
+            .mount(&server)
+            .await;
+
+        let err = client
+            .send_messages_with_tools(vec![user_message("Weather?")], vec![weather_tool()])
+            .await
+            .unwrap_err();
+        assert!(matches!(err, DeepSeekError::ParseError { .. }));
+        assert!(err.to_string().contains("get_weather"));
+    }
+
+    #[test]
+    fn explain_includes_error_specifics() {
+        let err = DeepSeekError::ApiError {
+            status: 401,
+            message: "invalid key".to_string(),
+            error_type: Some("authentication_error".to_string()),
+            code: None,
+        };
+        let steps = err.explain();
+        assert!(steps[0].contains("invalid key"));
+        assert!(steps[1].contains("type: authentication_error"));
+        assert!(steps.iter().any(|s| s.contains("DEEPSEEK_API_KEY")));
+
+        let busy = DeepSeekError::ServerBusy {
+            retry_after: Some(Duration::from_secs(7)),
+        };
+        assert!(busy.explain().iter().any(|s| s.contains("7 seconds")));
+        assert!(busy.explain()[0].contains("500"));
+    }
+
+    #[test]
+    fn build_json_prompt_appends_format_instructions() {
+        let prompt = build_json_prompt("What is Rust?", &[]);
+        assert!(prompt.starts_with("What is Rust?\n\n"));
+        assert!(prompt.contains("\"confidence\""));
+        assert!(!prompt.contains("may be empty"));
+
+        let prompt = build_json_prompt("What is Rust?", &["title".to_string()]);
+        assert!(prompt.contains("A concise title for the topic (string, may be empty)"));
+        assert!(prompt.contains("A brief description or summary (string)"));
+    }
+
+    #[test]
+    fn parse_response_names_missing_required_fields() {
+        let raw = r#"{"description": "d"}"#;
+        let err = parse_response(raw, &[]).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Failed to parse response: Response is missing required field(s): title, content"
+        );
+
+        // A null counts as missing
+        let err = parse_response(
+            r#"{"title": null, "description": "d", "content": "c"}"#,
+            &[],
+        )
+        .unwrap_err();
+        assert!(err.to_string().ends_with("required field(s): title"));
+    }
+
+    #[test]
+    fn parse_response_accepts_missing_optional_fields() {
+        let optional = ["title".to_string(), "content".to_string()];
+        let response = parse_response(r#"{"description": "d"}"#, &optional).unwrap();
+        assert_eq!(response.title, "");
+        assert_eq!(response.description, "d");
+        assert_eq!(response.content, "");
+    }
+
+    #[test]
+    fn parse_response_reads_common_field_aliases() {
+        let response = parse_response(r#"{"title": "t", "desc": "d", "text": "c"}"#, &[]).unwrap();
+        assert_eq!(response.description, "d");
+        assert_eq!(response.content, "c");
+
+        // The real name wins over an alias instead of failing as a duplicate field
+        let response = parse_response(
+            r#"{"title": "t", "description": "d", "desc": "other", "content": "c"}"#,
+            &[],
+        )
+        .unwrap();
+        assert_eq!(response.description, "d");
+
+        let direct: DeepSeekResponse =
+            serde_json::from_str(r#"{"title": "t", "desc": "d", "text": "c"}"#).unwrap();
+        assert_eq!(direct.content, "c");
+    }
+
+    #[test]
+    fn parse_response_with_aliases_applies_configured_names() {
+        let aliases = HashMap::from([
+            ("headline".to_string(), "title".to_string()),
+            ("score".to_string(), "confidence".to_string()),
+        ]);
+        let raw = r#"{"headline": "t", "summary": "d", "body": "c", "score": 0.8}"#;
+        assert!(parse_response(raw, &[]).is_err());
+
+        let mut aliases = aliases;
+        aliases.insert("summary".to_string(), "description".to_string());
+        aliases.insert("body".to_string(), "content".to_string());
+        let response = parse_response_with_aliases(raw, &[], &aliases).unwrap();
+        assert_eq!(response.title, "t");
+        assert_eq!(response.description, "d");
+        assert_eq!(response.content, "c");
+        assert_eq!(response.confidence, Some(0.8));
+    }
+
+    #[tokio::test]
+    async fn send_request_multi_parses_every_choice() {
+        let server = MockServer::start().await;
+        let client = build_client(&server.uri());
+
+        let choice = |title: &str| {
+            serde_json::json!({
+                "message": {
+                    "role": "assistant",
+                    "content": serde_json::json!({
+                        "title": title, "description": "d", "content": "c"
+                    })
+                    .to_string()
+                }
+            })
+        };
+        Mock::given(method("POST"))
+            .and(path("/chat/completions"))
+            .and(body_partial_json(serde_json::json!({ "n": 2 })))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "choices": [choice("First"), choice("Second")]
+            })))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let (responses, _) = client.send_request_multi("hi", 2).await.unwrap();
+        let titles: Vec<&str> = responses.iter().map(|r| r.title.as_str()).collect();
+        assert_eq!(titles, ["First", "Second"]);
+    }
+
+    /// Transport replaying canned replies in order, without any HTTP
+    struct FakeTransport {
+        replies: Mutex<std::collections::VecDeque<Result<String, DeepSeekError>>>,
+    }
+
+    impl FakeTransport {
+        fn new(replies: Vec<Result<String, DeepSeekError>>) -> Arc<Self> {
+            Arc::new(Self {
+                replies: Mutex::new(replies.into()),
+            })
+        }
+    }
+
+    impl ChatTransport for FakeTransport {
+        fn post<'a>(
+            &'a self,
+            _request: &'a ChatRequest,
+        ) -> futures::future::BoxFuture<'a, Result<String, DeepSeekError>> {
+            let reply = self
+                .replies
+                .lock()
+                .unwrap()
+                .pop_front()
+                .expect("no canned reply left");
+            Box::pin(async move { reply })
+        }
+    }
+
+    #[tokio::test]
+    async fn fake_transport_retries_busy_replies_and_parses_the_answer() {
+        let mut cfg = build_config("http://localhost");
+        cfg.retry_base_backoff_ms = 1;
+        let transport = FakeTransport::new(vec![
+            Err(DeepSeekError::ServerBusy { retry_after: None }),
+            Ok(
+                api_success_body(r#"{"title": "t", "description": "d", "content": "c"}"#)
+                    .to_string(),
+            ),
+        ]);
+        let client = DeepSeekClient::with_transport(cfg, transport).unwrap();
+
+        let response = client.send_request("hi").await.unwrap();
+        assert_eq!(response.title, "t");
+        assert_eq!(response.content, "c");
+        assert_eq!(client.requests_sent(), 2);
+    }
+
+    #[tokio::test]
+    async fn send_request_with_progress_reports_attempts_and_retries() {
+        let mut cfg = build_config("http://localhost");
+        cfg.retry_base_backoff_ms = 1;
+        let transport = FakeTransport::new(vec![
+            Err(DeepSeekError::ServerBusy { retry_after: None }),
+            Ok(
+                api_success_body(r#"{"title": "t", "description": "d", "content": "c"}"#)
+                    .to_string(),
+            ),
+        ]);
+        let client = DeepSeekClient::with_transport(cfg, transport).unwrap();
+
+        let events = Mutex::new(Vec::new());
+        let response = client
+            .send_request_with_progress("hi", |event| events.lock().unwrap().push(event))
+            .await
+            .unwrap();
+        assert_eq!(response.title, "t");
+        assert_eq!(
+            events.into_inner().unwrap(),
+            [
+                ProgressEvent::Sending { attempt: 1 },
+                ProgressEvent::Retrying {
+                    attempt: 2,
+                    delay: Duration::from_millis(1)
+                },
+                ProgressEvent::Sending { attempt: 2 },
+                ProgressEvent::Received,
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn fake_transport_reports_unparsable_envelopes() {
+        let transport = FakeTransport::new(vec![Ok("not json".to_string())]);
+        let client =
+            DeepSeekClient::with_transport(build_config("http://localhost"), transport).unwrap();
+
+        let err = client.send_request("hi").await.unwrap_err();
+        assert!(
+            matches!(&err, DeepSeekError::ParseError { message } if message.contains("Failed to parse API response")),
+            "unexpected error: {:?}",
+            err
+        );
+        assert_eq!(client.requests_sent(), 1);
+    }
+
+    #[test]
+    fn format_delay_picks_a_readable_unit() {
+        assert_eq!(format_delay(Duration::from_millis(500)), "500ms");
+        assert_eq!(format_delay(Duration::from_secs(2)), "2s");
+        assert_eq!(format_delay(Duration::from_millis(1500)), "1.5s");
+    }
+
+    #[test]
+    fn trailing_slash_in_base_url_is_ignored() {
+        let with_slash = build_client("https://api.deepseek.com/");
+        let without_slash = build_client("https://api.deepseek.com");
+        assert_eq!(with_slash.chat_url(), without_slash.chat_url());
+        assert_eq!(
+            with_slash.chat_url(),
+            "https://api.deepseek.com/chat/completions"
+        );
+    }
+
+    #[tokio::test]
+    async fn chat_path_overrides_the_completions_endpoint() {
+        let server = MockServer::start().await;
+        let mut cfg = build_config(&server.uri());
+        cfg.chat_path = Some("/v1/chat/completions".to_string());
+        let client = DeepSeekClient::new(cfg).unwrap();
+
+        Mock::given(method("POST"))
+            .and(path("/v1/chat/completions"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(api_success_body(r#"{"n":1}"#)))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let reply = client
+            .send_messages_raw(vec![user_message("hi")])
+            .await
+            .unwrap();
+        assert_eq!(reply, r#"{"n":1}"#);
+    }
+
+    #[tokio::test]
+    async fn auth_style_controls_how_the_api_key_is_sent() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/chat/completions"))
+            .and(header("api-key", "test_key"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(api_success_body(r#"{"n":1}"#)))
+            .expect(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/chat/completions"))
+            .and(query_param("api-key", "test_key"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(api_success_body(r#"{"n":2}"#)))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let mut cfg = build_config(&server.uri());
+        cfg.auth_style = AuthStyle::ApiKeyHeader;
+        let client = DeepSeekClient::new(cfg.clone()).unwrap();
+        let reply = client
+            .send_messages_raw(vec![user_message("hi")])
+            .await
+            .unwrap();
+        assert_eq!(reply, r#"{"n":1}"#);
+
+        cfg.auth_style = AuthStyle::QueryParam;
+        let client = DeepSeekClient::new(cfg).unwrap();
+        let preview = client.preview_request(&[user_message("hi")], "test-model");
+        assert!(preview["url"].as_str().unwrap().ends_with("?api-key=***"));
+        assert!(preview["headers"].get("Authorization").is_none());
+        let reply = client
+            .send_messages_raw(vec![user_message("hi")])
+            .await
+            .unwrap();
+        assert_eq!(reply, r#"{"n":2}"#);
+    }
+
+    #[tokio::test]
+    async fn list_models_returns_the_listed_ids() {
+        let server = MockServer::start().await;
+        let client = build_client(&format!("{}/", server.uri()));
+
+        Mock::given(method("GET"))
+            .and(path("/models"))
+            .and(header("Authorization", "Bearer test_key"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "object": "list",
+                "data": [
+                    { "id": "deepseek-chat", "object": "model", "owned_by": "deepseek" },
+                    { "id": "deepseek-reasoner", "object": "model", "owned_by": "deepseek" }
+                ]
+            })))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let models = client.list_models().await.unwrap();
+        assert_eq!(models, ["deepseek-chat", "deepseek-reasoner"]);
+    }
+
+    #[tokio::test]
+    async fn list_models_maps_error_statuses() {
+        let server = MockServer::start().await;
+        let client = build_client(&server.uri());
+
+        Mock::given(method("GET"))
+            .and(path("/models"))
+            .respond_with(ResponseTemplate::new(401).set_body_string("bad key"))
+            .mount(&server)
+            .await;
+
+        let err = client.list_models().await.unwrap_err();
+        assert!(matches!(err, DeepSeekError::ApiError { status: 401, .. }));
+
+        let fake = DeepSeekClient::with_transport(
+            build_config("http://localhost"),
+            FakeTransport::new(Vec::new()),
+        )
+        .unwrap();
+        assert!(matches!(
+            fake.list_models().await,
+            Err(DeepSeekError::ConfigError { .. })
+        ));
+    }
+
+    #[tokio::test]
+    async fn dedup_requests_shares_one_call_between_identical_requests() {
+        let server = MockServer::start().await;
+        let mut cfg = build_config(&server.uri());
+        cfg.dedup_requests = true;
+        let client = DeepSeekClient::new(cfg).unwrap();
+
+        let mut body = api_success_body(r#"{"n":1}"#);
+        body["usage"] =
+            serde_json::json!({ "prompt_tokens": 5, "completion_tokens": 5, "total_tokens": 10 });
+        Mock::given(method("POST"))
+            .and(path("/chat/completions"))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .set_body_json(body)
+                    .set_delay(Duration::from_millis(200)),
+            )
+            .expect(2)
+            .mount(&server)
+            .await;
+
+        let (first, second) = tokio::join!(
+            client.send_messages_raw(vec![user_message("same")]),
+            client.send_messages_raw(vec![user_message("same")]),
+        );
+        assert_eq!(first.unwrap(), r#"{"n":1}"#);
+        assert_eq!(second.unwrap(), r#"{"n":1}"#);
+        assert_eq!(client.requests_sent(), 1);
+        assert_eq!(client.tokens_used(), 10);
+
+        // Settled requests are forgotten, so a later identical request is sent again
+        client
+            .send_messages_raw(vec![user_message("same")])
+            .await
+            .unwrap();
+        assert_eq!(client.requests_sent(), 2);
+    }
+
+    #[tokio::test]
+    async fn dedup_requests_shares_one_call_between_identical_send_requests() {
+        let server = MockServer::start().await;
+        let mut cfg = build_config(&server.uri());
+        cfg.dedup_requests = true;
+        let client = DeepSeekClient::new(cfg).unwrap();
+
+        Mock::given(method("POST"))
+            .and(path("/chat/completions"))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .set_body_json(api_success_body(
+                        r#"{"title":"T","description":"D","content":"C"}"#,
+                    ))
+                    .set_delay(Duration::from_millis(200)),
+            )
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let (first, second) = tokio::join!(client.send_request("same"), async {
+            // Built later, so its prompt carries a different timestamp
+            tokio::time::sleep(Duration::from_millis(20)).await;
+            client.send_request("same").await
+        });
+        assert_eq!(first.unwrap().title, "T");
+        assert_eq!(second.unwrap().title, "T");
+        assert_eq!(client.requests_sent(), 1);
+    }
+
+    #[tokio::test]
+    async fn cache_dir_answers_repeated_requests_without_the_api() {
+        let server = MockServer::start().await;
+        let dir =
+            std::env::temp_dir().join(format!("deepseek_client_cache_{}", std::process::id()));
+        let mut cfg = build_config(&server.uri());
+        cfg.cache_dir = Some(dir.clone());
+        let client = DeepSeekClient::new(cfg).unwrap();
+
+        Mock::given(method("POST"))
+            .and(path("/chat/completions"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(api_success_body(r#"{"n":1}"#)))
+            .expect(2)
+            .mount(&server)
+            .await;
+
+        let first = client
+            .send_messages_raw(vec![user_message("hi")])
+            .await
+            .unwrap();
+        let second = client
+            .send_messages_raw(vec![user_message("hi")])
+            .await
+            .unwrap();
+        assert_eq!(first, r#"{"n":1}"#);
+        assert_eq!(second, first);
+        assert_eq!(client.requests_sent(), 1);
+
+        // A different request misses the cache
+        client
+            .send_messages_raw(vec![user_message("bye")])
+            .await
+            .unwrap();
+        assert_eq!(client.requests_sent(), 2);
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn cache_dir_answers_repeated_send_requests_without_the_api() {
+        let server = MockServer::start().await;
+        let dir = std::env::temp_dir().join(format!(
+            "deepseek_client_cache_structured_{}",
+            std::process::id()
+        ));
+        let mut cfg = build_config(&server.uri());
+        cfg.cache_dir = Some(dir.clone());
+        let client = DeepSeekClient::new(cfg).unwrap();
+
+        Mock::given(method("POST"))
+            .and(path("/chat/completions"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(api_success_body(
+                r#"{"title":"T","description":"D","content":"C"}"#,
+            )))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let first = client.send_request("hi").await.unwrap();
+        let second = client.send_request("hi").await.unwrap();
+        assert_eq!(second.title, first.title);
+        assert_eq!(client.requests_sent(), 1);
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn cache_dir_does_not_store_invalid_replies() {
+        let server = MockServer::start().await;
+        let dir = std::env::temp_dir().join(format!(
+            "deepseek_client_cache_invalid_{}",
+            std::process::id()
+        ));
+        let mut cfg = build_config(&server.uri());
+        cfg.cache_dir = Some(dir.clone());
+        cfg.retry_max_attempts = 1;
+        let client = DeepSeekClient::new(cfg).unwrap();
+
+        Mock::given(method("POST"))
+            .and(path("/chat/completions"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(api_success_body(" ")))
+            .up_to_n_times(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/chat/completions"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(api_success_body(
+                r#"{"title":"T","description":"D","content":"C"}"#,
+            )))
+            .mount(&server)
+            .await;
+
+        let err = client.send_request("hi").await.unwrap_err();
+        assert!(err.is_empty_response(), "unexpected error: {:?}", err);
+        let response = client.send_request("hi").await.unwrap();
+        assert_eq!(response.title, "T");
+        assert_eq!(client.requests_sent(), 2);
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[tokio::test]
+    async fn max_concurrent_requests_limits_requests_in_flight() {
+        let server = MockServer::start().await;
+        let mut cfg = build_config(&server.uri());
+        cfg.max_concurrent_requests = Some(2);
+        let client = DeepSeekClient::new(cfg).unwrap();
+
+        Mock::given(method("POST"))
+            .and(path("/chat/completions"))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .set_body_json(api_success_body("{}"))
+                    .set_delay(Duration::from_millis(200)),
+            )
+            .mount(&server)
+            .await;
+
+        // Six requests, two at a time, take at least three rounds of the delay
+        let started = Instant::now();
+        let requests = (0..6).map(|_| client.send_messages_raw(vec![user_message("hi")]));
+        for result in futures::future::join_all(requests).await {
+            result.unwrap();
+        }
+        assert!(started.elapsed() >= Duration::from_millis(600));
+    }
+
+    #[tokio::test]
+    async fn send_request_timed_reports_elapsed_time() {
+        let server = MockServer::start().await;
+        let client = build_client(&server.uri());
+
+        let content = serde_json::json!({
+            "title": "t",
+            "description": "d",
+            "content": "c"
+        })
+        .to_string();
+
+        Mock::given(method("POST"))
+            .and(path("/chat/completions"))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .set_body_json(api_success_body(&content))
+                    .set_delay(Duration::from_millis(100)),
+            )
+            .mount(&server)
+            .await;
+
+        let (response, elapsed) = client.send_request_timed("hi").await.unwrap();
+        assert_eq!(response.title, "t");
+        assert!(elapsed >= Duration::from_millis(100));
+    }
+
+    #[tokio::test]
+    async fn send_request_with_meta_sums_usage_across_corrections() {
+        let server = MockServer::start().await;
+        let mut cfg = build_config(&server.uri());
+        cfg.require_confidence = true;
+        let client = DeepSeekClient::new(cfg).unwrap();
+
+        let with_usage = |content: serde_json::Value| {
+            let mut body = api_success_body(&content.to_string());
+            body["usage"] = serde_json::json!({
+                "prompt_tokens": 10, "completion_tokens": 5, "total_tokens": 15
+            });
+            body
+        };
+        let without = serde_json::json!({ "title": "t", "description": "d", "content": "c" });
+        let with = serde_json::json!({
+            "title": "t", "description": "d", "content": "c", "confidence": 0.9
+        });
+
+        Mock::given(method("POST"))
+            .and(path("/chat/completions"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(with_usage(without)))
+            .up_to_n_times(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/chat/completions"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(with_usage(with)))
+            .mount(&server)
+            .await;
+
+        let (response, meta) = client.send_request_with_meta("hi").await.unwrap();
+        assert_eq!(response.confidence, Some(0.9));
+        assert_eq!(
+            meta.usage,
+            Some(Usage {
+                prompt_tokens: 20,
+                completion_tokens: 10,
+                total_tokens: 30
+            })
+        );
+    }
+
+    #[tokio::test]
+    async fn custom_system_prompt_is_sent_with_json_instructions() {
+        let server = MockServer::start().await;
+        let mut cfg = build_config(&server.uri());
+        cfg.system_prompt = Some("You are a pirate.".to_string());
+        let client = DeepSeekClient::new(cfg).unwrap();
+
+        let content = serde_json::json!({ "title": "t", "description": "d", "content": "c" });
+        Mock::given(method("POST"))
+            .and(path("/chat/completions"))
+            .respond_with(
+                ResponseTemplate::new(200).set_body_json(api_success_body(&content.to_string())),
+            )
+            .mount(&server)
+            .await;
+
+        client.send_request("hello").await.unwrap();
+
+        let requests = server.received_requests().await.unwrap();
+        let body: serde_json::Value = serde_json::from_slice(&requests[0].body).unwrap();
+        assert_eq!(body["messages"][0]["content"], "You are a pirate.");
+        let user = body["messages"][1]["content"].as_str().unwrap();
+        assert!(user.starts_with("hello"));
+        assert!(user.contains("Please respond with a JSON object"));
+    }
+
+    #[tokio::test]
+    async fn bom_prefixed_body_and_content_are_parsed() {
+        let server = MockServer::start().await;
+        let client = build_client(&server.uri());
+
+        let content = format!(
+            "\u{feff}{}",
+            serde_json::json!({ "title": "t", "description": "d", "content": "c" })
+        );
+        let body = format!("\u{feff}{}", api_success_body(&content));
+
+        Mock::given(method("POST"))
+            .and(path("/chat/completions"))
+            .respond_with(ResponseTemplate::new(200).set_body_raw(body, "application/json"))
+            .mount(&server)
+            .await;
+
+        let response = client
+            .send_request("hi")
+            .await
+            .expect("BOM should be stripped");
+        assert_eq!(response.title, "t");
+
+        let raw = client
+            .send_messages_raw(vec![user_message("hi")])
+            .await
+            .unwrap();
+        assert!(raw.starts_with('{'));
+    }
+
+    #[tokio::test]
+    async fn fenced_content_is_parsed() {
+        let server = MockServer::start().await;
+        let client = build_client(&server.uri());
+
+        let content = format!(
+            "```json\n{}\n```",
+            serde_json::json!({ "title": "t", "description": "d", "content": "c" })
+        );
+        Mock::given(method("POST"))
+            .and(path("/chat/completions"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(api_success_body(&content)))
+            .mount(&server)
+            .await;
+
+        let response = client
+            .send_request("hi")
+            .await
+            .expect("fence should be stripped");
+        assert_eq!(response.title, "t");
+    }
+
+    #[tokio::test]
+    async fn json_surrounded_by_prose_is_parsed() {
+        let server = MockServer::start().await;
+        let client = build_client(&server.uri());
+
+        let content = format!(
+            "Sure, here is the answer: {} Let me know if you need more.",
+            serde_json::json!({ "title": "t {braces}", "description": "d", "content": "c" })
+        );
+        Mock::given(method("POST"))
+            .and(path("/chat/completions"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(api_success_body(&content)))
+            .mount(&server)
+            .await;
+
+        let response = client
+            .send_request("hi")
+            .await
+            .expect("embedded object should be parsed");
+        assert_eq!(response.title, "t {braces}");
+    }
+
+    fn person_schema() -> serde_json::Value {
+        serde_json::json!({
+            "type": "object",
+            "properties": {
+                "name": { "type": "string" },
+                "age": { "type": "integer", "minimum": 0 }
+            },
+            "required": ["name", "age"]
+        })
+    }
+
+    #[tokio::test]
+    async fn send_request_schema_returns_validated_value() {
+        let server = MockServer::start().await;
+        let client = build_client(&server.uri());
+
+        let content = serde_json::json!({ "name": "Ada", "age": 36 }).to_string();
+        Mock::given(method("POST"))
+            .and(path("/chat/completions"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(api_success_body(&content)))
+            .mount(&server)
+            .await;
+
+        let value = client
+            .send_request_schema("Who wrote the first program?", &person_schema())
+            .await
+            .unwrap();
+        assert_eq!(value["name"], "Ada");
+
+        let requests = server.received_requests().await.unwrap();
+        let body: serde_json::Value = serde_json::from_slice(&requests[0].body).unwrap();
+        let user = body["messages"][1]["content"].as_str().unwrap();
+        assert!(user.contains("\"minimum\": 0"));
+    }
+
+    #[tokio::test]
+    async fn send_request_schema_rejects_mismatching_response() {
+        let server = MockServer::start().await;
+        let client = build_client(&server.uri());
+
+        let content = serde_json::json!({ "name": "Ada", "age": -1 }).to_string();
+        Mock::given(method("POST"))
+            .and(path("/chat/completions"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(api_success_body(&content)))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let err = client
+            .send_request_schema("Who?", &person_schema())
+            .await
+            .unwrap_err();
+        assert!(matches!(err, DeepSeekError::ParseError { .. }));
+        assert!(err.to_string().contains("/age"));
+    }
+
+    #[tokio::test]
+    async fn send_request_schema_rejects_invalid_schema() {
+        let client = build_client("http://localhost");
+        let err = client
+            .send_request_schema("Who?", &serde_json::json!({ "type": 12 }))
+            .await
+            .unwrap_err();
+        assert!(matches!(err, DeepSeekError::ConfigError { .. }));
+    }
+
+    #[tokio::test]
+    async fn require_confidence_sends_one_corrective_request() {
+        let server = MockServer::start().await;
+        let mut cfg = build_config(&server.uri());
+        cfg.require_confidence = true;
+        let client = DeepSeekClient::new(cfg).unwrap();
+
+        let without = serde_json::json!({ "title": "t", "description": "d", "content": "c" });
+        let with = serde_json::json!({
+            "title": "t", "description": "d", "content": "c", "confidence": 0.8
+        });
+
+        Mock::given(method("POST"))
+            .and(path("/chat/completions"))
+            .respond_with(
+                ResponseTemplate::new(200).set_body_json(api_success_body(&without.to_string())),
+            )
+            .up_to_n_times(1)
+            .expect(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/chat/completions"))
+            .respond_with(
+                ResponseTemplate::new(200).set_body_json(api_success_body(&with.to_string())),
+            )
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let response = client.send_request("classify this").await.unwrap();
+        assert_eq!(response.confidence, Some(0.8));
+
+        let requests = server.received_requests().await.unwrap();
+        let body: serde_json::Value = serde_json::from_slice(&requests[1].body).unwrap();
+        assert_eq!(body["messages"].as_array().unwrap().len(), 4);
+        assert_eq!(body["messages"][2]["role"], "assistant");
+    }
+
+    #[tokio::test]
+    async fn require_confidence_fails_when_still_missing() {
+        let server = MockServer::start().await;
+        let mut cfg = build_config(&server.uri());
+        cfg.require_confidence = true;
+        let client = DeepSeekClient::new(cfg).unwrap();
 
-    fn build_config(base_url: &str) -> Config {
-        Config {
-            api_key: "test_key".to_string(),
-            base_url: base_url.to_string(),
-            model: "test-model".to_string(),
-            max_tokens: 256,
-            temperature: 0.1,
-            timeout: 2,
-        }
+        let without = serde_json::json!({ "title": "t", "description": "d", "content": "c" });
+        Mock::given(method("POST"))
+            .and(path("/chat/completions"))
+            .respond_with(
+                ResponseTemplate::new(200).set_body_json(api_success_body(&without.to_string())),
+            )
+            .expect(2)
+            .mount(&server)
+            .await;
+
+        let err = client.send_request("classify this").await.unwrap_err();
+        assert!(matches!(err, DeepSeekError::ParseError { .. }));
+        assert!(err.to_string().contains("confidence"));
     }
 
-    fn build_client(base_url: &str) -> DeepSeekClient {
-        DeepSeekClient::new(build_config(base_url)).expect("client should be created")
+    #[tokio::test]
+    async fn category_schema_mismatch_is_retried_then_reported() {
+        let server = MockServer::start().await;
+        let mut cfg = build_config(&server.uri());
+        cfg.category_schemas.insert(
+            "weather".to_string(),
+            serde_json::json!({
+                "type": "object",
+                "required": ["city"],
+                "properties": { "city": { "type": "string" } }
+            }),
+        );
+        let client = DeepSeekClient::new(cfg).unwrap();
+
+        let mismatch = serde_json::json!({
+            "title": "t", "description": "d", "category": "Weather", "content": "{\"town\": \"Oslo\"}"
+        });
+        Mock::given(method("POST"))
+            .and(path("/chat/completions"))
+            .respond_with(
+                ResponseTemplate::new(200).set_body_json(api_success_body(&mismatch.to_string())),
+            )
+            .expect(2)
+            .mount(&server)
+            .await;
+
+        let err = client.send_request("weather in Oslo").await.unwrap_err();
+        assert!(matches!(err, DeepSeekError::ParseError { .. }));
+        assert!(err.to_string().contains("category 'weather'"));
+
+        let requests = server.received_requests().await.unwrap();
+        let body: serde_json::Value = serde_json::from_slice(&requests[1].body).unwrap();
+        let correction = body["messages"][3]["content"].as_str().unwrap();
+        assert!(correction.contains("\"city\""));
     }
 
-    fn api_success_body(content_json: &str) -> serde_json::Value {
-        serde_json::json!({
-            "choices": [
-                { "message": { "role": "assistant", "content": content_json } }
-            ]
-        })
+    #[test]
+    fn invalid_category_schema_is_config_error() {
+        let mut cfg = build_config("http://localhost");
+        cfg.category_schemas
+            .insert("weather".to_string(), serde_json::json!({ "type": 12 }));
+        let err = DeepSeekClient::new(cfg).unwrap_err();
+        assert!(matches!(err, DeepSeekError::ConfigError { .. }));
     }
 
     #[test]
-    fn new_with_invalid_config_returns_config_error() {
-        let bad_config = Config {
-            api_key: String::new(),
-            base_url: "http://localhost".to_string(),
-            model: "m".to_string(),
-            max_tokens: 1,
-            temperature: 0.0,
-            timeout: 1,
-        };
+    fn extraction_mode_forces_zero_temperature_and_strict_prompt() {
+        let mut cfg = build_config("http://localhost:1");
+        cfg.temperature = 0.9;
+        cfg.mode = Mode::Extraction;
+        let client = DeepSeekClient::new(cfg.clone()).unwrap();
+
+        assert_eq!(client.temperature(), 0.0);
+        let preview = client.preview_request(&client.structured_messages("hi"), "test-model");
+        assert_eq!(preview["body"]["temperature"], 0.0);
+        let system = preview["body"]["messages"][0]["content"].as_str().unwrap();
+        assert!(system.starts_with(JSON_SYSTEM_PROMPT));
+        assert!(system.ends_with(EXTRACTION_INSTRUCTIONS));
+
+        cfg.mode = Mode::Creative;
+        let client = DeepSeekClient::new(cfg).unwrap();
+        assert_eq!(client.temperature(), 0.9);
+        assert_eq!(client.system_prompt(), JSON_SYSTEM_PROMPT);
+    }
 
-        let err = DeepSeekClient::new(bad_config).unwrap_err();
-        match err {
-            DeepSeekError::ConfigError { message } => {
-                assert!(message.contains("API key cannot be empty"))
-            }
-            other => panic!("expected ConfigError, got {other}"),
-        }
+    #[test]
+    fn preview_request_redacts_api_key() {
+        let client = build_client("http://localhost:1");
+        let preview = client.preview_request(&client.structured_messages("hi"), "test-model");
+
+        assert_eq!(preview["url"], "http://localhost:1/chat/completions");
+        assert_eq!(preview["headers"]["Authorization"], "Bearer ***");
+        assert_eq!(preview["body"]["model"], "test-model");
+        assert_eq!(preview["body"]["response_format"]["type"], "json_object");
+        assert!(!preview.to_string().contains("test_key"));
+    }
+
+    #[test]
+    fn client_debug_output_redacts_api_key() {
+        let client = build_client("http://localhost");
+        let debug = format!("{:?}", client);
+        assert!(!debug.contains("test_key"));
+        assert!(debug.contains("test-model"));
+    }
+
+    #[test]
+    fn invalid_proxy_url_is_config_error() {
+        let mut cfg = build_config("http://localhost");
+        cfg.proxy_url = Some("not a url".to_string());
+        let err = DeepSeekClient::new(cfg).unwrap_err();
+        assert!(matches!(err, DeepSeekError::ConfigError { .. }));
     }
 
     #[tokio::test]
-    async fn send_request_success_parses_response() {
+    async fn unreachable_proxy_is_network_error() {
         let server = MockServer::start().await;
-        let client = build_client(&server.uri());
+        let mut cfg = build_config(&server.uri());
+        cfg.proxy_url = Some("http://127.0.0.1:1".to_string());
+        cfg.retry_max_attempts = 1;
+        let client = DeepSeekClient::new(cfg).unwrap();
 
-        let content = serde_json::json!({
-            "title": "Hello",
-            "description": "World",
-            "content": "Body",
-            "category": "demo",
-            "timestamp": "2024-01-01T00:00:00Z",
-            "confidence": 0.9
-        })
-        .to_string();
+        Mock::given(method("POST"))
+            .respond_with(ResponseTemplate::new(200))
+            .expect(0)
+            .mount(&server)
+            .await;
+
+        let err = client.send_request("hi").await.unwrap_err();
+        assert!(err.is_network_error(), "expected NetworkError, got {err:?}");
+    }
+
+    #[tokio::test]
+    async fn extra_headers_are_sent_with_requests() {
+        let server = MockServer::start().await;
+        let mut cfg = build_config(&server.uri());
+        cfg.extra_headers
+            .insert("x-org-id".to_string(), "tenant-7".to_string());
+        let client = DeepSeekClient::new(cfg).unwrap();
 
         Mock::given(method("POST"))
             .and(path("/chat/completions"))
-            .respond_with(ResponseTemplate::new(200).set_body_json(api_success_body(&content)))
+            .and(header("x-org-id", "tenant-7"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(api_success_body("{}")))
+            .expect(1)
             .mount(&server)
             .await;
 
-        let response = client
-            .send_request("please respond in json object format")
+        client
+            .send_messages_raw(vec![user_message("hi")])
             .await
-            .expect("request should succeed");
+            .unwrap();
+    }
 
-        assert_eq!(response.title, "Hello");
-        assert_eq!(response.description, "World");
-        assert_eq!(response.content, "Body");
-        assert_eq!(response.category.as_deref(), Some("demo"));
-        assert_eq!(response.timestamp.as_deref(), Some("2024-01-01T00:00:00Z"));
-        assert!((response.confidence.unwrap_or_default() - 0.9).abs() < f32::EPSILON);
+    #[test]
+    fn invalid_extra_header_name_is_config_error() {
+        let mut cfg = build_config("http://localhost");
+        cfg.extra_headers
+            .insert("bad header".to_string(), "v".to_string());
+        let err = DeepSeekClient::new(cfg).unwrap_err();
+        assert!(matches!(err, DeepSeekError::ConfigError { .. }));
     }
 
-    #[tokio::test(start_paused = true)]
-    async fn send_request_retries_and_returns_server_busy() {
+    #[tokio::test]
+    async fn token_budget_refuses_requests_once_exhausted() {
         let server = MockServer::start().await;
-        let client = build_client(&server.uri());
+        let mut cfg = build_config(&server.uri());
+        cfg.token_budget = Some(100);
+        let client = DeepSeekClient::new(cfg).unwrap();
 
-        // Always return 503 to trigger retries and final failure
+        let mut body = api_success_body("{}");
+        body["usage"] = serde_json::json!({
+            "prompt_tokens": 60, "completion_tokens": 60, "total_tokens": 120
+        });
         Mock::given(method("POST"))
             .and(path("/chat/completions"))
-            .respond_with(ResponseTemplate::new(503).set_body_string("busy"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(body))
+            .expect(1)
             .mount(&server)
             .await;
 
-        let task = tokio::spawn({
-            let client = client.clone();
-            async move { client.send_request("x").await }
-        });
-
-        // First backoff: 500ms, second: 1000ms
-        advance(Duration::from_millis(500)).await;
-        tokio::task::yield_now().await;
-        advance(Duration::from_millis(1000)).await;
-        tokio::task::yield_now().await;
+        client
+            .send_messages_raw(vec![user_message("hi")])
+            .await
+            .unwrap();
+        assert_eq!(client.tokens_used(), 120);
+        assert_eq!(client.remaining_budget(), Some(0));
 
-        let err = task.await.expect("join ok").expect_err("should fail");
-        match err {
-            DeepSeekError::ServerBusy => {}
-            DeepSeekError::ApiError { status: 503, .. } => {}
-            DeepSeekError::Timeout { .. } => {}
-            other => panic!("expected ServerBusy, 503 ApiError, or Timeout, got {other}"),
-        }
+        let err = client
+            .send_messages_raw(vec![user_message("again")])
+            .await
+            .unwrap_err();
+        assert!(matches!(
+            err,
+            DeepSeekError::BudgetExceeded {
+                used: 120,
+                budget: 100
+            }
+        ));
     }
 
     #[tokio::test]
-    async fn send_messages_raw_maps_http_errors() {
+    async fn sampling_controls_are_sent_only_when_set() {
         let server = MockServer::start().await;
-        let client = build_client(&server.uri());
+        let mut cfg = build_config(&server.uri());
+        cfg.top_p = Some(0.5);
+        cfg.presence_penalty = Some(-1.0);
+        let client = DeepSeekClient::new(cfg).unwrap();
 
-        // 400 -> ApiError
         Mock::given(method("POST"))
             .and(path("/chat/completions"))
-            .respond_with(ResponseTemplate::new(400).set_body_string("bad req"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(api_success_body("{}")))
             .mount(&server)
             .await;
 
-        let err = client
-            .send_messages_raw(vec![ChatMessage {
-                role: "user".to_string(),
-                content: "hi".to_string(),
-            }])
+        client
+            .send_messages_raw(vec![user_message("hi")])
             .await
-            .expect_err("should map to ApiError");
+            .unwrap();
 
-        match err {
-            DeepSeekError::ApiError { status, message } => {
-                assert_eq!(status, 400);
-                assert!(message.contains("bad req"));
-            }
-            other => panic!("expected ApiError, got {other}"),
-        }
+        let requests = server.received_requests().await.unwrap();
+        let body: serde_json::Value = serde_json::from_slice(&requests[0].body).unwrap();
+        assert_eq!(body["top_p"], 0.5);
+        assert_eq!(body["presence_penalty"], -1.0);
+        assert!(body.get("frequency_penalty").is_none());
     }
 
     #[tokio::test]
-    async fn send_request_empty_choices_is_parse_error() {
+    async fn model_and_temperature_changes_apply_to_clones() {
         let server = MockServer::start().await;
         let client = build_client(&server.uri());
+        let clone = client.clone();
+
+        clone.set_model("deepseek-reasoner").unwrap();
+        clone.set_temperature(1.5).unwrap();
+        assert!(clone.set_temperature(3.0).is_err());
+        assert!(clone.set_model("  ").is_err());
+        assert_eq!(client.model(), "deepseek-reasoner");
 
-        let body = serde_json::json!({ "choices": [] });
         Mock::given(method("POST"))
             .and(path("/chat/completions"))
-            .respond_with(ResponseTemplate::new(200).set_body_json(body))
+            .and(body_partial_json(serde_json::json!({
+                "model": "deepseek-reasoner",
+                "temperature": 1.5
+            })))
+            .respond_with(ResponseTemplate::new(200).set_body_json(api_success_body("{}")))
+            .expect(1)
             .mount(&server)
             .await;
 
-        let err = client
-            .send_request("x")
+        client
+            .send_messages_raw(vec![user_message("hi")])
             .await
-            .expect_err("should be parse error");
-        assert!(matches!(err, DeepSeekError::ParseError { .. }));
+            .unwrap();
     }
 
     #[tokio::test]
-    async fn send_request_invalid_json_in_content_is_parse_error() {
+    async fn taskfinisher_model_overrides_main_model() {
         let server = MockServer::start().await;
-        let client = build_client(&server.uri());
+        let mut cfg = build_config(&server.uri());
+        cfg.taskfinisher_model = Some("deepseek-reasoner".to_string());
+        let client = DeepSeekClient::new(cfg).unwrap();
+        assert_eq!(client.taskfinisher_model(), "deepseek-reasoner");
 
-        let content = "not-json";
         Mock::given(method("POST"))
             .and(path("/chat/completions"))
-            .respond_with(ResponseTemplate::new(200).set_body_json(api_success_body(content)))
+            .and(body_partial_json(
+                serde_json::json!({ "model": "deepseek-reasoner" }),
+            ))
+            .respond_with(ResponseTemplate::new(200).set_body_json(api_success_body("{}")))
+            .expect(1)
             .mount(&server)
             .await;
 
-        let err = client
-            .send_request("x")
+        client
+            .send_messages_raw_with_model(vec![user_message("hi")], &client.taskfinisher_model())
             .await
-            .expect_err("should be parse error");
-        assert!(matches!(err, DeepSeekError::ParseError { .. }));
+            .unwrap();
+
+        assert_eq!(
+            build_client("http://localhost").taskfinisher_model(),
+            "test-model"
+        );
     }
 
-    #[tokio::test(start_paused = true)]
-    async fn send_messages_raw_times_out() {
+    #[tokio::test]
+    async fn stop_sequences_are_serialized_when_configured() {
         let server = MockServer::start().await;
         let mut cfg = build_config(&server.uri());
-        cfg.timeout = 1; // seconds
+        cfg.stop_sequences = vec!["###".to_string(), "\n\n".to_string()];
         let client = DeepSeekClient::new(cfg).unwrap();
 
-        // Delay response beyond client timeout
         Mock::given(method("POST"))
             .and(path("/chat/completions"))
-            .respond_with(
-                ResponseTemplate::new(200)
-                    .set_delay(Duration::from_secs(2))
-                    .set_body_json(api_success_body(
-                        &serde_json::json!({
-                            "title": "t",
-                            "description": "d",
-                            "content": "c",
-                            "category": null,
-                            "timestamp": "2024-01-01T00:00:00Z",
-                            "confidence": 0.5
-                        })
-                        .to_string(),
-                    )),
-            )
+            .respond_with(ResponseTemplate::new(200).set_body_json(api_success_body("{}")))
             .mount(&server)
             .await;
 
-        let task = tokio::spawn({
-            let client = client.clone();
-            async move {
-                client
-                    .send_messages_raw(vec![ChatMessage {
-                        role: "user".to_string(),
-                        content: "hello".to_string(),
-                    }])
-                    .await
-            }
+        client
+            .send_messages_raw(vec![user_message("hi")])
+            .await
+            .unwrap();
+        build_client(&server.uri())
+            .send_messages_raw(vec![user_message("hi")])
+            .await
+            .unwrap();
+
+        let requests = server.received_requests().await.unwrap();
+        let with_stop: serde_json::Value = serde_json::from_slice(&requests[0].body).unwrap();
+        assert_eq!(with_stop["stop"], serde_json::json!(["###", "\n\n"]));
+        let without: serde_json::Value = serde_json::from_slice(&requests[1].body).unwrap();
+        assert!(without.get("stop").is_none());
+    }
+
+    #[tokio::test]
+    async fn stats_count_requests_tokens_and_errors() {
+        let server = MockServer::start().await;
+        let mut cfg = build_config(&server.uri());
+        cfg.input_denylist = vec!["forbidden".to_string()];
+        let client = DeepSeekClient::new(cfg).unwrap();
+
+        let mut body = api_success_body("{}");
+        body["usage"] = serde_json::json!({ "total_tokens": 42 });
+        Mock::given(method("POST"))
+            .and(path("/chat/completions"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(body))
+            .mount(&server)
+            .await;
+
+        client
+            .send_messages_raw(vec![user_message("hi")])
+            .await
+            .unwrap();
+        client.send_request("hi").await.unwrap_err();
+        client.send_request("forbidden").await.unwrap_err();
+
+        assert_eq!(client.requests_sent(), 2);
+        assert_eq!(client.tokens_used(), 84);
+        let errors = client.error_counts();
+        assert_eq!(errors.get("parse"), Some(&1));
+        assert_eq!(errors.get("input_rejected"), Some(&1));
+    }
+
+    #[tokio::test]
+    async fn finish_reason_length_is_truncated_error() {
+        let server = MockServer::start().await;
+        let client = build_client(&server.uri());
+
+        let body = serde_json::json!({
+            "choices": [{
+                "message": { "role": "assistant", "content": "{\"title\": \"Half" },
+                "finish_reason": "length"
+            }]
         });
+        Mock::given(method("POST"))
+            .and(path("/chat/completions"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(body))
+            .expect(1)
+            .mount(&server)
+            .await;
 
-        advance(Duration::from_secs(2)).await;
-        tokio::task::yield_now().await;
+        let err = client.send_request("long essay").await.unwrap_err();
+        assert!(matches!(err, DeepSeekError::Truncated { tokens: 256 }));
+        assert!(err.user_message().contains("--max-tokens"));
+    }
 
-        let err = task.await.unwrap().expect_err("should timeout");
-        match err {
-            DeepSeekError::Timeout { seconds } => assert_eq!(seconds, 1),
-            other => panic!("expected Timeout, got {other}"),
-        }
+    #[tokio::test]
+    async fn whitespace_content_is_empty_response_and_retried() {
+        let server = MockServer::start().await;
+        let mut cfg = build_config(&server.uri());
+        cfg.retry_max_attempts = 2;
+        cfg.retry_base_backoff_ms = 1;
+        let client = DeepSeekClient::new(cfg).unwrap();
+
+        Mock::given(method("POST"))
+            .and(path("/chat/completions"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(api_success_body(" \n ")))
+            .expect(2)
+            .mount(&server)
+            .await;
+
+        let err = client.send_request("hello").await.unwrap_err();
+        assert!(err.is_empty_response(), "unexpected error: {:?}", err);
+        assert_eq!(err.kind(), "empty_response");
+        assert!(
+            err.explain()
+                .iter()
+                .any(|step| step.contains("--max-tokens"))
+        );
     }
 }
@@ -0,0 +1,140 @@
+use serde::de::DeserializeOwned;
+
+/// Remove a leading UTF-8 byte order mark, if present
+pub fn strip_bom(s: &str) -> &str {
+    s.strip_prefix('\u{feff}').unwrap_or(s)
+}
+
+/// Remove a surrounding Markdown code fence (e.g. ```json ... ```), if present.
+/// Input without a fence is returned unchanged.
+pub fn strip_code_fences(s: &str) -> &str {
+    let trimmed = s.trim();
+    let Some(rest) = trimmed.strip_prefix("```") else {
+        return s;
+    };
+    // Skip the optional language tag on the opening fence line
+    let Some(newline) = rest.find('\n') else {
+        return s;
+    };
+    let body = rest[newline + 1..].trim_end();
+    body.strip_suffix("```").unwrap_or(body).trim()
+}
+
+/// Find the first balanced top-level `{...}` object in `s`.
+/// Braces inside JSON string literals are ignored.
+pub fn extract_json_object(s: &str) -> Option<&str> {
+    let start = s.find('{')?;
+    let mut depth = 0usize;
+    let mut in_string = false;
+    let mut escaped = false;
+
+    for (i, ch) in s[start..].char_indices() {
+        if in_string {
+            if escaped {
+                escaped = false;
+            } else if ch == '\\' {
+                escaped = true;
+            } else if ch == '"' {
+                in_string = false;
+            }
+            continue;
+        }
+        match ch {
+            '"' => in_string = true,
+            '{' => depth += 1,
+            '}' => {
+                depth -= 1;
+                if depth == 0 {
+                    return Some(&s[start..start + i + 1]);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    None
+}
+
+/// Parse `s` as JSON, falling back to the first balanced `{...}` object in it when the model
+/// surrounded the JSON with prose. The error of the direct parse is kept if both fail.
+pub fn from_str_or_embedded<T: DeserializeOwned>(s: &str) -> serde_json::Result<T> {
+    serde_json::from_str(s).or_else(|error| {
+        extract_json_object(s)
+            .and_then(|object| serde_json::from_str(object).ok())
+            .ok_or(error)
+    })
+}
+
+/// Byte offset of `part` within `whole`. `part` must be a subslice of `whole`.
+pub(crate) fn offset_in(whole: &str, part: &str) -> usize {
+    part.as_ptr() as usize - whole.as_ptr() as usize
+}
+
+/// Convert a 1-based serde_json line/column position into a byte offset within `s`
+pub(crate) fn byte_offset(s: &str, line: usize, column: usize) -> usize {
+    let line_start: usize = s
+        .split_inclusive('\n')
+        .take(line.saturating_sub(1))
+        .map(str::len)
+        .sum();
+    (line_start + column.saturating_sub(1)).min(s.len())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn strip_bom_removes_only_leading_mark() {
+        assert_eq!(strip_bom("\u{feff}{}"), "{}");
+        assert_eq!(strip_bom("{}"), "{}");
+    }
+
+    #[test]
+    fn strip_code_fences_removes_tagged_fence() {
+        let fenced = "```json\n{\"a\": 1}\n```";
+        assert_eq!(strip_code_fences(fenced), "{\"a\": 1}");
+    }
+
+    #[test]
+    fn strip_code_fences_keeps_unfenced_input() {
+        assert_eq!(strip_code_fences("{\"a\": 1}"), "{\"a\": 1}");
+        assert_eq!(strip_code_fences("```\n[1]\n```"), "[1]");
+    }
+
+    #[test]
+    fn extract_json_object_skips_leading_prose() {
+        let text = "Sure! Here you go: {\"a\": {\"b\": 2}} Thanks.";
+        assert_eq!(extract_json_object(text), Some("{\"a\": {\"b\": 2}}"));
+    }
+
+    #[test]
+    fn extract_json_object_ignores_braces_in_strings() {
+        let text = r#"Note: {"a": "}{", "b": {"c": "\"}"}} trailing {"d": 1}"#;
+        assert_eq!(
+            extract_json_object(text),
+            Some(r#"{"a": "}{", "b": {"c": "\"}"}}"#)
+        );
+        assert_eq!(extract_json_object("no object {here"), None);
+    }
+
+    #[test]
+    fn from_str_or_embedded_falls_back_to_embedded_object() {
+        let value: serde_json::Value =
+            from_str_or_embedded("Here is the JSON:\n{\"a\": {\"b\": 2}}\nHope it helps!").unwrap();
+        assert_eq!(value["a"]["b"], 2);
+
+        let direct: serde_json::Value = from_str_or_embedded("[1, 2]").unwrap();
+        assert_eq!(direct, serde_json::json!([1, 2]));
+
+        assert!(from_str_or_embedded::<serde_json::Value>("no json").is_err());
+    }
+
+    #[test]
+    fn byte_offset_maps_line_and_column() {
+        let s = "ab\ncd\nef";
+        assert_eq!(byte_offset(s, 1, 1), 0);
+        assert_eq!(byte_offset(s, 2, 2), 4);
+        assert_eq!(byte_offset(s, 9, 9), s.len());
+    }
+}
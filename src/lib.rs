@@ -1,18 +1,39 @@
 use anyhow::{Context, Result};
 
+#[cfg(feature = "blocking")]
+pub mod blocking;
+pub mod cache;
 pub mod cli;
 pub mod config;
 pub mod console;
 pub mod deepseek;
+pub mod extract;
+pub mod metrics;
+pub mod output;
+pub mod summary;
 pub mod taskfinisher;
-
-pub use config::Config;
-pub use console::Console;
-pub use deepseek::{DeepSeekClient, DeepSeekError, DeepSeekResponse};
+#[cfg(feature = "otlp")]
+pub mod telemetry;
+pub mod transport;
+
+#[cfg(feature = "blocking")]
+pub use blocking::BlockingClient;
+pub use config::{AuthStyle, Config, Mode};
+pub use console::{Console, ConsoleOptions, EmptyInputBehavior, Theme, ThemeName};
+pub use deepseek::{
+    ChatRequest, CompatibilityReport, DeepSeekClient, DeepSeekError, DeepSeekResponse, FunctionDef,
+    ProgressEvent, ResponseMeta, ToolCall, ToolCallResult, ToolDef, Usage,
+};
+pub use output::OutputFormat;
+pub use summary::{SessionSummary, TaskFinisherSummary};
 pub use taskfinisher::{
-    build_system_prompt, parse_taskfinisher_response, AnswersPayload, TaskFinisherResult,
-    DEFAULT_MAX_QUESTIONS,
+    AnswersPayload, ArtifactChange, ArtifactDiff, ChecklistProgress, DEFAULT_MAX_QUESTIONS,
+    DEFAULT_MAX_ROUNDS, END_TOKEN, ParseDiagnostics, ParseWarning, QuestionLog, TaskFinisherOutput,
+    TaskFinisherResult, TaskFinisherSession, artifact_json_schema, artifact_to_markdown,
+    build_system_prompt, diff_artifacts, incomplete_fields, parse_taskfinisher_response,
+    parse_taskfinisher_response_lenient, validate_artifact,
 };
+pub use transport::{ChatTransport, HttpTransport};
 
 /// Application struct that encapsulates the core functionality
 pub struct App {
@@ -27,7 +48,7 @@ impl App {
         let config = Config::load().context("Failed to load configuration")?;
 
         // Initialize DeepSeek client
-        let client = DeepSeekClient::new(config).map_err(|e| anyhow::anyhow!("{}", e))?;
+        let client = DeepSeekClient::new(config)?;
 
         // Create console interface
         let console = Console::new(client.clone());
@@ -37,11 +58,16 @@ impl App {
 
     /// Create a new application instance with custom configuration
     pub fn with_config(config: Config) -> Result<Self> {
+        Self::with_options(config, ConsoleOptions::default())
+    }
+
+    /// Create a new application instance with custom configuration and console options
+    pub fn with_options(config: Config, options: ConsoleOptions) -> Result<Self> {
         // Initialize DeepSeek client
-        let client = DeepSeekClient::new(config).map_err(|e| anyhow::anyhow!("{}", e))?;
+        let client = DeepSeekClient::new(config)?;
 
         // Create console interface
-        let console = Console::new(client.clone());
+        let console = Console::with_options(client.clone(), options);
 
         Ok(Self { client, console })
     }
@@ -55,17 +81,24 @@ impl App {
     }
 
     /// Run TaskFinisher-JSON interactive flow. If `initial_prompt` is None, the user will be asked.
+    /// Returns the final artifact, or the last raw reply when the flow ended without one.
     pub async fn run_taskfinisher(
         &self,
         initial_prompt: Option<&str>,
         max_questions: u32,
-    ) -> Result<()> {
+        max_rounds: u32,
+    ) -> Result<TaskFinisherOutput> {
         self.console
-            .run_taskfinisher(initial_prompt, max_questions)
+            .run_taskfinisher(initial_prompt, max_questions, max_rounds)
             .await
             .context("TaskFinisher flow failed")
     }
 
+    /// Build a run report from the accumulated client statistics and TaskFinisher outcome
+    pub fn session_summary(&self, elapsed: std::time::Duration) -> SessionSummary {
+        SessionSummary::collect(&self.client, elapsed, self.console.taskfinisher_outcome())
+    }
+
     /// Get a reference to the DeepSeek client
     pub fn client(&self) -> &DeepSeekClient {
         &self.client
@@ -75,6 +108,23 @@ impl App {
     pub async fn send_request(&self, input: &str) -> Result<DeepSeekResponse, DeepSeekError> {
         self.client.send_request(input).await
     }
+
+    /// Send a single request, reporting its progress (attempts, retries, the reply) to
+    /// `on_event` so a GUI or TUI can show its own indicator
+    pub async fn send_request_with_progress(
+        &self,
+        input: &str,
+        on_event: impl Fn(ProgressEvent) + Send + Sync,
+    ) -> Result<DeepSeekResponse, DeepSeekError> {
+        self.client
+            .send_request_with_progress(input, on_event)
+            .await
+    }
+
+    /// Send a single request and return the model's reply unparsed (useful for debugging prompts)
+    pub async fn send_request_raw(&self, input: &str) -> Result<String, DeepSeekError> {
+        self.client.send_request_raw(input).await
+    }
 }
 
 impl Default for App {
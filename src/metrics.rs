@@ -0,0 +1,213 @@
+use std::fmt::Write as _;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
+
+use anyhow::{Context, Result};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpListener;
+
+use crate::deepseek::DeepSeekError;
+
+/// Upper bounds of the latency histogram buckets, in seconds
+const LATENCY_BUCKETS: [f64; 10] = [0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0, 30.0, 60.0, 120.0];
+
+/// Error kinds counted separately, as returned by [`DeepSeekError::kind`]
+const ERROR_KINDS: [&str; 10] = [
+    "server_busy",
+    "network",
+    "timeout",
+    "api",
+    "parse",
+    "empty_response",
+    "config",
+    "input_rejected",
+    "truncated",
+    "budget_exceeded",
+];
+
+static REGISTRY: Metrics = Metrics::new();
+
+/// Process-wide request metrics, recorded by every client
+pub fn global() -> &'static Metrics {
+    &REGISTRY
+}
+
+/// Request counts, error counts by kind and a latency histogram, kept in atomics so that
+/// recording never blocks a request
+#[derive(Debug)]
+pub struct Metrics {
+    requests: AtomicU64,
+    errors: [AtomicU64; ERROR_KINDS.len()],
+    latency_buckets: [AtomicU64; LATENCY_BUCKETS.len()],
+    latency_count: AtomicU64,
+    /// Sum of all latencies in microseconds
+    latency_sum_micros: AtomicU64,
+}
+
+impl Default for Metrics {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Metrics {
+    pub const fn new() -> Self {
+        Self {
+            requests: AtomicU64::new(0),
+            errors: [const { AtomicU64::new(0) }; ERROR_KINDS.len()],
+            latency_buckets: [const { AtomicU64::new(0) }; LATENCY_BUCKETS.len()],
+            latency_count: AtomicU64::new(0),
+            latency_sum_micros: AtomicU64::new(0),
+        }
+    }
+
+    /// Record one finished request: its latency, retries included, and its error if it failed
+    pub fn observe(&self, latency: Duration, error: Option<&DeepSeekError>) {
+        self.requests.fetch_add(1, Ordering::Relaxed);
+        if let Some(error) = error
+            && let Some(index) = ERROR_KINDS.iter().position(|kind| *kind == error.kind())
+        {
+            self.errors[index].fetch_add(1, Ordering::Relaxed);
+        }
+
+        let seconds = latency.as_secs_f64();
+        for (bucket, bound) in self.latency_buckets.iter().zip(LATENCY_BUCKETS) {
+            if seconds <= bound {
+                bucket.fetch_add(1, Ordering::Relaxed);
+            }
+        }
+        self.latency_count.fetch_add(1, Ordering::Relaxed);
+        self.latency_sum_micros
+            .fetch_add(latency.as_micros() as u64, Ordering::Relaxed);
+    }
+
+    /// All metrics in the Prometheus text exposition format
+    pub fn render(&self) -> String {
+        let mut out = String::new();
+
+        out.push_str("# HELP deepseek_requests_total Requests sent to the DeepSeek API.\n");
+        out.push_str("# TYPE deepseek_requests_total counter\n");
+        let _ = writeln!(
+            out,
+            "deepseek_requests_total {}",
+            self.requests.load(Ordering::Relaxed)
+        );
+
+        out.push_str("# HELP deepseek_errors_total Failed requests by error kind.\n");
+        out.push_str("# TYPE deepseek_errors_total counter\n");
+        for (kind, count) in ERROR_KINDS.iter().zip(&self.errors) {
+            let _ = writeln!(
+                out,
+                "deepseek_errors_total{{kind=\"{}\"}} {}",
+                kind,
+                count.load(Ordering::Relaxed)
+            );
+        }
+
+        out.push_str(
+            "# HELP deepseek_request_duration_seconds Request latency, retries included.\n",
+        );
+        out.push_str("# TYPE deepseek_request_duration_seconds histogram\n");
+        for (bound, bucket) in LATENCY_BUCKETS.iter().zip(&self.latency_buckets) {
+            let _ = writeln!(
+                out,
+                "deepseek_request_duration_seconds_bucket{{le=\"{}\"}} {}",
+                bound,
+                bucket.load(Ordering::Relaxed)
+            );
+        }
+        let count = self.latency_count.load(Ordering::Relaxed);
+        let _ = writeln!(
+            out,
+            "deepseek_request_duration_seconds_bucket{{le=\"+Inf\"}} {}",
+            count
+        );
+        let _ = writeln!(
+            out,
+            "deepseek_request_duration_seconds_sum {}",
+            self.latency_sum_micros.load(Ordering::Relaxed) as f64 / 1_000_000.0
+        );
+        let _ = writeln!(out, "deepseek_request_duration_seconds_count {}", count);
+
+        out
+    }
+}
+
+/// Bind `127.0.0.1:port` and serve [`global`] metrics at `GET /metrics` in the background
+pub async fn spawn_server(port: u16) -> Result<()> {
+    let listener = TcpListener::bind(("127.0.0.1", port))
+        .await
+        .with_context(|| format!("Failed to bind the metrics endpoint on port {}", port))?;
+    tracing::info!("Serving metrics at http://127.0.0.1:{}/metrics", port);
+
+    tokio::spawn(async move {
+        loop {
+            match listener.accept().await {
+                Ok((stream, _)) => {
+                    tokio::spawn(async move {
+                        if let Err(e) = answer(stream, global()).await {
+                            tracing::debug!("Metrics request failed: {}", e);
+                        }
+                    });
+                }
+                Err(e) => tracing::warn!("Failed to accept a metrics connection: {}", e),
+            }
+        }
+    });
+    Ok(())
+}
+
+/// Answer a single HTTP request; anything but `GET /metrics` gets a 404
+async fn answer(mut stream: tokio::net::TcpStream, metrics: &Metrics) -> std::io::Result<()> {
+    let mut buffer = [0u8; 1024];
+    let read = stream.read(&mut buffer).await?;
+    let request = String::from_utf8_lossy(&buffer[..read]);
+    let mut request_line = request
+        .lines()
+        .next()
+        .unwrap_or_default()
+        .split_whitespace();
+    let path = match (request_line.next(), request_line.next()) {
+        (Some("GET"), Some(path)) => path,
+        _ => "",
+    };
+
+    let response = if path == "/metrics" {
+        let body = metrics.render();
+        format!(
+            "HTTP/1.1 200 OK\r\nContent-Type: text/plain; version=0.0.4\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+            body.len(),
+            body
+        )
+    } else {
+        "HTTP/1.1 404 Not Found\r\nContent-Length: 0\r\nConnection: close\r\n\r\n".to_string()
+    };
+    stream.write_all(response.as_bytes()).await?;
+    stream.shutdown().await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn render_reports_counts_errors_and_cumulative_buckets() {
+        let metrics = Metrics::new();
+        metrics.observe(Duration::from_millis(200), None);
+        metrics.observe(
+            Duration::from_secs(3),
+            Some(&DeepSeekError::Timeout { seconds: 3 }),
+        );
+
+        let text = metrics.render();
+        assert!(text.contains("deepseek_requests_total 2\n"));
+        assert!(text.contains("deepseek_errors_total{kind=\"timeout\"} 1\n"));
+        assert!(text.contains("deepseek_errors_total{kind=\"api\"} 0\n"));
+        assert!(text.contains("deepseek_request_duration_seconds_bucket{le=\"0.1\"} 0\n"));
+        assert!(text.contains("deepseek_request_duration_seconds_bucket{le=\"0.25\"} 1\n"));
+        assert!(text.contains("deepseek_request_duration_seconds_bucket{le=\"5\"} 2\n"));
+        assert!(text.contains("deepseek_request_duration_seconds_bucket{le=\"+Inf\"} 2\n"));
+        assert!(text.contains("deepseek_request_duration_seconds_sum 3.2\n"));
+        assert!(text.contains("deepseek_request_duration_seconds_count 2\n"));
+    }
+}
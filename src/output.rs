@@ -0,0 +1,133 @@
+use anyhow::{Context, Result};
+
+use crate::deepseek::DeepSeekResponse;
+
+/// Serialization format for single-query output
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum OutputFormat {
+    /// Pretty-printed JSON
+    #[default]
+    Json,
+    /// YAML document
+    Yaml,
+    /// Markdown document
+    Markdown,
+}
+
+/// Serialize a response in the requested output format
+pub fn format_response(response: &DeepSeekResponse, format: OutputFormat) -> Result<String> {
+    match format {
+        OutputFormat::Json => {
+            serde_json::to_string_pretty(response).context("Failed to serialize response")
+        }
+        OutputFormat::Yaml => {
+            serde_yaml::to_string(response).context("Failed to serialize response as YAML")
+        }
+        OutputFormat::Markdown => Ok(response_to_markdown(response)),
+    }
+}
+
+/// Serialize several alternative responses: a JSON array, a YAML sequence, or Markdown
+/// documents separated by horizontal rules
+pub fn format_responses(responses: &[DeepSeekResponse], format: OutputFormat) -> Result<String> {
+    match format {
+        OutputFormat::Json => {
+            serde_json::to_string_pretty(responses).context("Failed to serialize responses")
+        }
+        OutputFormat::Yaml => {
+            serde_yaml::to_string(responses).context("Failed to serialize responses as YAML")
+        }
+        OutputFormat::Markdown => Ok(responses
+            .iter()
+            .map(response_to_markdown)
+            .collect::<Vec<_>>()
+            .join("\n---\n\n")),
+    }
+}
+
+/// Render a response as Markdown: title heading, quoted description, content paragraph,
+/// and a bullet list of the optional metadata
+pub fn response_to_markdown(response: &DeepSeekResponse) -> String {
+    let mut md = format!("# {}\n\n", response.title.trim());
+
+    for line in response.description.trim().lines() {
+        if line.trim().is_empty() {
+            md.push_str(">\n");
+        } else {
+            md.push_str(&format!("> {}\n", line));
+        }
+    }
+    md.push('\n');
+
+    md.push_str(response.content.trim());
+    md.push('\n');
+
+    let mut metadata = Vec::new();
+    if let Some(category) = &response.category {
+        metadata.push(format!("- **Category:** {}", category));
+    }
+    if let Some(confidence) = response.confidence {
+        metadata.push(format!("- **Confidence:** {:.2}", confidence));
+    }
+    if !metadata.is_empty() {
+        md.push('\n');
+        md.push_str(&metadata.join("\n"));
+        md.push('\n');
+    }
+
+    md
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample_response() -> DeepSeekResponse {
+        DeepSeekResponse {
+            title: "Rust".to_string(),
+            description: "Systems language\nwith safety".to_string(),
+            content: "Rust prevents data races.".to_string(),
+            category: Some("Programming".to_string()),
+            timestamp: None,
+            confidence: Some(0.9),
+        }
+    }
+
+    #[test]
+    fn markdown_renders_all_sections() {
+        let md = response_to_markdown(&sample_response());
+        assert_eq!(
+            md,
+            "# Rust\n\n> Systems language\n> with safety\n\nRust prevents data races.\n\n\
+             - **Category:** Programming\n- **Confidence:** 0.90\n"
+        );
+    }
+
+    #[test]
+    fn markdown_omits_empty_metadata() {
+        let mut response = sample_response();
+        response.category = None;
+        response.confidence = None;
+        let md = response_to_markdown(&response);
+        assert!(md.ends_with("Rust prevents data races.\n"));
+    }
+
+    #[test]
+    fn multiple_responses_render_as_list_or_separated_documents() {
+        let responses = [sample_response(), sample_response()];
+        let json = format_responses(&responses, OutputFormat::Json).unwrap();
+        let value: serde_json::Value = serde_json::from_str(&json).unwrap();
+        assert_eq!(value.as_array().map(Vec::len), Some(2));
+
+        let md = format_responses(&responses, OutputFormat::Markdown).unwrap();
+        assert_eq!(md.matches("# Rust\n").count(), 2);
+        assert!(md.contains("\n---\n\n# Rust"));
+    }
+
+    #[test]
+    fn yaml_contains_fields() {
+        let yaml = format_response(&sample_response(), OutputFormat::Yaml).unwrap();
+        assert!(yaml.contains("title: Rust"));
+        assert!(yaml.contains("confidence: 0.9"));
+    }
+}
@@ -0,0 +1,99 @@
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::time::Duration;
+
+use crate::deepseek::DeepSeekClient;
+
+/// Outcome of a TaskFinisher session for the run report
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct TaskFinisherSummary {
+    pub artifact_produced: bool,
+    /// Filled artifact sections, or the last checklist's completion when no artifact was produced
+    pub completeness_percent: f32,
+}
+
+/// Machine-readable report of a whole run, printed with `--summary`
+#[derive(Debug, Clone, Serialize)]
+pub struct SessionSummary {
+    pub requests: u64,
+    pub total_tokens: u64,
+    pub elapsed_secs: f64,
+    pub errors: BTreeMap<String, u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub taskfinisher: Option<TaskFinisherSummary>,
+}
+
+impl SessionSummary {
+    /// Collect the accumulated client statistics for a run that took `elapsed`
+    pub fn collect(
+        client: &DeepSeekClient,
+        elapsed: Duration,
+        taskfinisher: Option<TaskFinisherSummary>,
+    ) -> Self {
+        Self {
+            requests: client.requests_sent(),
+            total_tokens: client.tokens_used(),
+            elapsed_secs: elapsed.as_secs_f64(),
+            errors: client.error_counts(),
+            taskfinisher,
+        }
+    }
+
+    /// Human-readable multi-line report
+    pub fn to_text(&self) -> String {
+        let mut text = format!(
+            "Session summary\n  Requests: {}\n  Total tokens: {}\n  Total time: {:.1}s\n",
+            self.requests, self.total_tokens, self.elapsed_secs
+        );
+        if self.errors.is_empty() {
+            text.push_str("  Errors: none\n");
+        } else {
+            let errors: Vec<String> = self
+                .errors
+                .iter()
+                .map(|(kind, count)| format!("{} x{}", kind, count))
+                .collect();
+            text.push_str(&format!("  Errors: {}\n", errors.join(", ")));
+        }
+        if let Some(taskfinisher) = &self.taskfinisher {
+            text.push_str(&format!(
+                "  Artifact produced: {}\n  Completeness: {:.0}%\n",
+                if taskfinisher.artifact_produced {
+                    "yes"
+                } else {
+                    "no"
+                },
+                taskfinisher.completeness_percent
+            ));
+        }
+        text
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn text_summary_lists_errors_and_artifact() {
+        let summary = SessionSummary {
+            requests: 3,
+            total_tokens: 1200,
+            elapsed_secs: 4.25,
+            errors: BTreeMap::from([("timeout".to_string(), 2)]),
+            taskfinisher: Some(TaskFinisherSummary {
+                artifact_produced: true,
+                completeness_percent: 90.0,
+            }),
+        };
+        let text = summary.to_text();
+        assert!(text.contains("Requests: 3"));
+        assert!(text.contains("Total time: 4.2s") || text.contains("Total time: 4.3s"));
+        assert!(text.contains("Errors: timeout x2"));
+        assert!(text.contains("Artifact produced: yes"));
+
+        let json = serde_json::to_value(&summary).unwrap();
+        assert_eq!(json["errors"]["timeout"], 2);
+        assert_eq!(json["taskfinisher"]["artifact_produced"], true);
+    }
+}
@@ -1,10 +1,25 @@
-use serde::{Deserialize, Serialize};
+use anyhow::{Context, Result};
+use schemars::JsonSchema;
+use serde::{Deserialize, Deserializer, Serialize};
+use std::path::Path;
+
+use crate::deepseek::ChatMessage;
+use crate::extract::{
+    byte_offset, extract_json_object, from_str_or_embedded, offset_in, strip_bom, strip_code_fences,
+};
 
 /// Default maximum number of clarifying questions
 pub const DEFAULT_MAX_QUESTIONS: u32 = 3;
 
-/// Build the TaskFinisher-JSON system prompt with a given max question limit
-pub fn build_system_prompt(max_questions: u32) -> String {
+/// Default maximum number of clarification rounds before giving up on the artifact
+pub const DEFAULT_MAX_ROUNDS: u32 = 5;
+
+/// Default token the model must place in the final artifact's `end_token` field
+pub const END_TOKEN: &str = "【END】";
+
+/// Build the TaskFinisher-JSON system prompt with a given max question limit and the
+/// `end_token` the final artifact must carry
+pub fn build_system_prompt(max_questions: u32, end_token: &str) -> String {
     format!(
         r#"You are TaskFinisher-JSON.
 
@@ -20,7 +35,7 @@ DEFINITION OF DONE
 - If information is missing after your questions or the user says \"proceed\", finalize anyway with minimal, labeled assumptions in \"assumptions\" and any remaining items in \"open_questions\".
 
 SELF-STOP RULE
-- When you output the final \"artifact\", include: \"status\":\"final\" and \"end_token\":\"【END】\".
+- When you output the final \"artifact\", include: \"status\":\"final\" and \"end_token\":\"{end_token}\".
 - After that, STOP. Do not send more messages.
 
 FORMAT RULES
@@ -75,7 +90,7 @@ ARTIFACT SHAPE (Technical Task JSON)
   ],
   "open_questions": ["<string>", ...],
   "status": "final",
-  "end_token": "【END】"
+  "end_token": "{end_token}"
 }}
 
 IMPORTANT
@@ -89,6 +104,11 @@ CONFIG
     )
 }
 
+/// JSON Schema of the final artifact, the same shape `build_system_prompt` describes in prose
+pub fn artifact_json_schema() -> serde_json::Value {
+    schemars::schema_for!(TechnicalTaskArtifact).to_value()
+}
+
 // =====================
 // JSON Types
 // =====================
@@ -98,10 +118,35 @@ pub struct ClarifyingQuestion {
     pub id: String,
     pub text: String,
     pub required: bool,
-    #[serde(default)]
+    #[serde(default, deserialize_with = "deserialize_options")]
     pub options: Option<Vec<String>>,
 }
 
+/// Accept `options` either as a JSON array or as a single comma-separated string
+fn deserialize_options<'de, D>(deserializer: D) -> Result<Option<Vec<String>>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum RawOptions {
+        List(Vec<String>),
+        Delimited(String),
+    }
+
+    Ok(match Option::<RawOptions>::deserialize(deserializer)? {
+        None => None,
+        Some(RawOptions::List(items)) => Some(items),
+        Some(RawOptions::Delimited(text)) => Some(
+            text.split(',')
+                .map(str::trim)
+                .filter(|item| !item.is_empty())
+                .map(str::to_string)
+                .collect(),
+        ),
+    })
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ChecklistItem {
     pub field: String,
@@ -119,19 +164,19 @@ pub struct ClarifyingQuestionsPayload {
     pub next_action: String,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
 pub struct Stakeholder {
     pub role: String,
     pub description: String,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
 pub struct Scope {
     pub in_scope: Vec<String>,
     pub out_of_scope: Vec<String>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
 pub struct FunctionalRequirement {
     pub id: String,
     pub statement: String,
@@ -139,53 +184,53 @@ pub struct FunctionalRequirement {
     pub rationale: Option<String>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
 pub struct NonFunctionalRequirement {
     pub id: String,
     pub category: String,
     pub target: String,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
 pub struct Requirements {
     pub functional: Vec<FunctionalRequirement>,
     pub non_functional: Vec<NonFunctionalRequirement>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
 pub struct RpcProviders {
     pub selection: Vec<String>,
     pub endpoints: serde_json::Map<String, serde_json::Value>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
 pub struct PriceSource {
     pub provider: String,
     #[serde(default)]
     pub ttl_seconds: Option<u64>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
 pub struct DataIntegrations {
     pub rpc_providers: RpcProviders,
     pub price_source: PriceSource,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
 pub struct Risk {
     pub id: String,
     pub description: String,
     pub mitigation: String,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
 pub struct Milestone {
     pub id: String,
     pub name: String,
     pub deliverables: Vec<String>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
 pub struct AcceptanceCriterion {
     pub id: String,
     pub given: String,
@@ -193,12 +238,14 @@ pub struct AcceptanceCriterion {
     pub then: String,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
 pub struct TechnicalTaskArtifact {
     #[serde(rename = "type")]
+    #[schemars(extend("const" = "artifact"))]
     pub type_field: String, // "artifact"
+    #[schemars(extend("const" = "technical_task"))]
     pub artifact_name: String, // "technical_task"
-    pub version: String,       // "1.0"
+    pub version: String, // "1.0"
     pub title: String,
     pub summary: String,
     pub stakeholders: Vec<Stakeholder>,
@@ -211,8 +258,12 @@ pub struct TechnicalTaskArtifact {
     pub milestones: Vec<Milestone>,
     pub acceptance_criteria: Vec<AcceptanceCriterion>,
     pub open_questions: Vec<String>,
-    pub status: String,    // "final"
+    #[schemars(extend("const" = "final"))]
+    pub status: String, // "final"
     pub end_token: String, // "【END】"
+    /// Top-level fields the schema does not know about, kept so they survive re-serialization
+    #[serde(flatten)]
+    pub extra: serde_json::Map<String, serde_json::Value>,
 }
 
 #[derive(Debug, Clone)]
@@ -221,8 +272,22 @@ pub enum TaskFinisherResult {
     Artifact(Box<TechnicalTaskArtifact>, String),   // parsed + raw JSON string
 }
 
+/// How a TaskFinisher run ended
+#[derive(Debug, Clone)]
+pub enum TaskFinisherOutput {
+    /// The model produced the final artifact
+    Artifact(Box<TechnicalTaskArtifact>),
+    /// The clarification round limit was hit; the latest assistant reply, unparsed
+    MaxRoundsReached(String),
+    /// The latest assistant reply could not be parsed
+    ParseFailed { error: String, raw: String },
+    /// The user stopped the run with `/cancel` while answering questions
+    Cancelled,
+}
+
 pub fn parse_taskfinisher_response(raw: &str) -> Result<TaskFinisherResult, String> {
-    let value: serde_json::Value = serde_json::from_str(raw)
+    let raw = strip_code_fences(strip_bom(raw));
+    let value: serde_json::Value = from_str_or_embedded(raw)
         .map_err(|e| format!("Failed to parse TaskFinisher JSON: {}", e))?;
     let typ = value
         .get("type")
@@ -246,6 +311,152 @@ pub fn parse_taskfinisher_response(raw: &str) -> Result<TaskFinisherResult, Stri
     }
 }
 
+/// Recovery step applied while leniently parsing a TaskFinisher response
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ParseWarning {
+    /// A leading UTF-8 byte order mark was removed
+    StrippedBom,
+    /// Markdown code fences around the JSON were removed
+    StrippedCodeFences,
+    /// The JSON object was extracted from surrounding text (byte range in the raw input)
+    ExtractedObject { start: usize, end: usize },
+}
+
+/// Structured description of why a TaskFinisher response could not be parsed
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseDiagnostics {
+    pub message: String,
+    /// Byte offset into the raw input where parsing failed, when known
+    pub offset: Option<usize>,
+    /// Path of the JSON value that failed to deserialize (e.g. `requirements.functional[0].id`)
+    pub path: Option<String>,
+    /// Recovery steps that were applied before the failure
+    pub warnings: Vec<ParseWarning>,
+}
+
+impl std::fmt::Display for ParseDiagnostics {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.message)?;
+        if let Some(path) = &self.path {
+            write!(f, " (path: {})", path)?;
+        }
+        if let Some(offset) = self.offset {
+            write!(f, " at byte {}", offset)?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for ParseDiagnostics {}
+
+/// Parse a TaskFinisher response, recovering from common model formatting mistakes
+/// (BOM, Markdown fences, prose around the JSON). Returns the applied recovery steps
+/// alongside the result, or structured diagnostics on failure.
+pub fn parse_taskfinisher_response_lenient(
+    raw: &str,
+) -> Result<(TaskFinisherResult, Vec<ParseWarning>), ParseDiagnostics> {
+    let mut warnings = Vec::new();
+
+    let unbommed = strip_bom(raw);
+    if unbommed.len() != raw.len() {
+        warnings.push(ParseWarning::StrippedBom);
+    }
+    let unfenced = strip_code_fences(unbommed);
+    if unfenced.len() != unbommed.len() {
+        warnings.push(ParseWarning::StrippedCodeFences);
+    }
+    let mut candidate = unfenced.trim();
+
+    let diagnostics =
+        |message: String, offset, path, warnings: &Vec<ParseWarning>| ParseDiagnostics {
+            message,
+            offset,
+            path,
+            warnings: warnings.clone(),
+        };
+
+    let value: serde_json::Value = match serde_json::from_str(candidate) {
+        Ok(value) => value,
+        Err(err) => {
+            let recovered = extract_json_object(candidate)
+                .and_then(|obj| serde_json::from_str(obj).ok().map(|v| (obj, v)));
+            match recovered {
+                Some((obj, value)) => {
+                    let start = offset_in(raw, obj);
+                    warnings.push(ParseWarning::ExtractedObject {
+                        start,
+                        end: start + obj.len(),
+                    });
+                    candidate = obj;
+                    value
+                }
+                None => {
+                    let offset = offset_in(raw, candidate)
+                        + byte_offset(candidate, err.line(), err.column());
+                    return Err(diagnostics(
+                        format!("Failed to parse TaskFinisher JSON: {}", err),
+                        Some(offset),
+                        None,
+                        &warnings,
+                    ));
+                }
+            }
+        }
+    };
+
+    let typ = match value.get("type").and_then(|v| v.as_str()) {
+        Some(typ) => typ,
+        None => {
+            return Err(diagnostics(
+                "Missing 'type' in TaskFinisher response".to_string(),
+                None,
+                Some("type".to_string()),
+                &warnings,
+            ));
+        }
+    };
+
+    let typed_error = |err: serde_path_to_error::Error<serde_json::Error>, shape: &str| {
+        let path = err.path().to_string();
+        let inner = err.inner();
+        let offset =
+            offset_in(raw, candidate) + byte_offset(candidate, inner.line(), inner.column());
+        diagnostics(
+            format!("Invalid {} shape: {}", shape, inner),
+            Some(offset),
+            Some(path),
+            &warnings,
+        )
+    };
+
+    let result = match typ {
+        "clarifying_questions" => {
+            let parsed: ClarifyingQuestionsPayload = serde_path_to_error::deserialize(
+                &mut serde_json::Deserializer::from_str(candidate),
+            )
+            .map_err(|e| typed_error(e, "clarifying_questions"))?;
+            TaskFinisherResult::Clarifying(parsed, candidate.to_string())
+        }
+        "artifact" => {
+            let parsed: TechnicalTaskArtifact = serde_path_to_error::deserialize(
+                &mut serde_json::Deserializer::from_str(candidate),
+            )
+            .map_err(|e| typed_error(e, "artifact"))?;
+            TaskFinisherResult::Artifact(Box::new(parsed), candidate.to_string())
+        }
+        other => {
+            return Err(diagnostics(
+                format!("Unsupported 'type': {}", other),
+                None,
+                Some("type".to_string()),
+                &warnings,
+            ));
+        }
+    };
+
+    Ok((result, warnings))
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct AnswerItem {
     pub id: String,
@@ -256,3 +467,994 @@ pub struct AnswerItem {
 pub struct AnswersPayload {
     pub answers: Vec<AnswerItem>,
 }
+
+/// A clarifying question together with the answer the user gave (if any)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LoggedQuestion {
+    pub id: String,
+    pub text: String,
+    pub required: bool,
+    pub answer: Option<String>,
+}
+
+/// Questions, answers, and checklist state from a single clarification round
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct QuestionLogRound {
+    pub round: u32,
+    pub questions: Vec<LoggedQuestion>,
+    pub checklist: Vec<ChecklistItem>,
+}
+
+/// Structured record of every clarification round in a session, for analyzing
+/// which fields commonly need clarification
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct QuestionLog {
+    pub rounds: Vec<QuestionLogRound>,
+}
+
+impl QuestionLog {
+    /// Record the questions of a round and the answers given to them
+    pub fn record(
+        &mut self,
+        round: u32,
+        payload: &ClarifyingQuestionsPayload,
+        answers: &AnswersPayload,
+    ) {
+        let questions = payload
+            .questions
+            .iter()
+            .map(|q| LoggedQuestion {
+                id: q.id.clone(),
+                text: q.text.clone(),
+                required: q.required,
+                answer: answers
+                    .answers
+                    .iter()
+                    .find(|a| a.id == q.id)
+                    .map(|a| a.answer.clone()),
+            })
+            .collect();
+
+        self.rounds.push(QuestionLogRound {
+            round,
+            questions,
+            checklist: payload.checklist.clone(),
+        });
+    }
+}
+
+/// Checklist state of one clarification round, written as a JSON line by `--checklist-log`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ChecklistProgress {
+    pub round: u32,
+    /// Share of required fields collected so far, 0-100
+    pub completion_percent: f32,
+    pub checklist: Vec<ChecklistItem>,
+}
+
+impl ChecklistProgress {
+    pub fn new(round: u32, checklist: &[ChecklistItem]) -> Self {
+        Self {
+            round,
+            completion_percent: checklist_completion(checklist),
+            checklist: checklist.to_vec(),
+        }
+    }
+}
+
+/// Completion percentage of a checklist: "complete" counts fully, "partial" counts half.
+/// An empty checklist is 0% complete.
+pub fn checklist_completion(checklist: &[ChecklistItem]) -> f32 {
+    if checklist.is_empty() {
+        return 0.0;
+    }
+    let score: f32 = checklist
+        .iter()
+        .map(
+            |item| match item.status.trim().to_ascii_lowercase().as_str() {
+                "complete" => 1.0,
+                "partial" => 0.5,
+                _ => 0.0,
+            },
+        )
+        .sum();
+    score / checklist.len() as f32 * 100.0
+}
+
+/// Checklist items that are not yet complete, in checklist order
+pub fn incomplete_fields(checklist: &[ChecklistItem]) -> Vec<&ChecklistItem> {
+    checklist
+        .iter()
+        .filter(|item| !item.status.trim().eq_ignore_ascii_case("complete"))
+        .collect()
+}
+
+/// Share of the artifact's content sections that are filled in, 0-100.
+/// Open questions are not counted, since an empty list there is the goal.
+pub fn artifact_completeness(artifact: &TechnicalTaskArtifact) -> f32 {
+    let sections = [
+        !artifact.summary.trim().is_empty(),
+        !artifact.stakeholders.is_empty(),
+        !artifact.scope.in_scope.is_empty(),
+        !artifact.requirements.functional.is_empty(),
+        !artifact.requirements.non_functional.is_empty(),
+        !artifact.constraints.is_empty(),
+        !artifact.assumptions.is_empty(),
+        !artifact.risks.is_empty(),
+        !artifact.milestones.is_empty(),
+        !artifact.acceptance_criteria.is_empty(),
+    ];
+    let filled = sections.iter().filter(|filled| **filled).count();
+    filled as f32 / sections.len() as f32 * 100.0
+}
+
+/// Check a parsed artifact for problems that deserialization alone does not catch:
+/// duplicate requirement/risk/milestone/acceptance IDs, a wrong `status`, and an `end_token`
+/// other than the configured one. Returns every violation found.
+pub fn validate_artifact(
+    artifact: &TechnicalTaskArtifact,
+    end_token: &str,
+) -> Result<(), Vec<String>> {
+    fn check_unique<'a>(
+        kind: &str,
+        ids: impl Iterator<Item = &'a str>,
+        violations: &mut Vec<String>,
+    ) {
+        let mut seen = std::collections::HashSet::new();
+        let mut reported = std::collections::HashSet::new();
+        for id in ids {
+            if !seen.insert(id) && reported.insert(id) {
+                violations.push(format!("Duplicate {} ID '{}'", kind, id));
+            }
+        }
+    }
+
+    let mut violations = Vec::new();
+    let requirements = &artifact.requirements;
+    check_unique(
+        "functional requirement",
+        requirements.functional.iter().map(|r| r.id.as_str()),
+        &mut violations,
+    );
+    check_unique(
+        "non-functional requirement",
+        requirements.non_functional.iter().map(|r| r.id.as_str()),
+        &mut violations,
+    );
+    check_unique(
+        "risk",
+        artifact.risks.iter().map(|r| r.id.as_str()),
+        &mut violations,
+    );
+    check_unique(
+        "milestone",
+        artifact.milestones.iter().map(|m| m.id.as_str()),
+        &mut violations,
+    );
+    check_unique(
+        "acceptance criterion",
+        artifact.acceptance_criteria.iter().map(|ac| ac.id.as_str()),
+        &mut violations,
+    );
+
+    if artifact.status != "final" {
+        violations.push(format!(
+            "Expected status \"final\", got \"{}\"",
+            artifact.status
+        ));
+    }
+    if artifact.end_token != end_token {
+        violations.push(format!(
+            "Expected end_token \"{}\", got \"{}\"",
+            end_token, artifact.end_token
+        ));
+    }
+
+    if violations.is_empty() {
+        Ok(())
+    } else {
+        Err(violations)
+    }
+}
+
+/// One field-level difference between two artifacts
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ArtifactChange {
+    /// An item present only in the new artifact, e.g. a new requirement
+    Added { field: String, item: String },
+    /// An item present only in the old artifact
+    Removed { field: String, item: String },
+    /// A value or an item with the same ID that differs between the artifacts
+    Changed {
+        field: String,
+        old: String,
+        new: String,
+    },
+}
+
+/// Differences between two versions of an artifact, in artifact field order
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ArtifactDiff {
+    pub changes: Vec<ArtifactChange>,
+}
+
+impl ArtifactDiff {
+    pub fn is_empty(&self) -> bool {
+        self.changes.is_empty()
+    }
+
+    fn value(&mut self, field: &str, old: &str, new: &str) {
+        if old != new {
+            self.changes.push(ArtifactChange::Changed {
+                field: field.to_string(),
+                old: old.to_string(),
+                new: new.to_string(),
+            });
+        }
+    }
+
+    /// Compare two lists of `(key, text)` items: items are matched by key, so an item whose
+    /// key stays the same but whose text differs is reported as changed
+    fn items(&mut self, field: &str, old: Vec<(String, String)>, new: Vec<(String, String)>) {
+        for (key, old_text) in &old {
+            match new.iter().find(|(new_key, _)| new_key == key) {
+                None => self.changes.push(ArtifactChange::Removed {
+                    field: field.to_string(),
+                    item: old_text.clone(),
+                }),
+                Some((_, new_text)) => self.value(field, old_text, new_text),
+            }
+        }
+        for (key, new_text) in &new {
+            if !old.iter().any(|(old_key, _)| old_key == key) {
+                self.changes.push(ArtifactChange::Added {
+                    field: field.to_string(),
+                    item: new_text.clone(),
+                });
+            }
+        }
+    }
+
+    /// Compare two lists of plain strings, which have no key besides their text
+    fn strings(&mut self, field: &str, old: &[String], new: &[String]) {
+        let keyed = |items: &[String]| {
+            items
+                .iter()
+                .map(|item| (item.clone(), item.clone()))
+                .collect()
+        };
+        self.items(field, keyed(old), keyed(new));
+    }
+}
+
+/// Field-level differences between an earlier and a regenerated artifact: changed text
+/// fields, and added, removed or changed list items (requirements, scope items, risks, ...).
+/// Items with an ID are matched by it; plain strings are matched by their text.
+pub fn diff_artifacts(old: &TechnicalTaskArtifact, new: &TechnicalTaskArtifact) -> ArtifactDiff {
+    fn keyed<T>(items: &[T], item: impl Fn(&T) -> (&str, String)) -> Vec<(String, String)> {
+        items
+            .iter()
+            .map(|i| {
+                let (key, text) = item(i);
+                (key.to_string(), text)
+            })
+            .collect()
+    }
+    let stakeholders = |a: &TechnicalTaskArtifact| {
+        keyed(&a.stakeholders, |s| {
+            (&s.role, format!("{}: {}", s.role, s.description))
+        })
+    };
+    let functional = |a: &TechnicalTaskArtifact| {
+        keyed(&a.requirements.functional, |r| {
+            (&r.id, format!("{}: {}", r.id, r.statement))
+        })
+    };
+    let non_functional = |a: &TechnicalTaskArtifact| {
+        keyed(&a.requirements.non_functional, |r| {
+            (&r.id, format!("{} ({}): {}", r.id, r.category, r.target))
+        })
+    };
+    let risks = |a: &TechnicalTaskArtifact| {
+        keyed(&a.risks, |r| {
+            (
+                &r.id,
+                format!("{}: {} (mitigation: {})", r.id, r.description, r.mitigation),
+            )
+        })
+    };
+    let milestones = |a: &TechnicalTaskArtifact| {
+        keyed(&a.milestones, |m| {
+            (
+                &m.id,
+                format!("{}: {} [{}]", m.id, m.name, m.deliverables.join(", ")),
+            )
+        })
+    };
+    let acceptance = |a: &TechnicalTaskArtifact| {
+        keyed(&a.acceptance_criteria, |ac| {
+            (
+                &ac.id,
+                format!(
+                    "{}: given {}, when {}, then {}",
+                    ac.id, ac.given, ac.when, ac.then
+                ),
+            )
+        })
+    };
+    let integrations =
+        |a: &TechnicalTaskArtifact| serde_json::to_string(&a.data_integrations).unwrap_or_default();
+
+    let mut diff = ArtifactDiff::default();
+    diff.value("title", &old.title, &new.title);
+    diff.value("summary", &old.summary, &new.summary);
+    diff.items("stakeholders", stakeholders(old), stakeholders(new));
+    diff.strings("scope.in_scope", &old.scope.in_scope, &new.scope.in_scope);
+    diff.strings(
+        "scope.out_of_scope",
+        &old.scope.out_of_scope,
+        &new.scope.out_of_scope,
+    );
+    diff.items("requirements.functional", functional(old), functional(new));
+    diff.items(
+        "requirements.non_functional",
+        non_functional(old),
+        non_functional(new),
+    );
+    diff.value("data_integrations", &integrations(old), &integrations(new));
+    diff.strings("constraints", &old.constraints, &new.constraints);
+    diff.strings("assumptions", &old.assumptions, &new.assumptions);
+    diff.items("risks", risks(old), risks(new));
+    diff.items("milestones", milestones(old), milestones(new));
+    diff.items("acceptance_criteria", acceptance(old), acceptance(new));
+    diff.strings("open_questions", &old.open_questions, &new.open_questions);
+    diff
+}
+
+/// Serializable snapshot of a TaskFinisher conversation, used to resume interrupted sessions
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TaskFinisherSession {
+    /// Current clarification round
+    pub round: u32,
+    /// Full chat history, including the system prompt and the latest assistant reply
+    pub history: Vec<ChatMessage>,
+}
+
+impl TaskFinisherSession {
+    /// Load a session from disk. Returns `Ok(None)` when the file does not exist.
+    pub fn load(path: &Path) -> Result<Option<Self>> {
+        if !path.exists() {
+            return Ok(None);
+        }
+        let data = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read session file {}", path.display()))?;
+        let session = serde_json::from_str(&data)
+            .with_context(|| format!("Corrupt session file {}", path.display()))?;
+        Ok(Some(session))
+    }
+
+    /// The artifact this session ended with, if its latest assistant reply is one
+    pub fn final_artifact(&self) -> Option<Box<TechnicalTaskArtifact>> {
+        let last = self.history.last().filter(|m| m.role == "assistant")?;
+        match parse_taskfinisher_response(&last.content) {
+            Ok(TaskFinisherResult::Artifact(artifact, _)) => Some(artifact),
+            _ => None,
+        }
+    }
+
+    /// The messages before the first assistant reply: the system prompt and the request
+    pub fn opening_messages(&self) -> &[ChatMessage] {
+        let end = self
+            .history
+            .iter()
+            .position(|m| m.role == "assistant")
+            .unwrap_or(self.history.len());
+        &self.history[..end]
+    }
+
+    /// Write the session to disk as JSON
+    pub fn save(&self, path: &Path) -> Result<()> {
+        let data = serde_json::to_string_pretty(self).context("Failed to serialize session")?;
+        std::fs::write(path, data)
+            .with_context(|| format!("Failed to write session file {}", path.display()))
+    }
+}
+
+/// Render a Technical Task artifact as a Markdown document.
+/// Sections without content are omitted.
+pub fn artifact_to_markdown(artifact: &TechnicalTaskArtifact) -> String {
+    fn bullets(md: &mut String, items: &[String]) {
+        for item in items {
+            md.push_str(&format!("- {}\n", item));
+        }
+    }
+
+    let mut md = format!("# {}\n\n", artifact.title);
+    md.push_str(&format!(
+        "_{} v{}_\n\n",
+        artifact.artifact_name, artifact.version
+    ));
+    if !artifact.summary.is_empty() {
+        md.push_str(&format!("{}\n\n", artifact.summary));
+    }
+
+    if !artifact.stakeholders.is_empty() {
+        md.push_str("## Stakeholders\n\n");
+        for stakeholder in &artifact.stakeholders {
+            md.push_str(&format!(
+                "- **{}** — {}\n",
+                stakeholder.role, stakeholder.description
+            ));
+        }
+        md.push('\n');
+    }
+
+    let scope = &artifact.scope;
+    if !scope.in_scope.is_empty() || !scope.out_of_scope.is_empty() {
+        md.push_str("## Scope\n\n");
+        if !scope.in_scope.is_empty() {
+            md.push_str("### In scope\n\n");
+            bullets(&mut md, &scope.in_scope);
+            md.push('\n');
+        }
+        if !scope.out_of_scope.is_empty() {
+            md.push_str("### Out of scope\n\n");
+            bullets(&mut md, &scope.out_of_scope);
+            md.push('\n');
+        }
+    }
+
+    let requirements = &artifact.requirements;
+    if !requirements.functional.is_empty() || !requirements.non_functional.is_empty() {
+        md.push_str("## Requirements\n\n");
+        if !requirements.functional.is_empty() {
+            md.push_str("### Functional\n\n");
+            for fr in &requirements.functional {
+                md.push_str(&format!("- **{}**: {}\n", fr.id, fr.statement));
+                if let Some(rationale) = &fr.rationale
+                    && !rationale.is_empty()
+                {
+                    md.push_str(&format!("  - _Rationale:_ {}\n", rationale));
+                }
+            }
+            md.push('\n');
+        }
+        if !requirements.non_functional.is_empty() {
+            md.push_str("### Non-functional\n\n");
+            for nfr in &requirements.non_functional {
+                md.push_str(&format!(
+                    "- **{}** ({}): {}\n",
+                    nfr.id, nfr.category, nfr.target
+                ));
+            }
+            md.push('\n');
+        }
+    }
+
+    let integrations = &artifact.data_integrations;
+    let rpc = &integrations.rpc_providers;
+    let price_provider = integrations.price_source.provider.trim();
+    let has_price_source =
+        !price_provider.is_empty() && !price_provider.eq_ignore_ascii_case("none");
+    if !rpc.selection.is_empty() || !rpc.endpoints.is_empty() || has_price_source {
+        md.push_str("## Data integrations\n\n");
+        if !rpc.selection.is_empty() {
+            md.push_str(&format!(
+                "- **RPC providers:** {}\n",
+                rpc.selection.join(", ")
+            ));
+        }
+        for (name, value) in &rpc.endpoints {
+            let value = value
+                .as_str()
+                .map_or_else(|| value.to_string(), str::to_string);
+            md.push_str(&format!("- **Endpoint `{}`:** `{}`\n", name, value));
+        }
+        if has_price_source {
+            md.push_str(&format!("- **Price source:** {}", price_provider));
+            if let Some(ttl) = integrations.price_source.ttl_seconds {
+                md.push_str(&format!(" (ttl {}s)", ttl));
+            }
+            md.push('\n');
+        }
+        md.push('\n');
+    }
+
+    for (heading, items) in [
+        ("Constraints", &artifact.constraints),
+        ("Assumptions", &artifact.assumptions),
+    ] {
+        if !items.is_empty() {
+            md.push_str(&format!("## {}\n\n", heading));
+            bullets(&mut md, items);
+            md.push('\n');
+        }
+    }
+
+    if !artifact.risks.is_empty() {
+        md.push_str("## Risks\n\n");
+        for risk in &artifact.risks {
+            md.push_str(&format!("- **{}**: {}\n", risk.id, risk.description));
+            md.push_str(&format!("  - _Mitigation:_ {}\n", risk.mitigation));
+        }
+        md.push('\n');
+    }
+
+    if !artifact.milestones.is_empty() {
+        md.push_str("## Milestones\n\n");
+        for milestone in &artifact.milestones {
+            md.push_str(&format!("### {} — {}\n\n", milestone.id, milestone.name));
+            bullets(&mut md, &milestone.deliverables);
+            md.push('\n');
+        }
+    }
+
+    if !artifact.acceptance_criteria.is_empty() {
+        md.push_str("## Acceptance criteria\n\n");
+        for ac in &artifact.acceptance_criteria {
+            md.push_str(&format!(
+                "- **{}**: Given {}, when {}, then {}\n",
+                ac.id, ac.given, ac.when, ac.then
+            ));
+        }
+        md.push('\n');
+    }
+
+    if !artifact.open_questions.is_empty() {
+        md.push_str("## Open questions\n\n");
+        bullets(&mut md, &artifact.open_questions);
+        md.push('\n');
+    }
+
+    md.truncate(md.trim_end().len());
+    md.push('\n');
+    md
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample_artifact() -> TechnicalTaskArtifact {
+        serde_json::from_value(serde_json::json!({
+            "type": "artifact",
+            "artifact_name": "technical_task",
+            "version": "1.0",
+            "title": "Price cache service",
+            "summary": "Fetch token prices and cache them.",
+            "stakeholders": [{ "role": "Backend team", "description": "Owns the service" }],
+            "scope": { "in_scope": ["Price fetching"], "out_of_scope": [] },
+            "requirements": {
+                "functional": [
+                    { "id": "FR1", "statement": "Fetch prices", "rationale": "Core feature" }
+                ],
+                "non_functional": [
+                    { "id": "NFR1", "category": "performance", "target": "p95 < 100ms" }
+                ]
+            },
+            "data_integrations": {
+                "rpc_providers": { "selection": [], "endpoints": {} },
+                "price_source": { "provider": "CoinGecko", "ttl_seconds": 60 }
+            },
+            "constraints": [],
+            "assumptions": ["Prices in USD"],
+            "risks": [{ "id": "R1", "description": "API outage", "mitigation": "Serve stale" }],
+            "milestones": [{ "id": "M1", "name": "MVP", "deliverables": ["Fetcher"] }],
+            "acceptance_criteria": [
+                { "id": "AC1", "given": "a cached price", "when": "queried", "then": "it returns" }
+            ],
+            "open_questions": [],
+            "status": "final",
+            "end_token": "【END】"
+        }))
+        .expect("sample artifact should deserialize")
+    }
+
+    #[test]
+    fn question_log_pairs_questions_with_answers() {
+        let payload: ClarifyingQuestionsPayload = serde_json::from_str(&clarifying_json()).unwrap();
+        let answers = AnswersPayload {
+            answers: vec![AnswerItem {
+                id: "q1".to_string(),
+                answer: "Ethereum".to_string(),
+            }],
+        };
+
+        let mut log = QuestionLog::default();
+        log.record(1, &payload, &answers);
+        log.record(2, &payload, &AnswersPayload { answers: vec![] });
+
+        assert_eq!(log.rounds.len(), 2);
+        assert_eq!(
+            log.rounds[0].questions[0].answer.as_deref(),
+            Some("Ethereum")
+        );
+        assert_eq!(log.rounds[0].checklist[0].field, "title");
+        assert_eq!(log.rounds[1].round, 2);
+        assert!(log.rounds[1].questions[0].answer.is_none());
+    }
+
+    #[test]
+    fn artifact_round_trip_keeps_unknown_fields() {
+        let mut value = serde_json::to_value(sample_artifact()).unwrap();
+        value["priority"] = serde_json::json!("high");
+
+        let artifact: TechnicalTaskArtifact = serde_json::from_value(value).unwrap();
+        assert_eq!(artifact.extra["priority"], "high");
+
+        let exported = serde_json::to_value(&artifact).unwrap();
+        assert_eq!(exported["priority"], "high");
+        assert_eq!(exported["title"], "Price cache service");
+        assert!(exported.get("extra").is_none());
+    }
+
+    #[test]
+    fn validate_artifact_accepts_well_formed_artifact() {
+        assert_eq!(validate_artifact(&sample_artifact(), END_TOKEN), Ok(()));
+    }
+
+    #[test]
+    fn validate_artifact_reports_all_violations() {
+        let mut artifact = sample_artifact();
+        let duplicate = artifact.requirements.functional[0].clone();
+        artifact.requirements.functional.push(duplicate.clone());
+        artifact.requirements.functional.push(duplicate);
+        let risk = artifact.risks[0].clone();
+        artifact.risks.push(risk);
+        artifact.status = "draft".to_string();
+        artifact.end_token = "END".to_string();
+
+        let violations = validate_artifact(&artifact, END_TOKEN).unwrap_err();
+        assert_eq!(
+            violations,
+            vec![
+                "Duplicate functional requirement ID 'FR1'".to_string(),
+                "Duplicate risk ID 'R1'".to_string(),
+                "Expected status \"final\", got \"draft\"".to_string(),
+                "Expected end_token \"【END】\", got \"END\"".to_string(),
+            ]
+        );
+    }
+
+    #[test]
+    fn custom_end_token_is_requested_and_enforced() {
+        let prompt = build_system_prompt(3, "<<END>>");
+        assert!(prompt.contains(r#""end_token": "<<END>>""#));
+        assert!(!prompt.contains(END_TOKEN));
+
+        let mut artifact = sample_artifact();
+        artifact.end_token = "<<END>>".to_string();
+        assert_eq!(validate_artifact(&artifact, "<<END>>"), Ok(()));
+        assert!(validate_artifact(&sample_artifact(), "<<END>>").is_err());
+    }
+
+    #[test]
+    fn artifact_markdown_renders_sections() {
+        let md = artifact_to_markdown(&sample_artifact());
+        assert!(md.starts_with("# Price cache service\n"));
+        assert!(md.contains("## Stakeholders\n\n- **Backend team** — Owns the service\n"));
+        assert!(md.contains("### In scope\n\n- Price fetching\n"));
+        assert!(md.contains("- **FR1**: Fetch prices\n  - _Rationale:_ Core feature\n"));
+        assert!(md.contains("- **NFR1** (performance): p95 < 100ms\n"));
+        assert!(md.contains("- **R1**: API outage\n  - _Mitigation:_ Serve stale\n"));
+        assert!(md.contains("### M1 — MVP\n\n- Fetcher\n"));
+        assert!(md.contains("## Data integrations\n\n- **Price source:** CoinGecko (ttl 60s)\n"));
+        assert!(md.contains("- **AC1**: Given a cached price, when queried, then it returns\n"));
+    }
+
+    #[test]
+    fn artifact_markdown_omits_empty_sections() {
+        let md = artifact_to_markdown(&sample_artifact());
+        assert!(!md.contains("Out of scope"));
+        assert!(!md.contains("## Constraints"));
+        assert!(!md.contains("## Open questions"));
+        assert!(!md.contains("(none)"));
+    }
+
+    fn clarifying_json() -> String {
+        serde_json::json!({
+            "type": "clarifying_questions",
+            "turn": 1,
+            "max_questions": 3,
+            "questions": [
+                { "id": "q1", "text": "Which chain?", "required": true }
+            ],
+            "checklist": [
+                { "field": "title", "status": "missing" }
+            ],
+            "next_action": "await_user"
+        })
+        .to_string()
+    }
+
+    fn question_options(options: serde_json::Value) -> Option<Vec<String>> {
+        let question: ClarifyingQuestion = serde_json::from_value(serde_json::json!({
+            "id": "q1",
+            "text": "Pick one",
+            "required": false,
+            "options": options
+        }))
+        .expect("question should deserialize");
+        question.options
+    }
+
+    #[test]
+    fn options_accept_json_array() {
+        assert_eq!(
+            question_options(serde_json::json!(["a", "b"])),
+            Some(vec!["a".to_string(), "b".to_string()])
+        );
+    }
+
+    #[test]
+    fn options_accept_comma_separated_string() {
+        assert_eq!(
+            question_options(serde_json::json!("a, b ,c,")),
+            Some(vec!["a".to_string(), "b".to_string(), "c".to_string()])
+        );
+    }
+
+    #[test]
+    fn options_accept_null_and_missing() {
+        assert_eq!(question_options(serde_json::Value::Null), None);
+
+        let question: ClarifyingQuestion =
+            serde_json::from_str(r#"{"id": "q1", "text": "t", "required": true}"#).unwrap();
+        assert_eq!(question.options, None);
+    }
+
+    fn temp_path(name: &str) -> std::path::PathBuf {
+        std::env::temp_dir().join(format!("deepseek_json_{}_{}", std::process::id(), name))
+    }
+
+    #[test]
+    fn session_round_trips_through_file() {
+        let path = temp_path("session_round_trip.json");
+        let session = TaskFinisherSession {
+            round: 2,
+            history: vec![ChatMessage {
+                role: "assistant".to_string(),
+                content: clarifying_json(),
+            }],
+        };
+        session.save(&path).unwrap();
+
+        let loaded = TaskFinisherSession::load(&path)
+            .unwrap()
+            .expect("session exists");
+        std::fs::remove_file(&path).ok();
+        assert_eq!(loaded.round, 2);
+        assert_eq!(loaded.history.len(), 1);
+        assert_eq!(loaded.history[0].content, clarifying_json());
+    }
+
+    #[test]
+    fn finished_session_exposes_artifact_and_opening_messages() {
+        let message = |role: &str, content: String| ChatMessage {
+            role: role.to_string(),
+            content,
+        };
+        let history = vec![
+            message("system", build_system_prompt(3, END_TOKEN)),
+            message("user", "Build a price cache".to_string()),
+            message("assistant", clarifying_json()),
+        ];
+        let mut session = TaskFinisherSession { round: 1, history };
+        assert!(session.final_artifact().is_none());
+        assert_eq!(session.opening_messages().len(), 2);
+
+        session.history.push(message(
+            "assistant",
+            serde_json::to_string(&sample_artifact()).unwrap(),
+        ));
+        assert_eq!(
+            session.final_artifact().as_deref(),
+            Some(&sample_artifact())
+        );
+        assert_eq!(session.opening_messages().len(), 2);
+    }
+
+    #[test]
+    fn diff_artifacts_reports_field_level_changes() {
+        let old = sample_artifact();
+        assert!(diff_artifacts(&old, &old).is_empty());
+
+        let mut new = sample_artifact();
+        new.title = "Price cache".to_string();
+        new.scope.in_scope.push("Price history".to_string());
+        new.requirements.functional[0].statement = "Fetch and cache prices".to_string();
+        new.risks.clear();
+
+        assert_eq!(
+            diff_artifacts(&old, &new).changes,
+            vec![
+                ArtifactChange::Changed {
+                    field: "title".to_string(),
+                    old: "Price cache service".to_string(),
+                    new: "Price cache".to_string(),
+                },
+                ArtifactChange::Added {
+                    field: "scope.in_scope".to_string(),
+                    item: "Price history".to_string(),
+                },
+                ArtifactChange::Changed {
+                    field: "requirements.functional".to_string(),
+                    old: "FR1: Fetch prices".to_string(),
+                    new: "FR1: Fetch and cache prices".to_string(),
+                },
+                ArtifactChange::Removed {
+                    field: "risks".to_string(),
+                    item: "R1: API outage (mitigation: Serve stale)".to_string(),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn session_load_handles_missing_and_corrupt_files() {
+        let missing = temp_path("session_missing.json");
+        assert!(TaskFinisherSession::load(&missing).unwrap().is_none());
+
+        let corrupt = temp_path("session_corrupt.json");
+        std::fs::write(&corrupt, "{ not json").unwrap();
+        let err = TaskFinisherSession::load(&corrupt).unwrap_err();
+        std::fs::remove_file(&corrupt).ok();
+        assert!(err.to_string().contains("Corrupt session file"));
+    }
+
+    #[test]
+    fn lenient_parse_accepts_clean_json_without_warnings() {
+        let (result, warnings) =
+            parse_taskfinisher_response_lenient(&clarifying_json()).expect("should parse");
+        assert!(matches!(result, TaskFinisherResult::Clarifying(..)));
+        assert!(warnings.is_empty());
+    }
+
+    #[test]
+    fn lenient_parse_recovers_fenced_json_with_bom() {
+        let raw = format!("\u{feff}```json\n{}\n```", clarifying_json());
+        let (result, warnings) = parse_taskfinisher_response_lenient(&raw).expect("should parse");
+        assert!(matches!(result, TaskFinisherResult::Clarifying(..)));
+        assert_eq!(
+            warnings,
+            vec![ParseWarning::StrippedBom, ParseWarning::StrippedCodeFences]
+        );
+    }
+
+    #[test]
+    fn lenient_parse_extracts_object_from_prose() {
+        let prefix = "Here are my questions: ";
+        let raw = format!("{}{} Let me know!", prefix, clarifying_json());
+        let (_, warnings) = parse_taskfinisher_response_lenient(&raw).expect("should parse");
+        assert_eq!(
+            warnings,
+            vec![ParseWarning::ExtractedObject {
+                start: prefix.len(),
+                end: prefix.len() + clarifying_json().len(),
+            }]
+        );
+    }
+
+    #[test]
+    fn lenient_parse_reports_failing_path() {
+        let raw = clarifying_json().replace("\"required\":true", "\"required\":\"yes\"");
+        let diagnostics = parse_taskfinisher_response_lenient(&raw).unwrap_err();
+        assert_eq!(diagnostics.path.as_deref(), Some("questions[0].required"));
+        let offset = diagnostics.offset.expect("offset should be known");
+        assert!(raw[..offset].contains("\"required\":"));
+    }
+
+    #[test]
+    fn lenient_parse_reports_syntax_error_offset() {
+        let raw = "{\"type\": \"artifact\",, }";
+        let diagnostics = parse_taskfinisher_response_lenient(raw).unwrap_err();
+        assert_eq!(diagnostics.offset, Some(20));
+        assert!(diagnostics.path.is_none());
+    }
+
+    #[test]
+    fn lenient_parse_rejects_unknown_type() {
+        let diagnostics = parse_taskfinisher_response_lenient(r#"{"type": "poem"}"#).unwrap_err();
+        assert_eq!(diagnostics.path.as_deref(), Some("type"));
+        assert!(diagnostics.message.contains("Unsupported 'type': poem"));
+    }
+
+    #[test]
+    fn checklist_completion_weights_partial_as_half() {
+        let item = |status: &str| ChecklistItem {
+            field: "f".to_string(),
+            status: status.to_string(),
+        };
+        let checklist = vec![
+            item("complete"),
+            item("partial"),
+            item("missing"),
+            item("Complete"),
+        ];
+        assert_eq!(checklist_completion(&checklist), 62.5);
+        assert_eq!(checklist_completion(&[]), 0.0);
+
+        let incomplete: Vec<&str> = incomplete_fields(&checklist)
+            .iter()
+            .map(|item| item.status.as_str())
+            .collect();
+        assert_eq!(incomplete, ["partial", "missing"]);
+
+        let line = serde_json::to_string(&ChecklistProgress::new(2, &checklist)).unwrap();
+        assert!(line.starts_with(r#"{"round":2,"completion_percent":62.5,"checklist":[{"field""#));
+    }
+
+    #[test]
+    fn strict_parser_accepts_bom_prefixed_json() {
+        let raw = format!("\u{feff}{}", clarifying_json());
+        assert!(matches!(
+            parse_taskfinisher_response(&raw),
+            Ok(TaskFinisherResult::Clarifying(..))
+        ));
+    }
+
+    #[test]
+    fn strict_parser_accepts_fenced_json() {
+        let raw = format!("```json\n{}\n```\n", clarifying_json());
+        assert!(matches!(
+            parse_taskfinisher_response(&raw),
+            Ok(TaskFinisherResult::Clarifying(..))
+        ));
+    }
+
+    #[test]
+    fn strict_parser_falls_back_to_json_inside_prose() {
+        let raw = format!(
+            "I have a few questions first.\n{}\nThanks!",
+            clarifying_json()
+        );
+        assert!(matches!(
+            parse_taskfinisher_response(&raw),
+            Ok(TaskFinisherResult::Clarifying(..))
+        ));
+    }
+
+    #[test]
+    fn artifact_json_schema_requires_the_prompt_fields() {
+        let schema = artifact_json_schema();
+        let required: Vec<&str> = schema["required"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .filter_map(|field| field.as_str())
+            .collect();
+        for field in [
+            "type",
+            "artifact_name",
+            "version",
+            "title",
+            "summary",
+            "stakeholders",
+            "scope",
+            "requirements",
+            "data_integrations",
+            "constraints",
+            "assumptions",
+            "risks",
+            "milestones",
+            "acceptance_criteria",
+            "open_questions",
+            "status",
+            "end_token",
+        ] {
+            assert!(required.contains(&field), "{} is not required", field);
+        }
+        assert_eq!(schema["properties"]["type"]["const"], "artifact");
+        assert_eq!(schema["properties"]["status"]["const"], "final");
+    }
+
+    #[test]
+    fn artifact_completeness_counts_filled_sections() {
+        let mut artifact = sample_artifact();
+        let full = artifact_completeness(&artifact);
+        artifact.constraints.clear();
+        artifact.assumptions.clear();
+        assert!(artifact_completeness(&artifact) < full);
+        assert!(full <= 100.0);
+    }
+}
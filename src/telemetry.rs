@@ -0,0 +1,84 @@
+use anyhow::{Context, Result};
+use opentelemetry::KeyValue;
+use opentelemetry::trace::TracerProvider as _;
+use opentelemetry_otlp::{SpanExporter, WithExportConfig};
+use opentelemetry_sdk::Resource;
+use opentelemetry_sdk::trace::SdkTracerProvider;
+use tracing::Subscriber;
+use tracing_subscriber::registry::LookupSpan;
+use tracing_subscriber::{EnvFilter, Layer};
+
+use crate::deepseek::REQUEST_LOG_TARGET;
+
+/// Path of the OTLP/HTTP traces endpoint, appended when the URL names only the collector
+const TRACES_PATH: &str = "/v1/traces";
+
+/// Exports spans to an OpenTelemetry collector; pending spans are flushed when dropped
+pub struct OtlpGuard {
+    provider: SdkTracerProvider,
+}
+
+impl Drop for OtlpGuard {
+    fn drop(&mut self) {
+        if let Err(e) = self.provider.shutdown() {
+            eprintln!("Failed to flush OpenTelemetry spans: {}", e);
+        }
+    }
+}
+
+/// Build a tracing layer that exports the API call spans to the OTLP/HTTP collector at
+/// `endpoint`, e.g. `http://localhost:4318`
+pub fn otlp_layer<S>(endpoint: &str) -> Result<(impl Layer<S>, OtlpGuard)>
+where
+    S: Subscriber + for<'span> LookupSpan<'span>,
+{
+    let exporter = SpanExporter::builder()
+        .with_http()
+        .with_endpoint(traces_url(endpoint))
+        .build()
+        .with_context(|| format!("Failed to create the OTLP exporter for {}", endpoint))?;
+
+    let resource = Resource::builder()
+        .with_service_name(env!("CARGO_PKG_NAME"))
+        .with_attribute(KeyValue::new("service.version", env!("CARGO_PKG_VERSION")))
+        .build();
+    let provider = SdkTracerProvider::builder()
+        .with_batch_exporter(exporter)
+        .with_resource(resource)
+        .build();
+
+    let layer = tracing_opentelemetry::layer()
+        .with_tracer(provider.tracer(env!("CARGO_PKG_NAME")))
+        .with_filter(EnvFilter::new(format!("{}=info", REQUEST_LOG_TARGET)));
+    Ok((layer, OtlpGuard { provider }))
+}
+
+fn traces_url(endpoint: &str) -> String {
+    let endpoint = endpoint.trim_end_matches('/');
+    if endpoint.ends_with(TRACES_PATH) {
+        endpoint.to_string()
+    } else {
+        format!("{}{}", endpoint, TRACES_PATH)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn traces_url_appends_the_traces_path_once() {
+        assert_eq!(
+            traces_url("http://localhost:4318"),
+            "http://localhost:4318/v1/traces"
+        );
+        assert_eq!(
+            traces_url("http://localhost:4318/"),
+            "http://localhost:4318/v1/traces"
+        );
+        assert_eq!(
+            traces_url("http://collector/v1/traces"),
+            "http://collector/v1/traces"
+        );
+    }
+}
@@ -0,0 +1,193 @@
+use futures::future::BoxFuture;
+use reqwest::{Client, RequestBuilder, StatusCode};
+
+use crate::config::{API_KEY_HEADER, API_KEY_QUERY_PARAM, AuthStyle, Config};
+use crate::deepseek::{ChatRequest, DeepSeekError, api_error, parse_retry_after};
+
+/// Delivers chat requests to the API and returns the raw response body.
+///
+/// This is the only part of [`DeepSeekClient`](crate::DeepSeekClient) that talks HTTP; the
+/// retry, parsing and accounting logic sits on top of it, so tests can swap in a transport
+/// that returns canned replies.
+pub trait ChatTransport: Send + Sync {
+    /// Send `request` and return the response body of a successful reply. Failures,
+    /// including non-success statuses, are reported as [`DeepSeekError`]s.
+    fn post<'a>(&'a self, request: &'a ChatRequest)
+    -> BoxFuture<'a, Result<String, DeepSeekError>>;
+
+    /// Fetch the body of the model list (`GET {base_url}/models`). Transports that cannot
+    /// list models report a [`DeepSeekError::ConfigError`].
+    fn get_models(&self) -> BoxFuture<'_, Result<String, DeepSeekError>> {
+        Box::pin(async {
+            Err(DeepSeekError::ConfigError {
+                message: "This transport cannot list models".to_string(),
+            })
+        })
+    }
+}
+
+/// Path of the model list under the base URL
+const MODELS_PATH: &str = "/models";
+
+/// The default transport: a JSON POST to the chat completions endpoint over `reqwest`
+pub struct HttpTransport {
+    client: Client,
+    url: String,
+    config: Config,
+}
+
+impl HttpTransport {
+    /// Transport sending to `url` with a prepared client; `config` supplies the API key and
+    /// the timeout and retry settings used to classify failures
+    pub fn new(client: Client, url: String, config: Config) -> Self {
+        Self {
+            client,
+            url,
+            config,
+        }
+    }
+
+    async fn post_once(&self, request: &ChatRequest) -> Result<String, DeepSeekError> {
+        let request = self
+            .client
+            .post(&self.url)
+            .header("Content-Type", "application/json")
+            .json(request);
+        self.send(request).await
+    }
+
+    async fn get_models_once(&self) -> Result<String, DeepSeekError> {
+        let url = format!(
+            "{}{}",
+            self.config.base_url.trim_end_matches('/'),
+            MODELS_PATH
+        );
+        self.send(self.client.get(url)).await
+    }
+
+    /// Authorize and send `request`, returning the body of a successful response
+    async fn send(&self, request: RequestBuilder) -> Result<String, DeepSeekError> {
+        let response = authorize(request, &self.config)
+            .send()
+            .await
+            .map_err(|e| self.map_reqwest_error(e))?;
+
+        // Handle HTTP status codes
+        let status = response.status();
+        tracing::Span::current().record("http.status", status.as_u16());
+        if !status.is_success() {
+            return Err(self.handle_error_response(status, response).await);
+        }
+
+        response.text().await.map_err(|e| self.map_reqwest_error(e))
+    }
+
+    /// Map reqwest errors to our custom error types
+    fn map_reqwest_error(&self, error: reqwest::Error) -> DeepSeekError {
+        // The URL carries the API key as a query parameter; keep it out of error messages
+        let error = match self.config.auth_style {
+            AuthStyle::QueryParam => error.without_url(),
+            _ => error,
+        };
+
+        // Checked first: a connect timeout is a network problem, not a slow response
+        if error.is_connect() {
+            let message = match self.config.connect_timeout {
+                Some(seconds) if error.is_timeout() => {
+                    format!("Failed to connect to server within {}s", seconds)
+                }
+                _ => "Failed to connect to server".to_string(),
+            };
+            return DeepSeekError::NetworkError { message };
+        }
+
+        if error.is_timeout() {
+            return DeepSeekError::Timeout {
+                seconds: self.config.timeout,
+            };
+        }
+
+        if error.is_request() {
+            return DeepSeekError::NetworkError {
+                message: "Request failed".to_string(),
+            };
+        }
+
+        // Check for specific network-related errors
+        let error_msg = error.to_string().to_lowercase();
+        if error_msg.contains("dns") {
+            return DeepSeekError::NetworkError {
+                message: "DNS resolution failed".to_string(),
+            };
+        }
+
+        if error_msg.contains("connection refused") {
+            return DeepSeekError::NetworkError {
+                message: "Connection refused by server".to_string(),
+            };
+        }
+
+        if error_msg.contains("network") || error_msg.contains("connection") {
+            return DeepSeekError::NetworkError {
+                message: error.to_string(),
+            };
+        }
+
+        DeepSeekError::NetworkError {
+            message: format!("Request error: {}", error),
+        }
+    }
+
+    /// Handle error responses from the server
+    async fn handle_error_response(
+        &self,
+        status: StatusCode,
+        response: reqwest::Response,
+    ) -> DeepSeekError {
+        let retry_after = response
+            .headers()
+            .get(reqwest::header::RETRY_AFTER)
+            .and_then(|value| value.to_str().ok())
+            .and_then(parse_retry_after);
+
+        let error_text = response
+            .text()
+            .await
+            .unwrap_or_else(|_| "Unknown error".to_string());
+
+        match status {
+            StatusCode::TOO_MANY_REQUESTS => DeepSeekError::ServerBusy { retry_after },
+            StatusCode::SERVICE_UNAVAILABLE => DeepSeekError::ServerBusy { retry_after },
+            StatusCode::BAD_GATEWAY | StatusCode::GATEWAY_TIMEOUT => {
+                DeepSeekError::ServerBusy { retry_after }
+            }
+            // DeepSeek returns intermittent 500s that usually succeed when retried
+            StatusCode::INTERNAL_SERVER_ERROR if self.config.retry_on_500 => {
+                DeepSeekError::ServerBusy { retry_after }
+            }
+            _ => api_error(status.as_u16(), &error_text),
+        }
+    }
+}
+
+/// Attach the API key to `request` the way `config.auth_style` asks for
+pub(crate) fn authorize(request: RequestBuilder, config: &Config) -> RequestBuilder {
+    match config.auth_style {
+        AuthStyle::Bearer => request.header("Authorization", format!("Bearer {}", config.api_key)),
+        AuthStyle::ApiKeyHeader => request.header(API_KEY_HEADER, &config.api_key),
+        AuthStyle::QueryParam => request.query(&[(API_KEY_QUERY_PARAM, &config.api_key)]),
+    }
+}
+
+impl ChatTransport for HttpTransport {
+    fn post<'a>(
+        &'a self,
+        request: &'a ChatRequest,
+    ) -> BoxFuture<'a, Result<String, DeepSeekError>> {
+        Box::pin(self.post_once(request))
+    }
+
+    fn get_models(&self) -> BoxFuture<'_, Result<String, DeepSeekError>> {
+        Box::pin(self.get_models_once())
+    }
+}